@@ -0,0 +1,34 @@
+#![feature(plugin, start)]
+#![no_std]
+#![plugin(macro_zinc)]
+
+extern crate zinc;
+
+use core::option::Option::Some;
+
+use zinc::hal::lpc11xx::{pin, timer};
+use zinc::hal::pin::Gpio;
+use zinc::hal::pin::GpioDirection;
+use zinc::hal::timer::Timer;
+
+#[zinc_main]
+pub fn main() {
+  zinc::hal::mem_init::init_stack();
+  zinc::hal::mem_init::init_data();
+
+  // P0.7 => LED2 (LPCXpresso LPC1114)
+  let led = pin::Pin::new(
+    pin::Port::Port0, 7,
+    pin::Function::Gpio,
+    Some(GpioDirection::Out));
+
+  // 12MHz IRC / 12 => 1MHz timer
+  let timer = timer::Timer::new(timer::TimerPeripheral::Timer32_0, 12);
+
+  loop  {
+    led.set_high();
+    timer.wait_ms(10);
+    led.set_low();
+    timer.wait_ms(10);
+  }
+}
@@ -49,7 +49,7 @@ pub fn main() {
     let timer = timer::Timer::new(timer::TimerPeripheral::Timer2, 16u32);
 
     loop {
-        let val = i2c.read();
+        let val = i2c.slave_read();
         if val == 0 {
             continue;
         }
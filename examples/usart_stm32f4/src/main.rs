@@ -0,0 +1,44 @@
+#![feature(plugin, start)]
+#![no_std]
+#![plugin(macro_zinc)]
+
+extern crate zinc;
+
+#[zinc_main]
+pub fn main() {
+  use zinc::drivers::chario::CharIO;
+  use zinc::hal;
+  use zinc::hal::pin::Gpio;
+  use zinc::hal::stm32f4::{init, pin, usart};
+
+  zinc::hal::mem_init::init_stack();
+  zinc::hal::mem_init::init_data();
+
+  let sys = init::SysConf {
+    clock: init::ClockConf {
+      source: init::SystemClockSource::SystemClockHSI,
+    },
+  };
+  sys.setup();
+
+  // PA2 => USART2 TX (AF7)
+  let pin_tx = pin::AlternateFunctionPin::new(2, pin::Port::PortA, 7);
+  pin_tx.setup();
+
+  let led1 = pin::Pin {
+    port: pin::Port::PortD,
+    pin: 12u8,
+    function: pin::Function::GPIOOut
+  };
+  led1.setup();
+
+  led1.set_low();
+
+  let uart = usart::Usart::new(usart::UsartPeripheral::Usart2, 38400, usart::WordLen::WordLen8bits,
+                               hal::uart::Parity::Disabled, usart::StopBit::StopBit1bit);
+  uart.puts("Hello, world\n");
+
+  led1.set_high();
+
+  loop {}
+}
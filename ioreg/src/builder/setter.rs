@@ -141,8 +141,9 @@ fn build_drop(cx: &ExtCtxt, path: &Vec<String>,
     for f in fields.iter() {
         match f.access {
             node::Access::SetToClear => {
-                let mask = 1 << (f.count.node * f.width) - 1;
-                clear |= mask;
+                let bits = f.count.node as u64 * f.width as u64;
+                let mask = ((1u64 << bits) - 1) << f.low_bit;
+                clear |= mask as u32;
             }
             _ => {}
         }
@@ -82,6 +82,20 @@ mod test {
     assert_eq!(get_value(&test, 0), 1<<25);
   }
 
+  #[test]
+  fn set_to_clear_fields_are_not_written_back() {
+    let test: BASIC_TEST = zeroed_safe();
+
+    test.reg1.set_field1(true);
+    assert_eq!(get_value(&test, 0), 1);
+    unsafe {
+      let ptr: *mut u32 = transmute(&test);
+      *ptr |= 1<<25;
+    }
+    test.reg1.set_field3(0xde);
+    assert_eq!(get_value(&test, 0), 0xde<<16 | 1);
+  }
+
   #[test]
   fn no_read_writeonly_registers() {
     let test: BASIC_TEST = zeroed_safe();
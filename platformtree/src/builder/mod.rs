@@ -134,6 +134,10 @@ impl Builder {
     self.main_stmts.clone()
   }
 
+  pub fn type_items(&self) -> Vec<P<ast::Item>> {
+    self.type_items.clone()
+  }
+
   pub fn pt(&self) -> Rc<node::PlatformTree> {
     self.pt.clone()
   }
@@ -37,6 +37,11 @@ pub fn attach(builder: &mut Builder, _: &mut ExtCtxt, node: Rc<node::Node>) {
     add_node_dependency(&node, &task_node);
     add_node_dependency(&task_node, &mcu_node);
 
+    match mcu_node.get_by_path("watchdog") {
+      Some(watchdog_node) => add_node_dependency(&task_node, &watchdog_node),
+      None => (),
+    }
+
     let maybe_args_node = task_node.get_by_path("args");
     if maybe_args_node.is_some() {
       let args_node = maybe_args_node.unwrap();
@@ -79,13 +84,30 @@ fn build_single_task(builder: &mut Builder, cx: &mut ExtCtxt,
           node.get_attr("loop").value_span,
           cx.ident_of(loop_fn.as_str()),
           args);
-      let loop_stmt = quote_stmt!(&*cx, loop { $call_expr; } ).unwrap();
+      let loop_stmt = match watchdog_name(builder) {
+        Some(watchdog) => quote_stmt!(&*cx,
+          loop {
+            $call_expr;
+            zinc::hal::watchdog::Watchdog::feed(&$watchdog);
+          }
+        ).unwrap(),
+        None => quote_stmt!(&*cx, loop { $call_expr; } ).unwrap(),
+      };
       builder.add_main_statement(loop_stmt);
     },
     None => (),
   }
 }
 
+/// Name of the watchdog declared by the `mcu` node, which the task loop
+/// feeds on every iteration.
+fn watchdog_name(builder: &Builder) -> Option<TokenString> {
+  builder.pt.get_by_path("mcu")
+    .and_then(|mcu| mcu.get_by_path("watchdog"))
+    .map(|watchdog| TokenString(
+        watchdog.name.clone().unwrap_or("watchdog".to_string())))
+}
+
 fn build_args(builder: &mut Builder, cx: &mut ExtCtxt,
     struct_name: &String, node: Rc<node::Node>) -> P<ast::Expr> {
   let mut fields = vec!();
@@ -240,6 +262,31 @@ mod test {
     });
   }
 
+  #[test]
+  fn builds_single_task_os_loop_feeding_watchdog() {
+    with_parsed("
+      lpc17xx@mcu {
+        watchdog {
+          timeout_ms = 500;
+        }
+      }
+
+      single_task {
+        loop = \"run\";
+      }", |cx, failed, pt| {
+      let mut builder = Builder::new(pt.clone(), cx);
+      build_single_task(&mut builder, cx, pt.get_by_path("single_task").unwrap().clone());
+      assert!(unsafe{*failed} == false);
+      assert!(builder.main_stmts.len() == 1);
+
+      assert_equal_source(&builder.main_stmts[0],
+          "loop {
+            run();
+            zinc::hal::watchdog::Watchdog::feed(&watchdog);
+          }");
+    });
+  }
+
   #[test]
   fn builds_single_task_with_args() {
     with_parsed("
@@ -13,13 +13,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Generic char output trait.
+//! Generic char output and input traits.
 
 use core::slice::SliceExt;
 use core::convert::AsRef;
+use core::option::Option::{self, Some, None};
 
 use core::mem::zeroed;
 
+use hal::timer::Timer;
 use util::strconv;
 
 /// CharIO provides interface for outputting characters.
@@ -64,11 +66,66 @@ pub trait CharIO {
   }
 }
 
+/// CharInput provides interface for receiving characters.
+///
+/// This trait implements blocking, timed and line-based reads, requiring only
+/// one method: `try_getc`.
+pub trait CharInput {
+  /// Returns a received character, or `None` if nothing is pending.
+  fn try_getc(&self) -> Option<char>;
+
+  /// Waits for a character and returns it.
+  fn getc(&self) -> char {
+    loop {
+      match self.try_getc() {
+        Some(c) => return c,
+        None => {},
+      }
+    }
+  }
+
+  /// Waits for a character for at most `timeout_us` microseconds, as measured
+  /// by `timer`.
+  fn getc_timeout(&self, timer: &Timer, timeout_us: u32) -> Option<char> {
+    let start = timer.get_counter();
+    loop {
+      match self.try_getc() {
+        Some(c) => return Some(c),
+        None => {},
+      }
+      if timer.get_counter().wrapping_sub(start) >= timeout_us {
+        return None;
+      }
+    }
+  }
+
+  /// Reads a line into `buf`, returning the number of bytes stored.
+  ///
+  /// Both `\r` and `\n` terminate a line and are not stored. Empty lines are
+  /// skipped, so `\r\n` line endings work as well. Reading stops early if
+  /// `buf` is full.
+  fn read_line(&self, buf: &mut [u8]) -> usize {
+    let mut len = 0;
+    while len < buf.len() {
+      match self.getc() {
+        '\r' | '\n' => if len > 0 { break },
+        c => {
+          buf[len] = c as u8;
+          len += 1;
+        },
+      }
+    }
+    len
+  }
+}
+
 #[cfg(test)]
 pub mod test {
-  use core::cell::RefCell;
+  use core::cell::{Cell, RefCell};
+  use core::option::Option::{self, Some, None};
 
-  use drivers::chario::CharIO;
+  use drivers::chario::{CharIO, CharInput};
+  use hal::timer::Timer;
 
   #[derive(Clone, Copy)]
   pub struct TestCharIOData {
@@ -109,6 +166,45 @@ pub mod test {
     }
   }
 
+  pub struct TestCharInput {
+    input: &'static [u8],
+    pos: Cell<usize>,
+  }
+
+  impl CharInput for TestCharInput {
+    fn try_getc(&self) -> Option<char> {
+      let pos = self.pos.get();
+      if pos < self.input.len() {
+        self.pos.set(pos + 1);
+        Some(self.input[pos] as char)
+      } else {
+        None
+      }
+    }
+  }
+
+  impl TestCharInput {
+    pub fn new(input: &'static [u8]) -> TestCharInput {
+      TestCharInput {
+        input: input,
+        pos: Cell::new(0),
+      }
+    }
+  }
+
+  /// A timer that advances by 10us every time it is read.
+  struct TestTimer {
+    counter: Cell<u32>,
+  }
+
+  impl Timer for TestTimer {
+    fn get_counter(&self) -> u32 {
+      let current = self.counter.get();
+      self.counter.set(current.wrapping_add(10));
+      current
+    }
+  }
+
   #[test]
   fn putc_should_store_a_char() {
     let io = TestCharIO::new();
@@ -179,4 +275,40 @@ pub mod test {
     assert!(io.get_last_char() == '\t');
     assert!(io.get_and_reset_putc_calls() == 2);
   }
+
+  #[test]
+  fn getc_should_return_chars_in_order() {
+    let io = TestCharInput::new(b"ab");
+    assert!(io.getc() == 'a');
+    assert!(io.getc() == 'b');
+    assert!(io.try_getc() == None);
+  }
+
+  #[test]
+  fn getc_timeout_should_give_up_when_no_input() {
+    let io = TestCharInput::new(b"x");
+    let timer = TestTimer { counter: Cell::new(0xffff_fff0) };
+    assert!(io.getc_timeout(&timer, 100) == Some('x'));
+    assert!(io.getc_timeout(&timer, 100) == None);
+    // the counter has wrapped around by now
+    assert!(timer.get_counter() < 0x100);
+  }
+
+  #[test]
+  fn read_line_should_stop_at_line_end() {
+    let io = TestCharInput::new(b"led on\r\nled off\n");
+    let mut buf = [0u8; 16];
+    let len = io.read_line(&mut buf);
+    assert!(&buf[..len] == b"led on");
+    let len = io.read_line(&mut buf);
+    assert!(&buf[..len] == b"led off");
+  }
+
+  #[test]
+  fn read_line_should_stop_when_buffer_is_full() {
+    let io = TestCharInput::new(b"abcdef\n");
+    let mut buf = [0u8; 4];
+    assert!(io.read_line(&mut buf) == 4);
+    assert!(&buf == b"abcd");
+  }
 }
@@ -0,0 +1,124 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+Analog to digital converter interface.
+
+`Adc` covers single conversions, scanning a list of channels and averaging
+several samples per result. Converters that can keep sampling on their own
+also implement `ContinuousAdc`, which reports each result from the conversion
+complete interrupt.
+*/
+
+/// Called from the ADC interrupt with the channel and raw value of each
+/// completed conversion.
+pub type ConversionHandler = fn(channel: u8, value: u16);
+
+/// Number of samples averaged into a single result.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Averaging {
+  /// Each result is a single sample.
+  NoAveraging = 0,
+  /// Average of 2 samples.
+  Samples2 = 1,
+  /// Average of 4 samples.
+  Samples4 = 2,
+  /// Average of 8 samples.
+  Samples8 = 3,
+  /// Average of 16 samples.
+  Samples16 = 4,
+}
+
+impl Averaging {
+  /// Returns the number of samples averaged.
+  pub fn samples(self) -> u32 {
+    1 << (self as u32)
+  }
+}
+
+/// Converts a raw result into millivolts.
+pub fn raw_to_millivolts(raw: u16, resolution: u8, reference_mv: u32) -> u32 {
+  let full_scale = (1u32 << resolution as usize) - 1;
+  (raw as u32 * reference_mv + full_scale / 2) / full_scale
+}
+
+/// Analog to digital converter.
+pub trait Adc {
+  /// Returns the number of input channels.
+  fn channel_count(&self) -> usize;
+
+  /// Returns the resolution of the results, in bits.
+  fn resolution(&self) -> u8;
+
+  /// Returns the reference voltage in millivolts, which is what the full
+  /// scale result corresponds to.
+  fn reference_mv(&self) -> u32;
+
+  /// Sets the number of samples averaged for each result.
+  fn set_averaging(&self, averaging: Averaging);
+
+  /// Converts a single channel and returns the raw result.
+  fn read_channel(&self, channel: u8) -> u16;
+
+  /// Converts each of `channels` in turn, storing the results at the same
+  /// index of `results`.
+  fn scan(&self, channels: &[u8], results: &mut [u16]) {
+    for (channel, result) in channels.iter().zip(results.iter_mut()) {
+      *result = self.read_channel(*channel);
+    }
+  }
+
+  /// Converts a single channel and returns the result in millivolts.
+  fn read_millivolts(&self, channel: u8) -> u32 {
+    raw_to_millivolts(self.read_channel(channel), self.resolution(),
+        self.reference_mv())
+  }
+}
+
+/// Converter that can sample a set of channels continuously.
+pub trait ContinuousAdc: Adc {
+  /// Starts converting `channels` in a loop, `handler` is called from the
+  /// interrupt with each result.
+  ///
+  /// The drivers don't define the ADC interrupt handler themselves, the
+  /// application has to call the driver's `handle_interrupt()` from it.
+  fn start_continuous(&self, channels: &[u8], handler: ConversionHandler);
+
+  /// Stops the conversions started by `start_continuous`.
+  fn stop_continuous(&self);
+}
+
+#[cfg(test)]
+mod test {
+  use super::{Averaging, raw_to_millivolts};
+
+  #[test]
+  fn converts_full_scale_to_reference() {
+    assert_eq!(raw_to_millivolts(4095, 12, 3300), 3300);
+    assert_eq!(raw_to_millivolts(0, 12, 3300), 0);
+    assert_eq!(raw_to_millivolts(1023, 10, 1800), 1800);
+  }
+
+  #[test]
+  fn converts_mid_scale() {
+    assert_eq!(raw_to_millivolts(2048, 12, 3300), 1650);
+  }
+
+  #[test]
+  fn counts_averaged_samples() {
+    assert_eq!(Averaging::NoAveraging.samples(), 1);
+    assert_eq!(Averaging::Samples16.samples(), 16);
+  }
+}
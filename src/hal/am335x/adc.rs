@@ -8,9 +8,25 @@ mod ioreg;
 #[macro_use]
 mod wait_for;
 
+use core::intrinsics::abort;
+
+use hal::adc::{Adc, Averaging};
 use hal::am335x::wakeup_clock;
 
+/// Number of analog inputs, AIN0 to AIN7.
+const CHANNEL_COUNT: usize = 8;
+
+/// Number of sequencer steps.
+const STEP_COUNT: usize = 16;
+
+/// Voltage of the internal reference, in millivolts.
+const REFERENCE_MV: u32 = 1800;
+
 /// ADC module
+///
+/// Averaging is done by the step sequencer, which also runs scans: each
+/// channel gets its own step and results are read back from FIFO 0 in step
+/// order. Conversion interrupts are not supported.
 #[derive(Clone, Copy)]
 #[allow(non_camel_case)]
 pub struct ADC_TSC {
@@ -23,22 +39,72 @@ impl ADC_TSC {
     pub fn new() -> ADC_TSC {
         wakeup_clock::WakeUpClock::enable(wakeup_clock::WakeUpClocks::ADC_TSC);
 
+        let reg = &self::reg::ADC_TSC;
+        reg.ctrl.set_step_config_write_protect(true);
+
         ADC_TSC {
-            reg: &self::reg::ADC_TSC
+            reg: reg
         }
     }
 
     /// Read analog input
     pub fn read_input(&self, input: u8) -> u16 {
-        self.reg.step[0].ctrl
-            .set_input_select(input as u32)
-            .set_mode(self::reg::ADC_TSC_step_ctrl_mode::SW_ONE_SHOT);
-        self.reg.step_enable.set_step_enable(0, true);
+        self.read_channel(input)
+    }
+
+    fn drain_fifo(&self) {
+        while self.reg.fifo[0].count.words() > 0 {
+            self.reg.fifo_data[0].adc_data();
+        }
+    }
+}
+
+impl Adc for ADC_TSC {
+    fn channel_count(&self) -> usize {
+        CHANNEL_COUNT
+    }
+
+    fn resolution(&self) -> u8 {
+        12
+    }
+
+    fn reference_mv(&self) -> u32 {
+        REFERENCE_MV
+    }
+
+    fn set_averaging(&self, averaging: Averaging) {
+        for step in 0..STEP_COUNT {
+            self.reg.step[step].ctrl.set_samples_average(averaging as u32);
+        }
+    }
+
+    fn read_channel(&self, channel: u8) -> u16 {
+        let mut result = [0u16];
+        self.scan(&[channel], &mut result);
+        result[0]
+    }
+
+    /// Runs the channels through the step sequencer, up to 16 at once.
+    fn scan(&self, channels: &[u8], results: &mut [u16]) {
+        if channels.len() > STEP_COUNT {
+            unsafe { abort() };
+        }
+
+        self.drain_fifo();
+        for (step, channel) in channels.iter().enumerate() {
+            self.reg.step[step].ctrl
+                .set_input_select(*channel as u32)
+                .set_fifo_select(false)
+                .set_mode(self::reg::ADC_TSC_step_ctrl_mode::SW_ONE_SHOT);
+            self.reg.step_enable.set_step_enable(step, true);
+        }
         self.reg.ctrl.set_enable(true);
 
-        wait_for!(self.reg.fifo[0].count.words() > 0);
+        wait_for!(self.reg.fifo[0].count.words() as usize >= channels.len());
 
-        self.reg.fifo_data[0].adc_data() as u16
+        for result in results.iter_mut().take(channels.len()) {
+            *result = self.reg.fifo_data[0].adc_data() as u16;
+        }
     }
 }
 
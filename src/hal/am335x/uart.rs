@@ -1,9 +1,11 @@
 //! UART module
 //!
 
+use core::option::Option::{self, Some, None};
+
 use hal::am335x::pin;
 use hal::am335x::util;
-use drivers::chario::CharIO;
+use drivers::chario::{CharIO, CharInput};
 
 #[path = "../../util/ioreg.rs"]
 #[macro_use]
@@ -95,6 +97,15 @@ impl UART {
         wait_for!(self.reg.lsr_uart.tx_fifo_shift_empty());
         self.reg.hr.set(byte as u16);
     }
+
+    /// Reads byte from FIFO register, if one has been received
+    pub fn read(&self) -> Option<u8> {
+        if self.reg.lsr_uart.rx_fifo_not_empty() {
+            Some(self.reg.hr.data() as u8)
+        } else {
+            None
+        }
+    }
 }
 
 impl CharIO for UART {
@@ -103,6 +114,12 @@ impl CharIO for UART {
     }
 }
 
+impl CharInput for UART {
+    fn try_getc(&self) -> Option<char> {
+        self.read().map(|b| b as char)
+    }
+}
+
 mod reg {
     use volatile_cell::VolatileCell;
     use core::ops::Drop;
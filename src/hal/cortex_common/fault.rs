@@ -0,0 +1,284 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fault reporting for ARMv7-M cores.
+//!
+//! The default fault handler in `isr.rs` passes the exception frame stacked
+//! by the faulting code to `report_fault()`, which decodes the SCB fault
+//! status into a `FaultReport`. The report goes to a hook set with
+//! `set_fault_hook()`, or is printed through `os::debug` otherwise. The MCU
+//! then stops at a breakpoint, or resets if `set_reset_on_fault(true)` was
+//! called.
+
+use core::fmt;
+use core::option::Option::{self, Some, None};
+
+use hal::cortex_common::scb;
+use os::debug;
+
+/// Registers stacked by the core on exception entry.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[allow(missing_docs)]
+pub struct ExceptionFrame {
+  pub r0: u32,
+  pub r1: u32,
+  pub r2: u32,
+  pub r3: u32,
+  pub r12: u32,
+  pub lr: u32,
+  pub pc: u32,
+  pub xpsr: u32,
+}
+
+/// Fault status captured in a fault handler.
+#[derive(Clone, Copy, Debug)]
+pub struct FaultReport {
+  /// Exception number of the fault handler.
+  pub exception: u32,
+  /// Registers of the faulting code.
+  pub frame: ExceptionFrame,
+  /// Configurable fault status register.
+  pub cfsr: u32,
+  /// HardFault status register.
+  pub hfsr: u32,
+  /// System handler control and state register.
+  pub shcsr: u32,
+  /// Faulting data address of a MemManage fault, when valid.
+  pub mmfar: Option<u32>,
+  /// Faulting data address of a precise BusFault, when valid.
+  pub bfar: Option<u32>,
+}
+
+#[allow(non_upper_case_globals)]
+static MemManageAddressValid: u32 = 1 << 7;
+#[allow(non_upper_case_globals)]
+static BusFaultAddressValid: u32 = 1 << 15;
+
+/// HFSR bits and their meaning.
+const HFSR_CAUSES: [(u32, &'static str); 3] = [
+  (1,  "vector table read on exception processing"),
+  (30, "escalated configurable fault"),
+  (31, "debug event"),
+];
+
+/// CFSR bits and their meaning.
+const CFSR_CAUSES: [(u32, &'static str); 17] = [
+  (0,  "instruction access violation"),
+  (1,  "data access violation"),
+  (3,  "MemManage fault on unstacking"),
+  (4,  "MemManage fault on stacking"),
+  (5,  "MemManage fault on lazy FP state preservation"),
+  (8,  "instruction bus error"),
+  (9,  "precise data bus error"),
+  (10, "imprecise data bus error"),
+  (11, "BusFault on unstacking"),
+  (12, "BusFault on stacking"),
+  (13, "BusFault on lazy FP state preservation"),
+  (16, "undefined instruction"),
+  (17, "invalid state"),
+  (18, "invalid PC load by EXC_RETURN"),
+  (19, "no coprocessor"),
+  (24, "unaligned access"),
+  (25, "divide by zero"),
+];
+
+impl FaultReport {
+  /// Captures the fault status registers along with `frame`.
+  pub fn capture(frame: &ExceptionFrame) -> FaultReport {
+    let cfsr = scb::cfsr();
+    FaultReport {
+      exception: scb::active_exception(),
+      frame: *frame,
+      cfsr: cfsr,
+      hfsr: scb::hfsr(),
+      shcsr: scb::shcsr(),
+      mmfar: if cfsr & MemManageAddressValid != 0 {
+        Some(scb::mmfar())
+      } else {
+        None
+      },
+      bfar: if cfsr & BusFaultAddressValid != 0 {
+        Some(scb::bfar())
+      } else {
+        None
+      },
+    }
+  }
+
+  /// Returns the name of the fault handler.
+  pub fn name(&self) -> &'static str {
+    match self.exception {
+      2 => "NMI",
+      3 => "HardFault",
+      4 => "MemManage fault",
+      5 => "BusFault",
+      6 => "UsageFault",
+      _ => "unexpected exception",
+    }
+  }
+
+  /// Returns the causes recorded in HFSR and CFSR.
+  pub fn causes(&self) -> Causes {
+    Causes {
+      hfsr: self.hfsr,
+      cfsr: self.cfsr,
+      index: 0,
+    }
+  }
+}
+
+impl fmt::Display for FaultReport {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    try!(write!(f, "{}\n", self.name()));
+    for cause in self.causes() {
+      try!(write!(f, "  {}\n", cause));
+    }
+    if let Some(address) = self.mmfar.or(self.bfar) {
+      try!(write!(f, "  address {:#010x}\n", address));
+    }
+    let r = &self.frame;
+    try!(write!(f, "  r0  {:#010x} r1 {:#010x} r2 {:#010x} r3   {:#010x}\n",
+        r.r0, r.r1, r.r2, r.r3));
+    write!(f, "  r12 {:#010x} lr {:#010x} pc {:#010x} xpsr {:#010x}\n",
+        r.r12, r.lr, r.pc, r.xpsr)
+  }
+}
+
+/// Iterator over the descriptions of the fault causes in a report.
+pub struct Causes {
+  hfsr: u32,
+  cfsr: u32,
+  index: usize,
+}
+
+impl Iterator for Causes {
+  type Item = &'static str;
+
+  fn next(&mut self) -> Option<&'static str> {
+    while self.index < HFSR_CAUSES.len() + CFSR_CAUSES.len() {
+      let (status, (bit, cause)) = if self.index < HFSR_CAUSES.len() {
+        (self.hfsr, HFSR_CAUSES[self.index])
+      } else {
+        (self.cfsr, CFSR_CAUSES[self.index - HFSR_CAUSES.len()])
+      };
+      self.index += 1;
+      if status & (1 << bit) != 0 {
+        return Some(cause);
+      }
+    }
+    None
+  }
+}
+
+/// Function receiving the fault report.
+pub type FaultHook = fn(&FaultReport);
+
+#[allow(non_upper_case_globals)]
+static mut Hook: Option<FaultHook> = None;
+#[allow(non_upper_case_globals)]
+static mut ResetOnFault: bool = false;
+
+/// Sets the function that receives fault reports instead of `os::debug`.
+pub fn set_fault_hook(hook: FaultHook) {
+  unsafe { Hook = Some(hook) };
+}
+
+/// Selects whether to reset the MCU after reporting a fault instead of
+/// stopping at a breakpoint.
+pub fn set_reset_on_fault(reset: bool) {
+  unsafe { ResetOnFault = reset };
+}
+
+struct DebugOutput;
+
+impl fmt::Write for DebugOutput {
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    debug::print(s);
+    Ok(())
+  }
+}
+
+/// Reports a fault with the exception frame stacked by the faulting code,
+/// then halts or resets. Called by the default fault handler.
+#[no_mangle]
+pub unsafe extern fn report_fault(frame: *const ExceptionFrame) -> ! {
+  use core::fmt::Write;
+
+  let report = FaultReport::capture(&*frame);
+  match Hook {
+    Some(hook) => hook(&report),
+    None => { let _ = write!(DebugOutput, "{}", report); },
+  }
+
+  if ResetOnFault {
+    scb::system_reset();
+  }
+  halt();
+}
+
+#[cfg(target_os = "none")]
+fn halt() -> ! {
+  unsafe { ::core::intrinsics::breakpoint() };
+  loop {}
+}
+
+#[cfg(not(target_os = "none"))]
+fn halt() -> ! {
+  loop {}
+}
+
+#[cfg(test)]
+mod test {
+  use std::string::String;
+  use std::vec::Vec;
+  use core::fmt::Write;
+  use super::{ExceptionFrame, FaultReport};
+
+  fn report(exception: u32, cfsr: u32, hfsr: u32) -> FaultReport {
+    FaultReport {
+      exception: exception,
+      frame: ExceptionFrame {
+        r0: 0, r1: 1, r2: 2, r3: 3, r12: 12,
+        lr: 0x0800_0101, pc: 0x0800_0200, xpsr: 0x0100_0000,
+      },
+      cfsr: cfsr,
+      hfsr: hfsr,
+      shcsr: 0,
+      mmfar: None,
+      bfar: None,
+    }
+  }
+
+  #[test]
+  fn decodes_causes() {
+    let causes: Vec<&str> = report(3, (1 << 9) | (1 << 25), 1 << 30)
+      .causes().collect();
+    assert_eq!(causes, ["escalated configurable fault",
+        "precise data bus error", "divide by zero"]);
+  }
+
+  #[test]
+  fn formats_report() {
+    let mut bus = report(5, (1 << 9) | (1 << 15), 0);
+    bus.bfar = Some(0x2002_0000);
+    let mut out = String::new();
+    write!(out, "{}", bus).unwrap();
+
+    assert!(out.starts_with("BusFault\n  precise data bus error\n"));
+    assert!(out.contains("address 0x20020000\n"));
+    assert!(out.contains("pc 0x08000200"));
+  }
+}
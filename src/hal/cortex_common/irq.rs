@@ -43,6 +43,96 @@ impl Drop for NoInterrupts {
   }
 }
 
+unsafe impl CriticalSection for NoInterrupts {}
+
+/// Marker for tokens that protect state shared with interrupt handlers.
+///
+/// Unsafe to implement as `Shared::borrow` relies on it: while a value of the
+/// implementing type is alive, no interrupt handler that accesses state
+/// guarded by it may run.
+pub unsafe trait CriticalSection {}
+
+#[cfg(any(feature = "cpu_cortex-m3",
+          feature = "cpu_cortex-m4",
+          feature = "cpu_cortex-m7"))]
+pub use self::ceiling::PriorityCeiling;
+
+#[cfg(any(feature = "cpu_cortex-m3",
+          feature = "cpu_cortex-m4",
+          feature = "cpu_cortex-m7"))]
+mod ceiling {
+  use core::ops::Drop;
+  use super::CriticalSection;
+
+  /// Phantom type to indicate that interrupts up to a priority ceiling are
+  /// masked through BASEPRI.
+  ///
+  /// Interrupts with a numerically lower priority value than the ceiling
+  /// keep running, so state guarded by this token must not be used by them.
+  /// A ceiling of 0 masks nothing. Tokens can be nested, an inner one only
+  /// ever raises the masked level.
+  pub struct PriorityCeiling {
+    previous: u8,
+  }
+
+  impl PriorityCeiling {
+    /// Masks interrupts with priority values of `ceiling` and above, as set
+    /// by `nvic::set_priority`.
+    ///
+    /// Unsafe as the token is accepted by `Shared::borrow`: the caller must
+    /// make sure that every interrupt handler using the state guarded by
+    /// this token has a priority value of `ceiling` or above, and that
+    /// `ceiling` is not 0.
+    pub unsafe fn new(ceiling: u8) -> PriorityCeiling {
+      let previous = basepri();
+      raise_basepri(ceiling);
+      PriorityCeiling { previous: previous }
+    }
+  }
+
+  impl Drop for PriorityCeiling {
+    fn drop(&mut self) {
+      unsafe {
+        set_basepri(self.previous);
+      }
+    }
+  }
+
+  unsafe impl CriticalSection for PriorityCeiling {}
+
+  /// Returns the current BASEPRI.
+  #[cfg(target_os = "none")]
+  #[inline(always)]
+  unsafe fn basepri() -> u8 {
+    let value: u32;
+    asm!("mrs $0, basepri" : "=r"(value) ::: "volatile");
+    value as u8
+  }
+
+  #[cfg(not(target_os = "none"))]
+  unsafe fn basepri() -> u8 { unimplemented!() }
+
+  /// Raises BASEPRI to `value`, unless it already masks more.
+  #[cfg(target_os = "none")]
+  #[inline(always)]
+  unsafe fn raise_basepri(value: u8) {
+    asm!("msr basepri_max, $0" :: "r"(value as u32) : "memory" : "volatile");
+  }
+
+  #[cfg(not(target_os = "none"))]
+  unsafe fn raise_basepri(_: u8) { unimplemented!() }
+
+  /// Sets BASEPRI to `value`.
+  #[cfg(target_os = "none")]
+  #[inline(always)]
+  unsafe fn set_basepri(value: u8) {
+    asm!("msr basepri, $0" :: "r"(value as u32) : "memory" : "volatile");
+  }
+
+  #[cfg(not(target_os = "none"))]
+  unsafe fn set_basepri(_: u8) { unimplemented!() }
+}
+
 #[cfg(target_os = "none")]
 static mut irq_level : usize = 0;
 
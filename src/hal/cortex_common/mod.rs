@@ -22,3 +22,11 @@ pub mod mpu;
 pub mod nvic;
 pub mod scb;
 pub mod irq;
+#[cfg(any(feature = "cpu_cortex-m3",
+          feature = "cpu_cortex-m4",
+          feature = "cpu_cortex-m7"))]
+pub mod fault;
+#[cfg(any(feature = "cpu_cortex-m3",
+          feature = "cpu_cortex-m4",
+          feature = "cpu_cortex-m7"))]
+pub mod vector_table;
@@ -16,15 +16,247 @@
 //! Interface to Memory Protection Unit.
 //!
 //! MPU memory location is 0xE000_ED90.
+//!
+//! Regions are described with `Region`, which checks the size and alignment
+//! rules of the MPU, and programmed with `set_region()`. Where regions
+//! overlap, the one with the higher number wins. For example, a guard region
+//! that traps null pointer accesses:
+//!
+//! ```ignore
+//! let guard = Region::new(0, 256).unwrap()
+//!   .access(Access::NoAccess)
+//!   .execute_never(true);
+//! mpu::set_region(0, &guard).unwrap();
+//! mpu::enable(true);
+//! ```
 //  Link: http://infocenter.arm.com/help/topic/com.arm.doc.dui0552a/BIHJJABA.html
 
-// TODO(bharrisau): Remove dead_code when MPU is implemented.
-#[allow(dead_code)]
+use util::support::{dsb, isb};
+
 #[inline(always)]
 fn get_reg() -> &'static reg::MPU {
   unsafe { &*(0xE000_ED90 as *mut reg::MPU) }
 }
 
+/// Smallest region size, in bytes.
+const MIN_SIZE: u32 = 32;
+
+/// Smallest region size that has sub-regions, in bytes.
+const MIN_SUBREGION_SIZE: u32 = 256;
+
+/// MPU configuration errors.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Error {
+  /// Region size is not a power of two of at least 32 bytes.
+  InvalidSize,
+  /// Region base is not aligned to the region size.
+  Misaligned,
+  /// Sub-regions can only be disabled in regions of 256 bytes or more.
+  SubregionsUnsupported,
+  /// Region number is not implemented by the MPU.
+  InvalidRegion,
+}
+
+/// Access permissions, for privileged and unprivileged code.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Access {
+  /// No access at all.
+  NoAccess = 0b000,
+  /// Privileged read-write, no unprivileged access.
+  PrivilegedReadWrite = 0b001,
+  /// Privileged read-write, unprivileged read-only.
+  UnprivilegedReadOnly = 0b010,
+  /// Read-write for everyone.
+  ReadWrite = 0b011,
+  /// Privileged read-only, no unprivileged access.
+  PrivilegedReadOnly = 0b101,
+  /// Read-only for everyone.
+  ReadOnly = 0b110,
+}
+
+/// Memory type and cache policy, encoded in TEX, C and B.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MemoryType {
+  /// Strongly-ordered, always shareable.
+  StronglyOrdered,
+  /// Device memory, always shareable.
+  Device,
+  /// Normal memory, write-through, no write allocate.
+  NormalWriteThrough,
+  /// Normal memory, write-back, no write allocate.
+  NormalWriteBack,
+  /// Normal memory, not cacheable.
+  NormalNonCacheable,
+  /// Normal memory, write-back with write allocate.
+  NormalWriteBackAllocate,
+}
+
+impl MemoryType {
+  /// Returns the TEX, C and B fields.
+  fn tex_c_b(self) -> (u32, bool, bool) {
+    match self {
+      MemoryType::StronglyOrdered         => (0b000, false, false),
+      MemoryType::Device                  => (0b000, false, true),
+      MemoryType::NormalWriteThrough      => (0b000, true,  false),
+      MemoryType::NormalWriteBack         => (0b000, true,  true),
+      MemoryType::NormalNonCacheable      => (0b001, false, false),
+      MemoryType::NormalWriteBackAllocate => (0b001, true,  true),
+    }
+  }
+}
+
+/// An MPU region.
+///
+/// New regions are read-write, executable, non-shareable write-back memory
+/// with all sub-regions enabled.
+#[derive(Clone, Copy)]
+pub struct Region {
+  base: u32,
+  size_log2: u8,
+  access: Access,
+  execute_never: bool,
+  memory: MemoryType,
+  shareable: bool,
+  disabled_subregions: u8,
+}
+
+impl Region {
+  /// Creates a region of `size` bytes at `base`.
+  ///
+  /// `size` must be a power of two of at least 32 bytes and `base` must be
+  /// aligned to it.
+  pub fn new(base: u32, size: u32) -> Result<Region, Error> {
+    if size < MIN_SIZE || !size.is_power_of_two() {
+      return Err(Error::InvalidSize);
+    }
+    if base & (size - 1) != 0 {
+      return Err(Error::Misaligned);
+    }
+    Ok(Region {
+      base: base,
+      size_log2: size.trailing_zeros() as u8,
+      access: Access::ReadWrite,
+      execute_never: false,
+      memory: MemoryType::NormalWriteBack,
+      shareable: false,
+      disabled_subregions: 0,
+    })
+  }
+
+  /// Sets the access permissions.
+  pub fn access(mut self, access: Access) -> Region {
+    self.access = access;
+    self
+  }
+
+  /// Forbids instruction fetches from the region.
+  pub fn execute_never(mut self, execute_never: bool) -> Region {
+    self.execute_never = execute_never;
+    self
+  }
+
+  /// Sets the memory type.
+  pub fn memory(mut self, memory: MemoryType) -> Region {
+    self.memory = memory;
+    self
+  }
+
+  /// Marks normal memory as shared between bus masters.
+  pub fn shareable(mut self, shareable: bool) -> Region {
+    self.shareable = shareable;
+    self
+  }
+
+  /// Disables sub-regions, bit n of `mask` disabling the n-th eighth of the
+  /// region.
+  ///
+  /// Accesses to disabled sub-regions are handled by lower numbered regions
+  /// or the default memory map.
+  pub fn disable_subregions(mut self, mask: u8) -> Result<Region, Error> {
+    if mask != 0 && self.size() < MIN_SUBREGION_SIZE {
+      return Err(Error::SubregionsUnsupported);
+    }
+    self.disabled_subregions = mask;
+    Ok(self)
+  }
+
+  /// Returns the base address.
+  pub fn base(&self) -> u32 {
+    self.base
+  }
+
+  /// Returns the size in bytes.
+  pub fn size(&self) -> u32 {
+    1 << self.size_log2
+  }
+}
+
+/// Returns the number of regions the MPU implements, zero if there is none.
+pub fn region_count() -> u8 {
+  get_reg().mpu_type.dregion() as u8
+}
+
+/// Programs and enables a region.
+pub fn set_region(number: u8, region: &Region) -> Result<(), Error> {
+  if number >= region_count() {
+    return Err(Error::InvalidRegion);
+  }
+  let reg = get_reg();
+  let (tex, c, b) = region.memory.tex_c_b();
+
+  reg.rnr.set_region(number as u32);
+  reg.rasr.set_enable(false);
+  reg.rbar.set_addr(region.base >> 5);
+  reg.rasr
+    .set_size((region.size_log2 - 1) as u32)
+    .set_srd(region.disabled_subregions as u32)
+    .set_b(b)
+    .set_c(c)
+    .set_s(region.shareable)
+    .set_tex(tex)
+    .set_ap(region.access as u32)
+    .set_xn(region.execute_never)
+    .set_enable(true);
+  dsb();
+  isb();
+  Ok(())
+}
+
+/// Disables a region.
+pub fn clear_region(number: u8) -> Result<(), Error> {
+  if number >= region_count() {
+    return Err(Error::InvalidRegion);
+  }
+  let reg = get_reg();
+
+  reg.rnr.set_region(number as u32);
+  reg.rasr.set_enable(false);
+  dsb();
+  isb();
+  Ok(())
+}
+
+/// Enables the MPU.
+///
+/// With `privileged_default` set, privileged code can access memory not
+/// covered by any region as in the default memory map, otherwise such
+/// accesses fault. The MPU stays disabled in HardFault and NMI handlers.
+pub fn enable(privileged_default: bool) {
+  get_reg().ctrl
+    .set_privdefena(privileged_default)
+    .set_hfnmiena(false)
+    .set_enable(true);
+  dsb();
+  isb();
+}
+
+/// Disables the MPU.
+pub fn disable() {
+  dsb();
+  get_reg().ctrl.set_enable(false);
+  isb();
+}
+
 mod reg {
   use volatile_cell::VolatileCell;
   use core::ops::Drop;
@@ -61,3 +293,45 @@ mod reg {
     }
   });
 }
+
+#[cfg(test)]
+mod test {
+  use super::{Region, Error, MemoryType};
+
+  #[test]
+  fn rejects_invalid_sizes() {
+    assert_eq!(Region::new(0, 0).err(), Some(Error::InvalidSize));
+    assert_eq!(Region::new(0, 16).err(), Some(Error::InvalidSize));
+    assert_eq!(Region::new(0, 96).err(), Some(Error::InvalidSize));
+  }
+
+  #[test]
+  fn rejects_misaligned_base() {
+    assert_eq!(Region::new(0x2000_0100, 0x200).err(), Some(Error::Misaligned));
+    assert!(Region::new(0x2000_0200, 0x200).is_ok());
+  }
+
+  #[test]
+  fn encodes_size() {
+    let region = Region::new(0x0800_0000, 0x10_0000).unwrap();
+    assert_eq!(region.size_log2, 20);
+    assert_eq!(region.size(), 0x10_0000);
+  }
+
+  #[test]
+  fn checks_subregion_size() {
+    let small = Region::new(0, 128).unwrap();
+    assert_eq!(small.disable_subregions(1).err(),
+        Some(Error::SubregionsUnsupported));
+    assert!(small.disable_subregions(0).is_ok());
+
+    let large = Region::new(0, 256).unwrap().disable_subregions(0x81).unwrap();
+    assert_eq!(large.disabled_subregions, 0x81);
+  }
+
+  #[test]
+  fn encodes_memory_types() {
+    assert_eq!(MemoryType::Device.tex_c_b(), (0, false, true));
+    assert_eq!(MemoryType::NormalWriteBackAllocate.tex_c_b(), (1, true, true));
+  }
+}
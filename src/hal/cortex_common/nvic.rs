@@ -18,6 +18,9 @@
 //! NVIC memory location is 0xE000_E000.
 //  Link: http://infocenter.arm.com/help/topic/com.arm.doc.dui0552a/CIHIGCIF.html
 
+use super::irq::NoInterrupts;
+use super::scb;
+
 #[inline(always)]
 fn get_reg() -> &'static reg::NVIC {
   unsafe { &*(0xE000_E000 as *mut reg::NVIC) }
@@ -63,6 +66,68 @@ pub fn get_priority(irqn: usize) -> u8 {
   get_reg().ipr[irqn / 4].ipr(irqn % 4) as u8
 }
 
+#[allow(non_upper_case_globals)]
+static mut PriorityBits: u8 = 0;
+
+/// Return the number of priority bits implemented by the MCU
+///
+/// Found once by writing all ones to the priority of interrupt 0 and reading
+/// back the bits that stuck, with interrupts disabled so that interrupt 0
+/// never runs with the probe value.
+pub fn priority_bits() -> u8 {
+  let _crit = NoInterrupts::new();
+  unsafe {
+    if PriorityBits == 0 {
+      let saved = get_priority(0);
+      set_priority(0, 0xff);
+      PriorityBits = get_priority(0).count_ones() as u8;
+      set_priority(0, saved);
+    }
+    PriorityBits
+  }
+}
+
+/// Set the preemption priority and sub-priority for the given interrupt,
+/// split according to `scb::priority_grouping()`
+pub fn set_grouped_priority(irqn: usize, preempt: u8, sub: u8) {
+  set_priority(irqn, encode_priority(scb::priority_grouping(),
+      priority_bits(), preempt, sub));
+}
+
+/// Return the preemption priority and sub-priority for the given interrupt
+pub fn get_grouped_priority(irqn: usize) -> (u8, u8) {
+  decode_priority(scb::priority_grouping(), priority_bits(),
+      get_priority(irqn))
+}
+
+/// Returns the number of preemption and sub-priority bits for a priority
+/// grouping, given the number of implemented priority bits.
+fn group_bits(prigroup: u8, bits: u8) -> (u8, u8) {
+  let sub = if prigroup + 1 + bits > 8 { prigroup + 1 + bits - 8 } else { 0 };
+  (bits - sub, sub)
+}
+
+/// Encodes a preemption priority and sub-priority into a priority value.
+///
+/// Both are truncated to the number of bits the grouping leaves for them.
+pub fn encode_priority(prigroup: u8, bits: u8, preempt: u8, sub: u8) -> u8 {
+  let (preempt_bits, sub_bits) = group_bits(prigroup, bits);
+  let preempt_mask = (1u32 << preempt_bits) - 1;
+  let sub_mask = (1u32 << sub_bits) - 1;
+
+  (((preempt as u32 & preempt_mask) << (8 - preempt_bits)) |
+      ((sub as u32 & sub_mask) << (8 - bits))) as u8
+}
+
+/// Decodes a priority value into its preemption priority and sub-priority.
+pub fn decode_priority(prigroup: u8, bits: u8, priority: u8) -> (u8, u8) {
+  let (preempt_bits, sub_bits) = group_bits(prigroup, bits);
+  let value = priority as u32;
+
+  (((value >> (8 - preempt_bits)) & ((1 << preempt_bits) - 1)) as u8,
+   ((value >> (8 - bits)) & ((1 << sub_bits) - 1)) as u8)
+}
+
 mod reg {
   use volatile_cell::VolatileCell;
   use core::ops::Drop;
@@ -91,3 +156,33 @@ mod reg {
     }
   });
 }
+
+#[cfg(test)]
+mod test {
+  use super::{encode_priority, decode_priority};
+
+  #[test]
+  fn encodes_preemption_only() {
+    // 4 implemented bits, all used for preemption
+    assert_eq!(encode_priority(3, 4, 5, 0), 0x50);
+    assert_eq!(encode_priority(0, 4, 15, 3), 0xf0);
+  }
+
+  #[test]
+  fn encodes_grouped_priority() {
+    // 4 implemented bits, 2 of them for sub-priority
+    assert_eq!(encode_priority(5, 4, 3, 2), 0xe0);
+    // 5 implemented bits, all of them for sub-priority
+    assert_eq!(encode_priority(7, 5, 1, 9), 0x48);
+  }
+
+  #[test]
+  fn decodes_what_it_encodes() {
+    for prigroup in 0..8 {
+      let value = encode_priority(prigroup, 3, 1, 1);
+      let (preempt, sub) = decode_priority(prigroup, 3, value);
+      assert_eq!(encode_priority(prigroup, 3, preempt, sub), value);
+    }
+    assert_eq!(decode_priority(5, 4, 0xe0), (3, 2));
+  }
+}
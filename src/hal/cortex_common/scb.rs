@@ -39,6 +39,92 @@ pub fn set_pendsv(val: bool) {
   }
 }
 
+/// Returns the pending state of the SysTick exception.
+pub fn systick_pending() -> bool {
+  get_reg().icsr.pendstset()
+}
+
+/// Selects deep sleep instead of sleep as the low power mode entered by
+/// `wfi`.
+pub fn set_sleep_deep(val: bool) {
+  get_reg().scr.set_sleepdeep(val);
+}
+
+/// Sets the priority grouping.
+///
+/// Priority bits above bit `prigroup` hold the preemption priority, bit
+/// `prigroup` and the ones below hold the sub-priority, which only orders
+/// pending interrupts of the same preemption priority.
+pub fn set_priority_grouping(prigroup: u8) {
+  get_reg().aircr
+    .set_vectkey(0x05fa)
+    .set_prigroup(prigroup as u32);
+}
+
+/// Returns the priority grouping.
+pub fn priority_grouping() -> u8 {
+  get_reg().aircr.prigroup() as u8
+}
+
+/// Returns the address of the vector table.
+pub fn vector_table() -> u32 {
+  get_reg().vtor.tbloff() << 7
+}
+
+/// Points the core at a vector table. `address` must be aligned to the
+/// table size rounded up to a power of two, and at least to 128 bytes.
+pub fn set_vector_table(address: u32) {
+  get_reg().vtor.set_tbloff(address >> 7);
+}
+
+/// Returns the number of the active exception, zero in thread mode.
+pub fn active_exception() -> u32 {
+  get_reg().icsr.vectactive()
+}
+
+/// Enables the MemManage, BusFault and UsageFault handlers. Disabled faults
+/// escalate to HardFault.
+pub fn enable_fault_handlers() {
+  get_reg().shcsr
+    .set_memfaultena(true)
+    .set_busfaultena(true)
+    .set_usgfaultena(true);
+}
+
+/// Returns the system handler control and state register.
+pub fn shcsr() -> u32 {
+  get_reg().shcsr.get().raw()
+}
+
+/// Returns the configurable fault status register, which holds MemManage,
+/// BusFault and UsageFault status.
+pub fn cfsr() -> u32 {
+  get_reg().cfsr.get().raw()
+}
+
+/// Returns the HardFault status register.
+pub fn hfsr() -> u32 {
+  get_reg().hfsr.get().raw()
+}
+
+/// Returns the MemManage fault address register.
+pub fn mmfar() -> u32 {
+  get_reg().mmfar.address()
+}
+
+/// Returns the BusFault address register.
+pub fn bfar() -> u32 {
+  get_reg().bfar.address()
+}
+
+/// Requests a system reset and waits for it.
+pub fn system_reset() -> ! {
+  get_reg().aircr
+    .set_vectkey(0x05fa)
+    .set_sysresetreq(true);
+  loop {}
+}
+
 mod reg {
   use volatile_cell::VolatileCell;
   use core::ops::Drop;
@@ -101,7 +187,7 @@ mod reg {
       13      => memfaultpended,
       14      => busfaultpended,
       15      => svfaultpended,
-      16      => memfaultpendena,
+      16      => memfaultena,
       17      => busfaultena,
       18      => usgfaultena,
     }
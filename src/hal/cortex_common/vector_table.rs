@@ -0,0 +1,119 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Vector table relocation to RAM.
+//!
+//! By default the core uses the vector table that the linker puts at the
+//! start of flash, with handlers bound at link time through `isr_*` symbols.
+//! `relocate()` copies that table to room the linker reserves at the start
+//! of RAM and points VTOR at the copy, after which `register_handler()` can
+//! replace interrupt handlers at run time.
+
+use core::intrinsics::{volatile_load, volatile_store};
+
+use hal::cortex_common::irq::NoInterrupts;
+use hal::cortex_common::scb;
+use util::support::{dsb, isb};
+
+/// Interrupt handler installed at run time.
+pub type Handler = extern fn();
+
+/// Number of core exception vectors before the first interrupt.
+const CORE_VECTORS: usize = 16;
+
+extern {
+  static _vectors: u32;
+  static _evectors: u32;
+}
+
+/// First word of the RAM table. The linker script only reserves room for
+/// the table if this section is linked in.
+#[allow(non_upper_case_globals)]
+#[link_section = ".vector_ram"]
+static mut VectorRam: u32 = 0;
+
+fn flash_table() -> *const u32 {
+  unsafe { &_vectors as *const u32 }
+}
+
+fn ram_table() -> *mut u32 {
+  unsafe { &mut VectorRam as *mut u32 }
+}
+
+fn vector_count() -> usize {
+  let end = unsafe { &_evectors as *const u32 };
+  (end as usize - flash_table() as usize) / 4
+}
+
+/// Returns the number of interrupts in the vector table.
+pub fn irq_count() -> usize {
+  vector_count() - CORE_VECTORS
+}
+
+/// Returns true if the core uses the RAM copy of the vector table.
+pub fn is_relocated() -> bool {
+  scb::vector_table() == ram_table() as u32
+}
+
+/// Copies the vector table to RAM and points VTOR at the copy.
+///
+/// Handlers registered before are kept if the table is already relocated.
+pub fn relocate() {
+  let _crit = NoInterrupts::new();
+  if is_relocated() {
+    return;
+  }
+
+  for i in 0..vector_count() as isize {
+    unsafe {
+      let vector = volatile_load(flash_table().offset(i));
+      volatile_store(ram_table().offset(i), vector);
+    }
+  }
+  dsb();
+  scb::set_vector_table(ram_table() as u32);
+  dsb();
+  isb();
+}
+
+/// Installs `handler` for interrupt `irqn`, relocating the vector table
+/// first if needed.
+///
+/// The interrupt itself still has to be enabled with `nvic::enable_irq`.
+pub fn register_handler(irqn: usize, handler: Handler) {
+  assert!(irqn < irq_count());
+  relocate();
+
+  unsafe {
+    volatile_store(ram_table().offset((CORE_VECTORS + irqn) as isize),
+        handler as usize as u32);
+  }
+  dsb();
+}
+
+/// Restores the link time handler for interrupt `irqn`.
+pub fn unregister_handler(irqn: usize) {
+  assert!(irqn < irq_count());
+  if !is_relocated() {
+    return;
+  }
+
+  let index = (CORE_VECTORS + irqn) as isize;
+  unsafe {
+    volatile_store(ram_table().offset(index),
+        volatile_load(flash_table().offset(index)));
+  }
+  dsb();
+}
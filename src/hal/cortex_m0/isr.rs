@@ -26,12 +26,15 @@ extern {
   fn isr_svcall();
   fn isr_pendsv();
   fn isr_systick();
+
+  fn isr_reserved_1();
 }
 
 #[no_mangle]
 pub unsafe extern fn isr_handler_wrapper() {
   asm!(".weak isr_nmi, isr_hardfault
       .weak isr_svcall, isr_pendsv, isr_systick
+      .weak isr_reserved_1
 
       .thumb_func
       isr_nmi:
@@ -73,7 +76,7 @@ pub static ISRVectors: [Option<unsafe extern fn()>; ISRCount] = [
   None,                   // Reserved
   None,                   // Reserved
   None,                   // Reserved
-  None,                   // Reserved
+  Some(isr_reserved_1),   // Reserved - Used as NXP Checksum
   None,                   // Reserved
   None,                   // Reserved
   None,                   // Reserved
@@ -0,0 +1,138 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+Digital to analog converter interface.
+
+`Dac` sets the output level directly. Converters implementing `Waveform` can
+also play a buffer of samples, paced by a periodic alarm on any timer that
+implements `hal::timer::Alarms`. Only one waveform plays at a time.
+*/
+
+use core::option::Option::{self, Some, None};
+
+use hal::timer::{Alarms, AlarmAction};
+
+/// Converts millivolts into a raw value, clamping at full scale.
+pub fn millivolts_to_raw(mv: u32, resolution: u8, reference_mv: u32) -> u16 {
+  let full_scale = (1u32 << resolution as usize) - 1;
+  if mv >= reference_mv {
+    return full_scale as u16;
+  }
+  ((mv * full_scale + reference_mv / 2) / reference_mv) as u16
+}
+
+/// Digital to analog converter.
+pub trait Dac {
+  /// Returns the resolution of the output, in bits.
+  fn resolution(&self) -> u8;
+
+  /// Returns the reference voltage in millivolts, which is what the full
+  /// scale value corresponds to.
+  fn reference_mv(&self) -> u32;
+
+  /// Sets the output to a raw value.
+  fn write(&self, value: u16);
+
+  /// Sets the output to the given voltage, in millivolts.
+  fn write_millivolts(&self, mv: u32) {
+    self.write(millivolts_to_raw(mv, self.resolution(), self.reference_mv()));
+  }
+}
+
+/// Converter that can play back a buffer of samples.
+pub trait Waveform: Dac {
+  /// Sets the output to a raw value, called from the timer interrupt.
+  fn output(value: u16);
+
+  /// Starts playing `samples`, one every `period` ticks of `timer`, using the
+  /// given alarm. Playback stops at the end of the buffer, unless `repeat` is
+  /// set.
+  fn play<T: Alarms>(&self, timer: &T, alarm: usize, period: u32,
+      samples: &'static [u16], repeat: bool) {
+    timer.cancel_alarm(alarm);
+    unsafe {
+      Playback = PlaybackState {
+        samples: Some(samples),
+        position: 0,
+        repeat: repeat,
+        output: Some(Self::output as fn(u16)),
+      };
+    }
+    timer.set_periodic_alarm(alarm, period, next_sample);
+  }
+
+  /// Stops playback, the output keeps the last sample.
+  fn stop<T: Alarms>(&self, timer: &T, alarm: usize) {
+    timer.cancel_alarm(alarm);
+    unsafe { Playback.samples = None };
+  }
+
+  /// Returns true while a waveform is playing.
+  fn is_playing(&self) -> bool {
+    unsafe { Playback.samples.is_some() }
+  }
+}
+
+struct PlaybackState {
+  samples: Option<&'static [u16]>,
+  position: usize,
+  repeat: bool,
+  output: Option<fn(u16)>,
+}
+
+#[allow(non_upper_case_globals)]
+static mut Playback: PlaybackState = PlaybackState {
+  samples: None,
+  position: 0,
+  repeat: false,
+  output: None,
+};
+
+fn next_sample() -> AlarmAction {
+  let state = unsafe { &mut Playback };
+  let (samples, output) = match (state.samples, state.output) {
+    (Some(samples), Some(output)) => (samples, output),
+    _ => return AlarmAction::Cancel,
+  };
+
+  if state.position >= samples.len() {
+    if !state.repeat || samples.len() == 0 {
+      state.samples = None;
+      return AlarmAction::Cancel;
+    }
+    state.position = 0;
+  }
+  output(samples[state.position]);
+  state.position += 1;
+  AlarmAction::Continue
+}
+
+#[cfg(test)]
+mod test {
+  use super::millivolts_to_raw;
+
+  #[test]
+  fn converts_millivolts() {
+    assert_eq!(millivolts_to_raw(0, 10, 3300), 0);
+    assert_eq!(millivolts_to_raw(1650, 10, 3300), 512);
+    assert_eq!(millivolts_to_raw(3300, 12, 3300), 4095);
+  }
+
+  #[test]
+  fn clamps_above_reference() {
+    assert_eq!(millivolts_to_raw(5000, 10, 3300), 1023);
+  }
+}
@@ -0,0 +1,107 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+DMA interface.
+
+A DMA channel moves a block of data between two endpoints without CPU
+involvement. Transfers are described by MCU-specific descriptors, which can be
+chained into linked lists on controllers that support scatter-gather. Their
+completion can be polled with `Channel::wait()` or reported through a callback
+registered with the MCU-specific channel.
+
+The hardware accesses buffers behind the compiler's back, every buffer and
+descriptor must stay valid and untouched until the transfer completes.
+*/
+
+/// Size of a single element moved by the controller.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Width {
+  /// 8-bit elements.
+  Bits8,
+  /// 16-bit elements.
+  Bits16,
+  /// 32-bit elements.
+  Bits32,
+}
+
+impl Width {
+  /// Returns the element size in bytes.
+  pub fn bytes(self) -> usize {
+    match self {
+      Width::Bits8  => 1,
+      Width::Bits16 => 2,
+      Width::Bits32 => 4,
+    }
+  }
+}
+
+/// One side of a transfer.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Endpoint {
+  /// Buffer starting at the given address, advanced after every element.
+  Buffer(u32),
+  /// Fixed address, usually a peripheral data register.
+  Fixed(u32),
+}
+
+impl Endpoint {
+  /// Returns the start address.
+  pub fn address(self) -> u32 {
+    match self {
+      Endpoint::Buffer(addr) | Endpoint::Fixed(addr) => addr,
+    }
+  }
+
+  /// Returns true if the address is incremented during the transfer.
+  pub fn increments(self) -> bool {
+    match self {
+      Endpoint::Buffer(_) => true,
+      Endpoint::Fixed(_) => false,
+    }
+  }
+}
+
+/// DMA transfer error.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Error {
+  /// The controller got a bus error while accessing an endpoint or descriptor.
+  BusError,
+}
+
+/// Completion callback, called from the DMA interrupt.
+pub type Callback = fn(Result<(), Error>);
+
+/// DMA channel trait.
+pub trait Channel {
+  /// Returns true while a transfer is in progress.
+  fn is_busy(&self) -> bool;
+
+  /// Returns true if the last transfer ended with an error.
+  fn has_failed(&self) -> bool;
+
+  /// Stops the current transfer, if any.
+  fn abort(&self);
+
+  /// Waits for the current transfer to complete.
+  fn wait(&self) -> Result<(), Error> {
+    while self.is_busy() {}
+    if self.has_failed() {
+      Err(Error::BusError)
+    } else {
+      Ok(())
+    }
+  }
+}
@@ -0,0 +1,166 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Common definitions for on-chip flash programming.
+//!
+//! Flash is divided into sectors, which are the smallest unit that can be
+//! erased. Erased flash reads as `0xff`, and programming can only clear bits,
+//! so a location has to be erased before it is written again.
+
+use core::intrinsics::volatile_load;
+
+/// Flash operation errors.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Error {
+  /// Address is out of flash, misaligned or the sector does not exist.
+  InvalidAddress,
+  /// Sector is write protected.
+  WriteProtected,
+  /// The flash controller reported an error.
+  OperationFailed,
+  /// Flash contents do not match the data written.
+  VerifyFailed,
+}
+
+/// On-chip flash memory.
+pub trait Flash {
+  /// Returns the number of sectors.
+  fn sector_count(&self) -> usize;
+
+  /// Returns the start address of a sector.
+  fn sector_start(&self, sector: usize) -> usize;
+
+  /// Returns the size of a sector in bytes.
+  fn sector_size(&self, sector: usize) -> usize;
+
+  /// Returns the alignment in bytes required for programming addresses.
+  fn program_size(&self) -> usize;
+
+  /// Erases a sector.
+  fn erase(&self, sector: usize) -> Result<(), Error>;
+
+  /// Programs `data` at `address`, which must be in erased flash and aligned
+  /// to `program_size()`.
+  fn program(&self, address: usize, data: &[u8]) -> Result<(), Error>;
+
+  /// Returns the sector containing `address`, if any.
+  fn sector_at(&self, address: usize) -> Option<usize> {
+    for sector in 0..self.sector_count() {
+      let start = self.sector_start(sector);
+      if address >= start && address - start < self.sector_size(sector) {
+        return Some(sector);
+      }
+    }
+    None
+  }
+
+  /// Reads flash contents at `address` into `buf`.
+  fn read(&self, address: usize, buf: &mut [u8]) {
+    for (i, byte) in buf.iter_mut().enumerate() {
+      *byte = unsafe { volatile_load((address + i) as *const u8) };
+    }
+  }
+
+  /// Checks that flash at `address` holds `data`.
+  fn verify(&self, address: usize, data: &[u8]) -> Result<(), Error> {
+    for (i, byte) in data.iter().enumerate() {
+      if unsafe { volatile_load((address + i) as *const u8) } != *byte {
+        return Err(Error::VerifyFailed);
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Checks that `len` bytes at `address` can be programmed.
+///
+/// The range must start at a `program_size()` boundary and lie within the
+/// flash sectors.
+pub fn check_range<F: Flash>(flash: &F, address: usize, len: usize)
+    -> Result<(), Error> {
+  if address % flash.program_size() != 0 {
+    return Err(Error::InvalidAddress);
+  }
+  if len == 0 {
+    return Ok(());
+  }
+  match (flash.sector_at(address), flash.sector_at(address + len - 1)) {
+    (Some(_), Some(_)) => Ok(()),
+    _ => Err(Error::InvalidAddress),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{Flash, Error, check_range};
+
+  /// Flash with four 16 byte sectors followed by two 64 byte ones, backed
+  /// by a buffer so that reads work.
+  struct FakeFlash {
+    base: usize,
+  }
+
+  impl Flash for FakeFlash {
+    fn sector_count(&self) -> usize { 6 }
+    fn sector_start(&self, sector: usize) -> usize {
+      if sector < 4 {
+        self.base + sector * 16
+      } else {
+        self.base + 64 + (sector - 4) * 64
+      }
+    }
+    fn sector_size(&self, sector: usize) -> usize {
+      if sector < 4 { 16 } else { 64 }
+    }
+    fn program_size(&self) -> usize { 4 }
+    fn erase(&self, _sector: usize) -> Result<(), Error> { Ok(()) }
+    fn program(&self, _address: usize, _data: &[u8]) -> Result<(), Error> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn finds_sectors() {
+    let flash = FakeFlash { base: 0x1000 };
+    assert_eq!(flash.sector_at(0x1000), Some(0));
+    assert_eq!(flash.sector_at(0x103f), Some(3));
+    assert_eq!(flash.sector_at(0x1040), Some(4));
+    assert_eq!(flash.sector_at(0x10bf), Some(5));
+    assert_eq!(flash.sector_at(0x10c0), None);
+    assert_eq!(flash.sector_at(0xfff), None);
+  }
+
+  #[test]
+  fn checks_program_range() {
+    let flash = FakeFlash { base: 0x1000 };
+    assert_eq!(check_range(&flash, 0x1000, 0xc0), Ok(()));
+    assert_eq!(check_range(&flash, 0x1002, 4), Err(Error::InvalidAddress));
+    assert_eq!(check_range(&flash, 0x10bc, 8), Err(Error::InvalidAddress));
+    assert_eq!(check_range(&flash, 0x2000, 0), Ok(()));
+  }
+
+  #[test]
+  fn reads_and_verifies() {
+    let memory = [1u8, 2, 3, 4, 5, 6, 7, 8];
+    let flash = FakeFlash { base: memory.as_ptr() as usize };
+    let mut buf = [0u8; 4];
+
+    flash.read(flash.base + 2, &mut buf);
+    assert_eq!(buf, [3, 4, 5, 6]);
+    assert_eq!(flash.verify(flash.base, &memory[..4]), Ok(()));
+    assert_eq!(flash.verify(flash.base + 1, &memory[..4]),
+        Err(Error::VerifyFailed));
+  }
+}
@@ -0,0 +1,56 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+I2C interface.
+
+The bus is configured by the MCU-specific driver, this trait only covers master
+transactions. Addresses are 7-bit, without the R/W bit.
+
+Every transaction starts with a START condition and ends with a STOP condition,
+`write_read()` uses a repeated START between the two phases so that no other
+master can take the bus in between. A failed transaction leaves the bus idle.
+*/
+
+/// I2C transaction error.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Error {
+  /// No device acknowledged the address.
+  AddressNack,
+  /// The device did not acknowledge a data byte.
+  DataNack,
+  /// Another master took over the bus.
+  ArbitrationLost,
+  /// A misplaced START or STOP condition was detected.
+  BusError,
+  /// The peripheral did not respond in time.
+  Timeout,
+}
+
+/// I2C master trait.
+pub trait I2c {
+  /// Writes `data` to the device at `addr`.
+  fn write(&self, addr: u8, data: &[u8]) -> Result<(), Error>;
+
+  /// Reads `buf.len()` bytes from the device at `addr`.
+  fn read(&self, addr: u8, buf: &mut [u8]) -> Result<(), Error>;
+
+  /// Writes `data` to the device at `addr`, then reads `buf.len()` bytes back
+  /// after a repeated START.
+  ///
+  /// This is the usual way of reading a register of a device.
+  fn write_read(&self, addr: u8, data: &[u8], buf: &mut [u8])
+      -> Result<(), Error>;
+}
@@ -30,6 +30,9 @@
 #[cfg(feature = "cpu_cortex-m7")]
 #[path="cortex_m3/isr.rs"] pub mod isr_cortex_m7;
 
+#[cfg(feature = "mcu_lpc11xx")]
+#[path="lpc11xx/isr.rs"] pub mod isr_lpc11xx;
+
 #[cfg(feature = "mcu_lpc17xx")]
 #[path="lpc17xx/isr.rs"] pub mod isr_lpc17xx;
 
@@ -38,3 +41,9 @@
 
 #[cfg(feature = "mcu_tiva_c")]
 #[path="tiva_c/isr.rs"] pub mod isr_tiva_c;
+
+#[cfg(feature = "mcu_stm32f4")]
+#[path="stm32f4/isr.rs"] pub mod isr_stm32f4;
+
+#[cfg(feature = "mcu_stm32l1")]
+#[path="stm32l1/isr.rs"] pub mod isr_stm32l1;
@@ -0,0 +1,141 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! 12-bit DAC0 for Kinetis K20.
+//!
+//! The data buffer is not used, writes go straight to the output.
+
+use hal::dac;
+
+use super::sim;
+
+/// Reference voltage source.
+#[allow(missing_docs)]
+#[derive(Clone, Copy)]
+pub enum Reference {
+  /// DACREF_1, VREFH.
+  VrefH,
+  /// DACREF_2, VDDA.
+  Vdda,
+}
+
+/// The digital to analog converter.
+#[derive(Clone, Copy)]
+pub struct DAC {
+  reference_mv: u32,
+}
+
+impl DAC {
+  /// Enables DAC0 and sets the output to 0. `reference_mv` is the voltage of
+  /// the selected reference in millivolts.
+  pub fn new(reference: Reference, reference_mv: u32) -> DAC {
+    use self::reg::DAC_c0_rfs as rfs;
+
+    sim::reg::SIM.scgc2.set_dac0(true);
+
+    write_raw(0);
+    reg::DAC0.c1.set_bfen(false);
+    reg::DAC0.c0
+      .set_rfs(match reference {
+        Reference::VrefH => rfs::DACREF_1,
+        Reference::Vdda  => rfs::DACREF_2,
+      })
+      .set_en(true);
+
+    DAC {
+      reference_mv: reference_mv,
+    }
+  }
+}
+
+fn write_raw(value: u16) {
+  let dat = &reg::DAC0.dat[0];
+  dat.low.set_data((value & 0xFF) as u8);
+  dat.high.set_data(((value >> 8) & 0x0F) as u8);
+}
+
+impl dac::Dac for DAC {
+  fn resolution(&self) -> u8 {
+    12
+  }
+
+  fn reference_mv(&self) -> u32 {
+    self.reference_mv
+  }
+
+  fn write(&self, value: u16) {
+    write_raw(value);
+  }
+}
+
+impl dac::Waveform for DAC {
+  fn output(value: u16) {
+    write_raw(value);
+  }
+}
+
+/// Registers
+#[allow(dead_code)]
+mod reg {
+  use volatile_cell::VolatileCell;
+  use core::ops::Drop;
+
+  ioregs!(DAC = {
+    0x00   => group dat[16] {     //! Data buffer
+      0x0  => reg8 low {
+        0..7 => data,
+      }
+      0x1  => reg8 high {
+        0..3 => data,
+      }
+    }
+
+    0x20   => reg8 sr {           //! Status
+      0    => bfrpbf,             //= Read pointer bottom flag
+      1    => bfrptf,             //= Read pointer top flag
+      2    => bfwmf,              //= Watermark flag
+    }
+
+    0x21   => reg8 c0 {           //! Control 0
+      0    => bfbien,             //= Bottom flag interrupt enable
+      1    => bftien,             //= Top flag interrupt enable
+      2    => bfwmien,            //= Watermark interrupt enable
+      3    => lpen,               //= Low power mode
+      4    => swtrg: wo,          //= Software trigger
+      5    => trgsel,             //= Trigger select
+      6    => rfs {               //= Reference select
+        0 => DACREF_1,
+        1 => DACREF_2,
+      }
+      7    => en,                 //= DAC enable
+    }
+
+    0x22   => reg8 c1 {           //! Control 1
+      0    => bfen,               //= Buffer enable
+      1..2 => bfmd,               //= Buffer work mode
+      3..4 => bfwm,               //= Watermark select
+      7    => dmaen,              //= DMA enable
+    }
+
+    0x23   => reg8 c2 {           //! Control 2
+      0..3 => bfup,               //= Buffer upper limit
+      4..7 => bfrp,               //= Buffer read pointer
+    }
+  });
+
+  extern {
+    #[link_name="k20_iomem_DAC0"] pub static DAC0: DAC;
+  }
+}
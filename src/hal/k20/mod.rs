@@ -16,6 +16,8 @@
 //! HAL for Freescale Kinetis K20.
 
 pub mod sim;
+pub mod dac;
 pub mod pin;
+pub mod rtc;
 pub mod uart;
 pub mod watchdog;
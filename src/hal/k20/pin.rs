@@ -18,11 +18,20 @@ Pin configuration.
 
 Some pins that could be configured here may be missing from actual MCU depending
 on the package.
+
+Edge interrupts are configured through the IRQC field of the pin control
+register. The application defines the `isr_port_a` to `isr_port_e` handlers
+it needs and calls `handle_interrupt()` from them, which dispatches to the
+handlers registered with `InterruptPin::set_interrupt`.
 */
 
 use core::option::Option;
 use core::marker::Copy;
 
+use hal::cortex_m4::irq::NoInterrupts;
+use hal::cortex_m4::nvic;
+use hal::pin::{Edge, PinHandler};
+
 use super::sim;
 
 use self::Port::*;
@@ -157,14 +166,17 @@ impl Pin {
   }
 
   fn pcr(&self) -> &'static reg::Port_pcr {
-    let port: &reg::Port = match self.port {
-      PortA => &reg::PORT_A,
-      PortB => &reg::PORT_B,
-      PortC => &reg::PORT_C,
-      PortD => &reg::PORT_D,
-      PortE => &reg::PORT_E,
-    };
-    return &port.pcr[self.pin as usize];
+    &port_reg(self.port).pcr[self.pin as usize]
+  }
+}
+
+fn port_reg(port: Port) -> &'static reg::Port {
+  match port {
+    PortA => &reg::PORT_A,
+    PortB => &reg::PORT_B,
+    PortC => &reg::PORT_C,
+    PortD => &reg::PORT_D,
+    PortE => &reg::PORT_E,
   }
 }
 
@@ -200,6 +212,87 @@ impl ::hal::pin::Gpio for Pin {
   }
 }
 
+impl ::hal::pin::InterruptPin for Pin {
+  /// Registers the handler and enables the interrupt for the given edge.
+  fn set_interrupt(&self, edge: Edge, handler: PinHandler) {
+    {
+      let _crit = NoInterrupts::new();
+      unsafe {
+        PinInterrupts[port_index(self.port)][self.pin as usize] = PinInterrupt {
+          handler: Some(handler),
+          edge: edge,
+        };
+      }
+    }
+    self.clear_interrupt();
+    self.enable_interrupt();
+    nvic::enable_irq(PORT_IRQ_BASE + self.port as usize);
+  }
+
+  fn enable_interrupt(&self) {
+    use self::reg::Port_pcr_irqc as irqc;
+    let edge = unsafe {
+      PinInterrupts[port_index(self.port)][self.pin as usize].edge
+    };
+    let mode = match edge {
+      Edge::Rising  => irqc::IRQ_RISING,
+      Edge::Falling => irqc::IRQ_FALLING,
+      Edge::Both    => irqc::IRQ_EITHER,
+    };
+    self.pcr().set_irqc(mode);
+  }
+
+  fn disable_interrupt(&self) {
+    self.pcr().set_irqc(reg::Port_pcr_irqc::IRQ_NONE);
+  }
+
+  fn clear_interrupt(&self) {
+    port_reg(self.port).isfr.ignoring_state().clear_isf(self.pin as usize);
+  }
+}
+
+#[derive(Clone, Copy)]
+struct PinInterrupt {
+  handler: Option<PinHandler>,
+  edge: Edge,
+}
+
+const PIN_INTERRUPT_INIT: PinInterrupt = PinInterrupt {
+  handler: None,
+  edge: Edge::Both,
+};
+
+/// IRQ number of PORTA, the following ports are numbered sequentially.
+/// `Port` discriminants start at 1.
+const PORT_IRQ_BASE: usize = 86;
+
+#[allow(non_upper_case_globals)]
+static mut PinInterrupts: [[PinInterrupt; 32]; 5] =
+    [[PIN_INTERRUPT_INIT; 32]; 5];
+
+fn port_index(port: Port) -> usize {
+  port as usize - 1
+}
+
+/// Dispatches the pending pin interrupts of `port`.
+///
+/// Unsafe because it reads the handlers without a critical section, it must
+/// only be called from the interrupt handler of the port.
+pub unsafe fn handle_interrupt(port: Port) {
+  let reg = port_reg(port);
+  let pending = reg.isfr.get().raw();
+
+  for pin in 0..32 {
+    if pending & (1 << pin) != 0 {
+      reg.isfr.ignoring_state().clear_isf(pin);
+      match PinInterrupts[port_index(port)][pin].handler {
+        Some(handler) => handler(),
+        None => {},
+      }
+    }
+  }
+}
+
 /// Register definitions
 pub mod reg {
   use volatile_cell::VolatileCell;
@@ -250,8 +343,8 @@ pub mod reg {
       16..31 => gpwe,
     }
 
-    0x88   => reg32 isfr {    //= Interrupt status
-      0..31  => isf
+    0xa0   => reg32 isfr {    //= Interrupt status
+      0..31  => isf[32]: set_to_clear,
     }
   });
 
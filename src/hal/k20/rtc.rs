@@ -0,0 +1,180 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Real-time clock for Kinetis K20.
+//!
+//! The RTC is a seconds counter running from the 32.768 kHz oscillator in the
+//! VBAT domain, its value is kept as a Unix timestamp. The VBAT register file
+//! provides eight backup registers. Applications using the alarm define
+//! `isr_rtc_alarm` and call `handle_interrupt()` from it, which calls the alarm
+//! handler.
+
+use core::option::Option::{self, Some, None};
+
+use hal::cortex_m4::nvic;
+use hal::rtc;
+use hal::rtc::{AlarmHandler, DateTime};
+
+use super::sim;
+
+const RTC_ALARM_IRQ: usize = 66;
+
+/// Number of 32-bit registers in the VBAT register file.
+const VBAT_REGISTER_COUNT: usize = 8;
+
+#[allow(non_upper_case_globals)]
+static mut Alarm: Option<AlarmHandler> = None;
+
+/// The real-time clock.
+#[derive(Clone, Copy)]
+pub struct RTC;
+
+impl RTC {
+  /// Enables the RTC oscillator and counter. The time keeps running from the
+  /// battery if it was already set, otherwise it starts from the epoch.
+  pub fn new() -> RTC {
+    sim::reg::SIM.scgc6.set_rtc(true);
+
+    reg::RTC.cr.set_osce(true);
+    if reg::RTC.sr.tif() {
+      // The time is invalid after a VBAT power on reset, writing TSR clears
+      // the flag.
+      reg::RTC.sr.set_tce(false);
+      reg::RTC.tsr.set_seconds(0);
+    }
+    reg::RTC.sr.set_tce(true);
+
+    RTC
+  }
+}
+
+impl rtc::Rtc for RTC {
+  fn now(&self) -> DateTime {
+    DateTime::from_unix(reg::RTC.tsr.seconds())
+  }
+
+  fn set(&self, time: DateTime) {
+    reg::RTC.sr.set_tce(false);
+    reg::RTC.tpr.set_prescaler(0);
+    reg::RTC.tsr.set_seconds(time.to_unix());
+    reg::RTC.sr.set_tce(true);
+  }
+
+  fn set_alarm(&self, time: DateTime, handler: AlarmHandler) {
+    reg::RTC.ier.set_taie(false);
+    unsafe {
+      Alarm = Some(handler);
+    }
+
+    // Writing TAR also clears a pending alarm flag
+    reg::RTC.tar.set_seconds(time.to_unix());
+    reg::RTC.ier.set_taie(true);
+    nvic::enable_irq(RTC_ALARM_IRQ);
+  }
+
+  fn cancel_alarm(&self) {
+    reg::RTC.ier.set_taie(false);
+    nvic::disable_irq(RTC_ALARM_IRQ);
+    unsafe {
+      Alarm = None;
+    }
+  }
+
+  fn backup_register_count(&self) -> usize {
+    VBAT_REGISTER_COUNT
+  }
+
+  fn read_backup(&self, index: usize) -> u32 {
+    reg::VBAT.data[index].value()
+  }
+
+  fn write_backup(&self, index: usize, value: u32) {
+    reg::VBAT.data[index].set_value(value);
+  }
+}
+
+/// Acknowledges the RTC alarm interrupt and calls the alarm handler.
+///
+/// Unsafe because it reads the handler without a critical section, it must
+/// only be called from `isr_rtc_alarm`.
+pub unsafe fn handle_interrupt() {
+  if !reg::RTC.sr.taf() {
+    return;
+  }
+  // The alarm fires once, moving it away clears the flag
+  reg::RTC.tar.set_seconds(0);
+
+  match Alarm {
+    Some(handler) => handler(),
+    None => {},
+  }
+}
+
+#[allow(dead_code)]
+mod reg {
+  use volatile_cell::VolatileCell;
+  use core::ops::Drop;
+
+  ioregs!(RTC = {
+    /// Time Seconds Register
+    0x0 => reg32 tsr {
+      0..31 => seconds,
+    },
+
+    /// Time Prescaler Register
+    0x4 => reg32 tpr {
+      0..15 => prescaler,
+    },
+
+    /// Time Alarm Register
+    0x8 => reg32 tar {
+      0..31 => seconds,
+    },
+
+    /// Control Register
+    0x10 => reg32 cr {
+      0 => swr,            //= Software reset
+      8 => osce,           //= Oscillator enable
+    },
+
+    /// Status Register
+    0x14 => reg32 sr {
+      0 => tif: ro,        //= Time invalid
+      1 => tof: ro,        //= Time overflow
+      2 => taf: ro,        //= Time alarm
+      4 => tce,            //= Time counter enable
+    },
+
+    /// Interrupt Enable Register
+    0x1c => reg32 ier {
+      0 => tiie,           //= Time invalid interrupt enable
+      1 => toie,           //= Time overflow interrupt enable
+      2 => taie,           //= Time alarm interrupt enable
+      4 => tsie,           //= Time seconds interrupt enable
+    },
+  });
+
+  ioregs!(VBAT = {
+    /// VBAT Register File
+    0x0 => reg32 data[8] {
+      0..31 => value,
+    },
+  });
+
+  extern {
+    #[link_name="k20_iomem_RTC"] pub static RTC: RTC;
+    #[link_name="k20_iomem_VBAT"] pub static VBAT: VBAT;
+  }
+}
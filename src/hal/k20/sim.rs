@@ -100,6 +100,10 @@ pub mod reg {
       7      => adc0alttrgen,
     },
 
+    0x102c => reg32 scgc2 {
+      12     => dac0,
+    },
+
     0x1034 => reg32 scgc4 {
       1      => ewm,
       2      => cmt,
@@ -18,8 +18,9 @@ UART configuration.
 */
 
 use core::intrinsics::abort;
+use core::option::Option::{self, Some, None};
 
-use drivers::chario::CharIO;
+use drivers::chario::{CharIO, CharInput};
 use hal::uart;
 
 use self::UARTPeripheral::*;
@@ -122,6 +123,16 @@ impl CharIO for UART {
   }
 }
 
+impl CharInput for UART {
+  fn try_getc(&self) -> Option<char> {
+    if self.reg.s1.rdrf() {
+      Some(self.reg.d.re() as char)
+    } else {
+      None
+    }
+  }
+}
+
 /// Register definitions
 pub mod reg {
   use volatile_cell::VolatileCell;
@@ -14,7 +14,13 @@
 // limitations under the License.
 
 //! Watchdog for Kinetis SIM module.
+//!
+//! The watchdog is enabled out of reset. `init` has to be called early to
+//! either disable it or keep it enabled and allow it to be reconfigured with
+//! `Watchdog::start`, which clocks it from the 1 kHz low power oscillator.
 
+use hal::cortex_m4::irq::NoInterrupts;
+use hal::watchdog;
 use util::support::nop;
 
 #[path="../../util/ioreg.rs"] mod ioreg;
@@ -50,11 +56,45 @@ fn unlock() {
   nop();
 }
 
-/// Write refresh sequence to refresh watchdog
-pub fn refresh() {
-  use self::reg::WDOG_refresh_refresh::*;
-  reg::WDOG.refresh.set_refresh(RefreshSeq1);
-  reg::WDOG.refresh.set_refresh(RefreshSeq2);
+/// Watchdog timer
+#[derive(Clone, Copy)]
+pub struct Watchdog;
+
+impl Watchdog {
+  /// Returns the watchdog timer
+  pub fn new() -> Watchdog {
+    Watchdog
+  }
+}
+
+impl watchdog::Watchdog for Watchdog {
+  fn start(&self, timeout_ms: u32) {
+    let timeout = if timeout_ms < 4 { 4 } else { timeout_ms };
+
+    // the unlock sequence and the register updates have to complete within
+    // the watchdog's update window
+    let _crit = NoInterrupts::new();
+    unlock();
+    reg::WDOG.tovalh.set_toval((timeout >> 16) as u16);
+    reg::WDOG.tovall.set_toval(timeout as u16);
+    reg::WDOG.presc.set_prescval(0);
+    reg::WDOG.stctrlh
+      .set_clksrc(reg::WDOG_stctrlh_clksrc::Lpo)
+      .set_en(true);
+  }
+
+  /// Write refresh sequence to refresh watchdog
+  fn feed(&self) {
+    use self::reg::WDOG_refresh_refresh::*;
+    // both writes have to happen within 20 bus cycles
+    let _crit = NoInterrupts::new();
+    reg::WDOG.refresh.set_refresh(RefreshSeq1);
+    reg::WDOG.refresh.set_refresh(RefreshSeq2);
+  }
+
+  fn caused_reset(&self) -> bool {
+    reg::RCM.srs0.wdog()
+  }
 }
 
 #[allow(dead_code)]
@@ -67,10 +107,24 @@ mod reg {
     0x0 => reg16 stctrlh
     {
       0 => en,             //= Watchdog enable
+      1 => clksrc {        //! Watchdog clock source
+        0 => Lpo,
+        1 => Alternate,
+      },
       4 => allowupdate     //= Enables updates to watchdog write-once registers,
                            //= after the reset-triggered initial configuration window
     },
 
+    /// Timeout Value Register High
+    0x4 => reg16 tovalh {
+      0..15 => toval,
+    },
+
+    /// Timeout Value Register Low
+    0x6 => reg16 tovall {
+      0..15 => toval,
+    },
+
     /// Refresh Register
     0xc => reg16 refresh {
       0..15 => refresh: wo
@@ -89,10 +143,28 @@ mod reg {
       },
     },
 
+    /// Prescaler Register
+    0x16 => reg16 presc {
+      8..10 => prescval,   //= Clock divided by prescval + 1
+    },
+
+  });
+
+  ioregs!(RCM = {
+    /// System Reset Status Register 0
+    0x0 => reg8 srs0 {
+      0 => wakeup: ro,     //= Low leakage wakeup reset
+      1 => lvd: ro,        //= Low voltage detect reset
+      2 => loc: ro,        //= Loss of clock reset
+      5 => wdog: ro,       //= Watchdog reset
+      6 => pin: ro,        //= External reset pin
+      7 => por: ro,        //= Power on reset
+    },
   });
 
 
   extern {
     #[link_name="k20_iomem_WDOG"] pub static WDOG: WDOG;
+    #[link_name="k20_iomem_RCM"] pub static RCM: RCM;
   }
 }
@@ -0,0 +1,92 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ISR Data for lpc11xx
+
+use core::option::Option::{self, Some, None};
+
+extern {
+  fn isr_wakeup_0();
+  fn isr_wakeup_1();
+  fn isr_wakeup_2();
+  fn isr_wakeup_3();
+  fn isr_wakeup_4();
+  fn isr_wakeup_5();
+  fn isr_wakeup_6();
+  fn isr_wakeup_7();
+  fn isr_wakeup_8();
+  fn isr_wakeup_9();
+  fn isr_wakeup_10();
+  fn isr_wakeup_11();
+  fn isr_wakeup_12();
+  fn isr_can();
+  fn isr_ssp_1();
+  fn isr_i2c();
+  fn isr_timer16_0();
+  fn isr_timer16_1();
+  fn isr_timer32_0();
+  fn isr_timer32_1();
+  fn isr_ssp_0();
+  fn isr_uart();
+  fn isr_adc();
+  fn isr_wdt();
+  fn isr_bod();
+  fn isr_pio_3();
+  fn isr_pio_2();
+  fn isr_pio_1();
+  fn isr_pio_0();
+}
+
+#[allow(non_upper_case_globals)]
+const ISRCount: usize = 32;
+
+#[allow(non_upper_case_globals)]
+#[link_section=".isr_vector_nvic"]
+#[no_mangle]
+pub static NVICVectors: [Option<unsafe extern fn()>; ISRCount] = [
+  // s.a. lpc111x user manual (UM10398), chapter 6
+  Some(isr_wakeup_0),   // PIO0_0 start logic
+  Some(isr_wakeup_1),
+  Some(isr_wakeup_2),
+  Some(isr_wakeup_3),
+  Some(isr_wakeup_4),
+  Some(isr_wakeup_5),
+  Some(isr_wakeup_6),
+  Some(isr_wakeup_7),
+  Some(isr_wakeup_8),
+  Some(isr_wakeup_9),
+  Some(isr_wakeup_10),
+  Some(isr_wakeup_11),  // PIO0_11 start logic
+  Some(isr_wakeup_12),  // PIO1_0 start logic
+  Some(isr_can),
+  Some(isr_ssp_1),
+  Some(isr_i2c),
+  Some(isr_timer16_0),
+  Some(isr_timer16_1),
+  Some(isr_timer32_0),
+  Some(isr_timer32_1),
+  Some(isr_ssp_0),
+  Some(isr_uart),
+  None,                 // Reserved
+  None,                 // Reserved
+  Some(isr_adc),
+  Some(isr_wdt),
+  Some(isr_bod),
+  None,                 // Reserved
+  Some(isr_pio_3),
+  Some(isr_pio_2),
+  Some(isr_pio_1),
+  Some(isr_pio_0),
+];
@@ -0,0 +1,91 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+Peripheral clock management.
+
+Peripherals are clocked from the system clock and have to be enabled in
+SYSAHBCLKCTRL before use. SSP and UART have an additional divider.
+
+This module should be considered private until further notice.
+*/
+
+use core::intrinsics::abort;
+
+use super::system_clock::system_clock;
+use super::system_clock::reg::SYSCON;
+use self::PeripheralClock::*;
+
+/// Peripheral clocks, numbered by their SYSAHBCLKCTRL bit.
+#[allow(missing_docs)]
+#[derive(Clone, Copy)]
+pub enum PeripheralClock {
+  I2CClock    = 5,
+  GPIOClock   = 6,
+  CT16B0Clock = 7,
+  CT16B1Clock = 8,
+  CT32B0Clock = 9,
+  CT32B1Clock = 10,
+  SSP0Clock   = 11,
+  UARTClock   = 12,
+  ADCClock    = 13,
+  // reserved = 14,
+  WDTClock    = 15,
+  IOCONClock  = 16,
+  CANClock    = 17,
+  SSP1Clock   = 18,
+}
+
+impl PeripheralClock {
+  /// Enables the given peripheral clock.
+  pub fn enable(self) {
+    SYSCON.sysahbclkctrl.set_enable(self as usize, true);
+  }
+
+  /// Disables the given peripheral clock.
+  pub fn disable(self) {
+    SYSCON.sysahbclkctrl.set_enable(self as usize, false);
+  }
+
+  /// Returns the clock frequency based on active divisor.
+  pub fn frequency(self) -> u32 {
+    match self.get_divisor() {
+      0 => 0,
+      d => system_clock() / d as u32,
+    }
+  }
+
+  /// Returns the given peripheral clock divisor, zero if the peripheral clock
+  /// is gated by its divider.
+  pub fn get_divisor(self) -> u8 {
+    match self {
+      SSP0Clock => SYSCON.ssp0clkdiv.div() as u8,
+      UARTClock => SYSCON.uartclkdiv.div() as u8,
+      SSP1Clock => SYSCON.ssp1clkdiv.div() as u8,
+      _         => 1,
+    }
+  }
+
+  /// Sets the given peripheral clock divisor. Only SSP and UART clocks have a
+  /// divider.
+  pub fn set_divisor(self, divisor: u8) {
+    match self {
+      SSP0Clock => { SYSCON.ssp0clkdiv.set_div(divisor as u32); },
+      UARTClock => { SYSCON.uartclkdiv.set_div(divisor as u32); },
+      SSP1Clock => { SYSCON.ssp1clkdiv.set_div(divisor as u32); },
+      _         => unsafe { abort() },
+    }
+  }
+}
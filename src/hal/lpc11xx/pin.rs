@@ -0,0 +1,279 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+Pin configuration.
+
+Some pins that could be configured here may be missing from actual MCU depending
+on the package.
+
+The IOCON registers are laid out in no particular order, `iocon_index()` maps
+port and pin to the register. Output writes go through the masked data
+addresses, so setting one pin doesn't race with other pins of the same port.
+*/
+
+use core::intrinsics::abort;
+use core::option::Option;
+
+use hal::lpc11xx::peripheral_clock::PeripheralClock;
+
+use self::Port::*;
+
+/// Available port names.
+#[allow(missing_docs)]
+#[derive(Clone, Copy)]
+pub enum Port {
+  Port0,
+  Port1,
+  Port2,
+  Port3,
+}
+
+/// Pin functions (GPIO or up to three additional functions).
+///
+/// Alternative functions are numbered in the order of the IOCON function
+/// table, skipping GPIO. On pins that don't come up as GPIO out of reset
+/// (RESET, SWCLK, SWDIO and the R_ pins) `AltFunction1` is the reset function.
+#[derive(PartialEq, Clone, Copy)]
+#[allow(missing_docs)]
+pub enum Function {
+  Gpio         = 0,
+  AltFunction1 = 1,
+  AltFunction2 = 2,
+  AltFunction3 = 3,
+}
+
+/// Pin modes
+#[derive(PartialEq, Clone, Copy)]
+#[allow(missing_docs)]
+pub enum Mode {
+  Floating = 0,
+  PullDown = 1,
+  PullUp = 2,
+  Repeater = 3,
+}
+
+/// Structure to describe the location of a pin
+#[derive(Clone, Copy)]
+pub struct Pin {
+  /// Port the pin is attached to
+  port: Port,
+  /// Pin number in the port
+  pin: u8
+}
+
+impl Pin {
+  /// Create and setup a Pin
+  pub fn new(port: Port, pin_index: u8, function: Function,
+      gpiodir: Option<::hal::pin::GpioDirection>) -> Pin {
+    let pin = Pin {
+      port: port,
+      pin: pin_index,
+    };
+
+    PeripheralClock::IOCONClock.enable();
+    PeripheralClock::GPIOClock.enable();
+    pin.setup_regs(function, gpiodir);
+
+    pin
+  }
+
+  /// Sets the pull resistor mode.
+  pub fn set_mode(&self, mode: Mode) {
+    use self::reg::IOCON_pio_mode as m;
+    let val = match mode {
+      Mode::Floating => m::Floating,
+      Mode::PullDown => m::PullDown,
+      Mode::PullUp   => m::PullUp,
+      Mode::Repeater => m::Repeater,
+    };
+    reg::IOCON.pio[self.iocon_index()].set_mode(val);
+  }
+
+  fn setup_regs(&self, function: Function,
+      gpiodir: Option<::hal::pin::GpioDirection>) {
+    let func = function as u32;
+    let func = if self.gpio_is_alternative() {
+      match func {
+        0 => 1,
+        1 => 0,
+        f => f,
+      }
+    } else {
+      func
+    };
+    reg::IOCON.pio[self.iocon_index()].set_func(func);
+
+    match function {
+      Function::Gpio => (self as &::hal::pin::Gpio).set_direction(gpiodir.unwrap()),
+      _ => {},
+    }
+  }
+
+  /// Returns true if GPIO is function 1 of the pin, not function 0.
+  fn gpio_is_alternative(&self) -> bool {
+    match (self.port, self.pin) {
+      (Port0, 0) | (Port0, 10) | (Port0, 11) => true,
+      (Port1, 0...3) => true,
+      _ => false,
+    }
+  }
+
+  fn iocon_index(&self) -> usize {
+    match self.port {
+      Port0 => match self.pin {
+        0  => 0x0c / 4,
+        1  => 0x10 / 4,
+        2  => 0x1c / 4,
+        3  => 0x2c / 4,
+        4  => 0x30 / 4,
+        5  => 0x34 / 4,
+        6  => 0x4c / 4,
+        7  => 0x50 / 4,
+        8  => 0x60 / 4,
+        9  => 0x64 / 4,
+        10 => 0x68 / 4,
+        11 => 0x74 / 4,
+        _  => unsafe { abort() },
+      },
+      Port1 => match self.pin {
+        0  => 0x78 / 4,
+        1  => 0x7c / 4,
+        2  => 0x80 / 4,
+        3  => 0x90 / 4,
+        4  => 0x94 / 4,
+        5  => 0xa0 / 4,
+        6  => 0xa4 / 4,
+        7  => 0xa8 / 4,
+        8  => 0x14 / 4,
+        9  => 0x38 / 4,
+        10 => 0x6c / 4,
+        11 => 0x98 / 4,
+        _  => unsafe { abort() },
+      },
+      Port2 => match self.pin {
+        0  => 0x08 / 4,
+        1  => 0x28 / 4,
+        2  => 0x5c / 4,
+        3  => 0x8c / 4,
+        4  => 0x40 / 4,
+        5  => 0x44 / 4,
+        6  => 0x00 / 4,
+        7  => 0x20 / 4,
+        8  => 0x24 / 4,
+        9  => 0x54 / 4,
+        10 => 0x58 / 4,
+        11 => 0x70 / 4,
+        _  => unsafe { abort() },
+      },
+      Port3 => match self.pin {
+        0  => 0x84 / 4,
+        1  => 0x88 / 4,
+        2  => 0x9c / 4,
+        3  => 0xac / 4,
+        4  => 0x3c / 4,
+        5  => 0x48 / 4,
+        _  => unsafe { abort() },
+      },
+    }
+  }
+
+  fn gpioreg(&self) -> &'static reg::GPIO {
+    match self.port {
+      Port0 => &reg::GPIO0,
+      Port1 => &reg::GPIO1,
+      Port2 => &reg::GPIO2,
+      Port3 => &reg::GPIO3,
+    }
+  }
+}
+
+impl ::hal::pin::Gpio for Pin {
+  /// Sets output GPIO value to high.
+  fn set_high(&self) {
+    let mask = 1 << (self.pin as usize);
+    self.gpioreg().masked[mask].ignoring_state().set_data(mask as u32);
+  }
+
+  /// Sets output GPIO value to low.
+  fn set_low(&self) {
+    let mask = 1 << (self.pin as usize);
+    self.gpioreg().masked[mask].ignoring_state().set_data(0);
+  }
+
+  /// Returns input GPIO level.
+  fn level(&self) -> ::hal::pin::GpioLevel {
+    match self.gpioreg().data.data(self.pin as usize) {
+      false => ::hal::pin::Low,
+      true  => ::hal::pin::High,
+    }
+  }
+
+  /// Sets output GPIO direction.
+  fn set_direction(&self, new_mode: ::hal::pin::GpioDirection) {
+    use self::reg::GPIO_dir_dir as dir;
+    let val = match new_mode {
+      ::hal::pin::In  => dir::Input,
+      ::hal::pin::Out => dir::Output,
+    };
+    self.gpioreg().dir.set_dir(self.pin as usize, val);
+  }
+}
+
+mod reg {
+  use core::ops::Drop;
+  use volatile_cell::VolatileCell;
+
+  ioregs!(IOCON = {
+    0x0 => reg32 pio[44] {     //! Pin configuration, see `iocon_index()`
+      0..2 => func,            //= Pin function
+      3..4 => mode {           //= Pull resistor mode
+        0 => Floating,
+        1 => PullDown,
+        2 => PullUp,
+        3 => Repeater,
+      },
+      5    => hys,             //= Hysteresis
+      7    => admode {         //= Analog mode on AD pins
+        0 => Analog,
+        1 => Digital,
+      },
+      10   => od,              //= Pseudo open-drain
+    }
+  });
+
+  ioregs!(GPIO = {
+    0x0000 => reg32 masked[4095] { //! Data, masked by the array index
+      0..11 => data,
+    }
+    0x3ffc => reg32 data {     //! Data
+      0..11 => data[12],
+    }
+    0x8000 => reg32 dir {      //! Direction
+      0..11 => dir[12] {
+        0 => Input,
+        1 => Output,
+      }
+    }
+  });
+
+  extern {
+    #[link_name="lpc11xx_iomem_IOCON"] pub static IOCON: IOCON;
+    #[link_name="lpc11xx_iomem_GPIO0"] pub static GPIO0: GPIO;
+    #[link_name="lpc11xx_iomem_GPIO1"] pub static GPIO1: GPIO;
+    #[link_name="lpc11xx_iomem_GPIO2"] pub static GPIO2: GPIO;
+    #[link_name="lpc11xx_iomem_GPIO3"] pub static GPIO3: GPIO;
+  }
+}
@@ -0,0 +1,253 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+MCU initialisation and clock configuration.
+
+The main clock runs either straight from the selected oscillator or from the
+system PLL fed by it. The core, memories and AHB peripherals all run from the
+main clock.
+*/
+
+use core::intrinsics::abort;
+use core::option::Option::{self, Some, None};
+
+use util::support::nop;
+
+#[path="../../util/wait_for.rs"]
+#[macro_use] mod wait_for;
+
+/// Main clock source, also used as the PLL input.
+#[derive(Clone, Copy)]
+pub enum ClockSource {
+  /// Internal RC oscillator, 12MHz.
+  Internal,
+  /// System oscillator with external crystal of given frequency, 1 to 25MHz.
+  Main(u32),
+}
+
+/// System PLL configuration.
+///
+/// Frequency is calculated as
+///
+/// ```
+/// Fout = m * Fin
+/// Fcco = 2 * p * Fout
+/// ```
+///
+/// Fin has to be between 10MHz and 25MHz, Fcco between 156MHz and 320MHz, and
+/// Fout may not exceed 50MHz.
+#[derive(Clone, Copy)]
+pub struct PLL {
+  /// PLL multiplier, 1 to 32.
+  pub m: u8,
+  /// PLL post divider, one of 1, 2, 4 or 8.
+  pub p: u8,
+}
+
+/// MCU clock configuration.
+#[derive(Clone, Copy)]
+pub struct Clock {
+  /// Clocking source.
+  pub source: ClockSource,
+  /// PLL configuration.
+  pub pll: Option<PLL>,
+}
+
+#[allow(non_upper_case_globals)]
+static mut SystemClock: u32 = 0;
+
+/// Frequency of the internal RC oscillator, which runs the MCU out of reset.
+const IRC_FREQUENCY: u32 = 12_000_000;
+
+/// Returns system clock frequency according to configuration.
+#[inline(always)]
+pub fn system_clock() -> u32 {
+  match unsafe { SystemClock } {
+    0    => IRC_FREQUENCY,
+    freq => freq,
+  }
+}
+
+/// Initialise the system clock.
+pub fn init_clock(clock: &Clock) {
+  use self::ClockSource::*;
+  use self::reg::SYSCON_mainclksel_sel as MainClock;
+  use self::reg::SYSCON_syspllclksel_sel as PllInput;
+
+  let src_clock: u32 = match clock.source {
+    Internal =>   IRC_FREQUENCY,
+    Main(freq) => freq,
+  };
+  let dst_clock: u32 = match clock.pll {
+    Some(ref pll) => src_clock * pll.m as u32,
+    None => src_clock,
+  };
+
+  // Run from the IRC while the oscillator and the PLL are reconfigured.
+  select_main_clock(MainClock::Internal);
+  init_flash_access(IRC_FREQUENCY);
+
+  match clock.source {
+    Internal => select_pll_input(PllInput::Internal),
+    Main(freq) => {
+      init_system_oscillator(freq);
+      select_pll_input(PllInput::SystemOscillator);
+    },
+  }
+
+  init_flash_access(dst_clock);
+  match clock.pll {
+    Some(ref pll) => {
+      init_pll(pll);
+      select_main_clock(MainClock::PllOutput);
+    },
+    None => match clock.source {
+      Internal => (),
+      Main(_) => select_main_clock(MainClock::PllInput),
+    },
+  }
+  reg::SYSCON.sysahbclkdiv.set_div(1);
+
+  unsafe { SystemClock = dst_clock };
+}
+
+fn init_system_oscillator(freq: u32) {
+  reg::SYSCON.sysoscctrl
+    .set_bypass(false)
+    .set_freqrange(freq > 15_000_000);
+  reg::SYSCON.pdruncfg.set_sysosc_pd(false);
+
+  // There's no ready flag, the user manual asks for a 500us delay.
+  for _ in 0..0x400 {
+    nop();
+  }
+}
+
+fn init_flash_access(freq: u32) {
+  let wait_states: u32 = if freq > 40_000_000 { 2 } else
+                         if freq > 20_000_000 { 1 } else
+                         { 0 };
+  reg::FLASHCFG.flashcfg.set_flashtim(wait_states);
+}
+
+fn init_pll(pll: &PLL) {
+  let psel: u32 = match pll.p {
+    1 => 0,
+    2 => 1,
+    4 => 2,
+    8 => 3,
+    _ => unsafe { abort() },
+  };
+  if pll.m < 1 || pll.m > 32 {
+    unsafe { abort() };
+  }
+
+  reg::SYSCON.pdruncfg.set_syspll_pd(true);
+  reg::SYSCON.syspllctrl
+    .set_msel(pll.m as u32 - 1)
+    .set_psel(psel);
+  reg::SYSCON.pdruncfg.set_syspll_pd(false);
+  wait_for!(reg::SYSCON.syspllstat.lock());
+}
+
+fn select_pll_input(input: reg::SYSCON_syspllclksel_sel) {
+  reg::SYSCON.syspllclksel.set_sel(input);
+  reg::SYSCON.syspllclkuen.set_ena(false);
+  reg::SYSCON.syspllclkuen.set_ena(true);
+}
+
+fn select_main_clock(source: reg::SYSCON_mainclksel_sel) {
+  reg::SYSCON.mainclksel.set_sel(source);
+  reg::SYSCON.mainclkuen.set_ena(false);
+  reg::SYSCON.mainclkuen.set_ena(true);
+}
+
+// This mod is pub as SYSCON is also used by peripheral_clock.rs.
+#[allow(missing_docs)]
+pub mod reg {
+  use core::ops::Drop;
+  use volatile_cell::VolatileCell;
+
+  ioregs!(SYSCON = {
+    0x008 => reg32 syspllctrl {    //! System PLL control
+      0..4 => msel,                //= Feedback divider, M - 1
+      5..6 => psel,                //= Post divider, log2(P)
+    }
+    0x00c => reg32 syspllstat {    //! System PLL status
+      0 => lock: ro,
+    }
+    0x020 => reg32 sysoscctrl {    //! System oscillator control
+      0 => bypass,
+      1 => freqrange,              //= Crystal above 15MHz
+    }
+    0x040 => reg32 syspllclksel {  //! System PLL clock source
+      0..1 => sel {
+        0 => Internal,
+        1 => SystemOscillator,
+      },
+    }
+    0x044 => reg32 syspllclkuen {  //! System PLL clock source update
+      0 => ena,
+    }
+    0x070 => reg32 mainclksel {    //! Main clock source
+      0..1 => sel {
+        0 => Internal,
+        1 => PllInput,
+        2 => WatchdogOscillator,
+        3 => PllOutput,
+      },
+    }
+    0x074 => reg32 mainclkuen {    //! Main clock source update
+      0 => ena,
+    }
+    0x078 => reg32 sysahbclkdiv {  //! System clock divider
+      0..7 => div,
+    }
+    0x080 => reg32 sysahbclkctrl { //! System clock gating
+      0..18 => enable[19],
+    }
+    0x094 => reg32 ssp0clkdiv {    //! SSP0 clock divider, 0 gates the clock
+      0..7 => div,
+    }
+    0x098 => reg32 uartclkdiv {    //! UART clock divider, 0 gates the clock
+      0..7 => div,
+    }
+    0x09c => reg32 ssp1clkdiv {    //! SSP1 clock divider, 0 gates the clock
+      0..7 => div,
+    }
+    0x238 => reg32 pdruncfg {      //! Power-down configuration, set bits are off
+      0 => ircout_pd,
+      1 => irc_pd,
+      2 => flash_pd,
+      3 => bod_pd,
+      4 => adc_pd,
+      5 => sysosc_pd,
+      6 => wdtosc_pd,
+      7 => syspll_pd,
+    }
+  });
+
+  ioregs!(FLASHCFG = {
+    0x0 => reg32 flashcfg {        //! Flash configuration
+      0..1 => flashtim,            //= Flash access time in clocks, minus one
+    }
+  });
+
+  extern {
+    #[link_name="lpc11xx_iomem_SYSCON"] pub static SYSCON: SYSCON;
+    #[link_name="lpc11xx_iomem_FLASHCFG"] pub static FLASHCFG: FLASHCFG;
+  }
+}
@@ -0,0 +1,172 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+Timer configuration.
+
+This code supports the two 16-bit (CT16B0, CT16B1) and the two 32-bit
+(CT32B0, CT32B1) counter/timers. The prescaler of the 16-bit timers is 16 bits
+wide as well, so they can't divide the system clock by more than 65536.
+
+`get_counter()` of a 16-bit timer wraps at 65536, `wait_us()` accounts for
+that as long as it is called for the full wait.
+*/
+
+use core::intrinsics::abort;
+
+use hal::lpc11xx::peripheral_clock::PeripheralClock;
+use hal::timer;
+
+use self::TimerPeripheral::*;
+
+/// Available timer peripherals.
+#[allow(missing_docs)]
+#[derive(Clone, Copy)]
+pub enum TimerPeripheral {
+  Timer16_0,
+  Timer16_1,
+  Timer32_0,
+  Timer32_1,
+}
+
+impl TimerPeripheral {
+  fn reg(self) -> &'static reg::TIMER {
+    match self {
+      Timer16_0 => &reg::CT16B0,
+      Timer16_1 => &reg::CT16B1,
+      Timer32_0 => &reg::CT32B0,
+      Timer32_1 => &reg::CT32B1,
+    }
+  }
+
+  fn peripheral_clock(self) -> PeripheralClock {
+    match self {
+      Timer16_0 => PeripheralClock::CT16B0Clock,
+      Timer16_1 => PeripheralClock::CT16B1Clock,
+      Timer32_0 => PeripheralClock::CT32B0Clock,
+      Timer32_1 => PeripheralClock::CT32B1Clock,
+    }
+  }
+
+  fn counter_mask(self) -> u32 {
+    match self {
+      Timer16_0 | Timer16_1 => 0xffff,
+      Timer32_0 | Timer32_1 => 0xffff_ffff,
+    }
+  }
+}
+
+/// Struct describing a timer instance.
+#[derive(Clone, Copy)]
+pub struct Timer {
+  peripheral: TimerPeripheral,
+  reg: &'static reg::TIMER,
+}
+
+impl Timer {
+  /// Create and start a timer, counting once every `prescale` system clock
+  /// cycles.
+  pub fn new(peripheral: TimerPeripheral, prescale: u32) -> Timer {
+    if prescale == 0 || prescale - 1 > peripheral.counter_mask() {
+      unsafe { abort() };
+    }
+
+    let reg = peripheral.reg();
+    peripheral.peripheral_clock().enable();
+
+    reg.ctcr.set_mode(reg::TIMER_ctcr_mode::Timer);
+    reg.tcr.set_crst(true);
+    reg.pr.set_value(prescale - 1);
+    reg.tcr
+      .set_crst(false)
+      .set_cen(true);
+
+    Timer {
+      peripheral: peripheral,
+      reg: reg,
+    }
+  }
+}
+
+impl timer::Timer for Timer {
+  #[inline(always)]
+  fn get_counter(&self) -> u32 {
+    self.reg.tc.value()
+  }
+
+  fn wait_us(&self, us: u32) {
+    let mask = self.peripheral.counter_mask();
+    let mut last = self.reg.tc.value();
+    let mut remaining = us;
+    while remaining > 0 {
+      let now = self.reg.tc.value();
+      remaining = remaining.saturating_sub(now.wrapping_sub(last) & mask);
+      last = now;
+    }
+  }
+}
+
+mod reg {
+  use core::ops::Drop;
+  use volatile_cell::VolatileCell;
+
+  ioregs!(TIMER = {
+    0x00 => reg32 ir {         //! Interrupt flags
+      0..3 => mr[4]: set_to_clear,   //= Match channel interrupt
+      4    => cr0: set_to_clear,     //= Capture channel interrupt
+    }
+    0x04 => reg32 tcr {        //! Timer control
+      0 => cen,                //= Counter enable
+      1 => crst,               //= Counter reset
+    }
+    0x08 => reg32 tc {         //! Timer counter
+      0..31 => value,
+    }
+    0x0c => reg32 pr {         //! Prescale register
+      0..31 => value,
+    }
+    0x10 => reg32 pc {         //! Prescale counter
+      0..31 => value,
+    }
+    0x14 => reg32 mcr {        //! Match control
+      0..11 => value,
+    }
+    0x18 => reg32 mr[4] {      //! Match registers
+      0..31 => value,
+    }
+    0x28 => reg32 ccr {        //! Capture control
+      0..2 => value,
+    }
+    0x2c => reg32 cr0 {        //! Capture register
+      0..31 => value: ro,
+    }
+    0x70 => reg32 ctcr {       //! Count control
+      0..1 => mode {
+        0 => Timer,            //= Count on every prescaler overflow
+        1 => CounterRising,    //= Count on rising edges of the capture input
+        2 => CounterFalling,
+        3 => CounterBoth,
+      },
+      2..3 => input,           //= Capture input used as counter clock
+    }
+  });
+
+  extern {
+    #[link_name="lpc11xx_iomem_CT16B0"] pub static CT16B0: TIMER;
+    #[link_name="lpc11xx_iomem_CT16B1"] pub static CT16B1: TIMER;
+    #[link_name="lpc11xx_iomem_CT32B0"] pub static CT32B0: TIMER;
+    #[link_name="lpc11xx_iomem_CT32B1"] pub static CT32B1: TIMER;
+  }
+}
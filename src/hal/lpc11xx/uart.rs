@@ -0,0 +1,200 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+UART configuration.
+
+The MCU has a single 16550-style UART. RXD and TXD have to be routed to pins
+separately, e.g. P1.6 and P1.7 to `AltFunction1`.
+*/
+
+use core::intrinsics::abort;
+use core::option::Option::{self, Some, None};
+
+use drivers::chario::{CharIO, CharInput};
+use hal::lpc11xx::peripheral_clock::PeripheralClock::UARTClock;
+use hal::uart;
+
+#[path="../../util/wait_for.rs"]
+#[macro_use] mod wait_for;
+
+/// Structure describing the UART.
+#[derive(Clone, Copy)]
+pub struct UART {
+  reg: &'static reg::UART,
+}
+
+impl UART {
+  /// Create and setup the UART.
+  pub fn new(baudrate: u32, word_len: u8, parity: uart::Parity,
+      stop_bits: u8) -> UART {
+    let uart = UART {
+      reg: &reg::UART,
+    };
+
+    UARTClock.enable();
+    UARTClock.set_divisor(1);
+
+    uart.set_baud_rate(baudrate);
+    uart.set_mode(word_len, parity, stop_bits);
+    uart.reg.fcr
+      .set_fifoen(true)
+      .set_rxfifores(true)
+      .set_txfifores(true);
+
+    uart
+  }
+
+  fn set_baud_rate(&self, baud_rate: u32) {
+    let (dl, div_add_val, mul_val) =
+        calculate_divisors(UARTClock.frequency(), baud_rate);
+
+    self.reg.lcr.set_dlab(true);
+    self.reg.buffer.ignoring_state().set_data(dl & 0xff);
+    self.reg.ier.ignoring_state().set_value(dl >> 8);
+    self.reg.lcr.set_dlab(false);
+    self.reg.fdr
+      .set_divaddval(div_add_val)
+      .set_mulval(mul_val);
+  }
+
+  fn set_mode(&self, word_len: u8, parity: uart::Parity, stop_bits: u8) {
+    use self::reg::UART_lcr_ps as ps;
+
+    let wls = match word_len {
+      5...8 => word_len as u32 - 5,
+      _     => unsafe { abort() },
+    };
+    let sbs = match stop_bits {
+      1 => false,
+      2 => true,
+      _ => unsafe { abort() },
+    };
+    let (pe, select) = match parity {
+      uart::Parity::Disabled => (false, ps::Odd),
+      uart::Parity::Odd      => (true,  ps::Odd),
+      uart::Parity::Even     => (true,  ps::Even),
+      uart::Parity::Forced1  => (true,  ps::Forced1),
+      uart::Parity::Forced0  => (true,  ps::Forced0),
+    };
+
+    self.reg.lcr
+      .set_wls(wls)
+      .set_sbs(sbs)
+      .set_pe(pe)
+      .set_ps(select);
+  }
+}
+
+/// Finds the divisor latch and fractional divider values closest to
+/// `baud_rate`.
+///
+/// The resulting rate is `pclk / (16 * dl * (1 + div_add_val / mul_val))`.
+fn calculate_divisors(pclk: u32, baud_rate: u32) -> (u32, u32, u32) {
+  let mut best = (pclk / (16 * baud_rate), 0, 1);
+  let mut best_err = baud_rate;
+
+  for mul_val in 1..16 {
+    for div_add_val in 0..mul_val {
+      let den = 16 * baud_rate * (mul_val + div_add_val);
+      let dl = (pclk * mul_val + den / 2) / den;
+      // DLL has to be at least 3 with the fractional divider active.
+      if dl == 0 || dl > 0xffff || (div_add_val > 0 && dl < 3) {
+        continue;
+      }
+
+      let actual = pclk * mul_val / (16 * dl * (mul_val + div_add_val));
+      let err = if actual > baud_rate {
+        actual - baud_rate
+      } else {
+        baud_rate - actual
+      };
+      if err < best_err {
+        best = (dl, div_add_val, mul_val);
+        best_err = err;
+      }
+    }
+  }
+  best
+}
+
+impl CharIO for UART {
+  fn putc(&self, value: char) {
+    wait_for!(self.reg.lsr.thre());
+    // Reading RBR would pop the RX FIFO.
+    self.reg.buffer.ignoring_state().set_data(value as u32);
+  }
+}
+
+impl CharInput for UART {
+  fn try_getc(&self) -> Option<char> {
+    if self.reg.lsr.rdr() {
+      Some(self.reg.buffer.data() as u8 as char)
+    } else {
+      None
+    }
+  }
+}
+
+mod reg {
+  use core::ops::Drop;
+  use volatile_cell::VolatileCell;
+
+  ioregs!(UART = {
+    0x00 => reg32 buffer {     //! RBR and THR, DLL with DLAB set
+      0..7 => data,
+    }
+    0x04 => reg32 ier {        //! Interrupt enable, DLM with DLAB set
+      0..7 => value,
+    }
+    0x08 => reg32 fcr {        //! FIFO control
+      0    => fifoen: wo,
+      1    => rxfifores: wo,
+      2    => txfifores: wo,
+      6..7 => rxtl: wo,        //= RX trigger level
+    }
+    0x0c => reg32 lcr {        //! Line control
+      0..1 => wls,             //= Word length, minus five
+      2    => sbs,             //= Two stop bits
+      3    => pe,              //= Parity enable
+      4..5 => ps {             //= Parity select
+        0 => Odd,
+        1 => Even,
+        2 => Forced1,
+        3 => Forced0,
+      },
+      6    => bc,              //= Break control
+      7    => dlab,            //= Divisor latch access
+    }
+    0x14 => reg32 lsr {        //! Line status
+      0 => rdr: ro,            //= Receiver data ready
+      1 => oe: ro,             //= Overrun error
+      2 => pe: ro,             //= Parity error
+      3 => fe: ro,             //= Framing error
+      4 => bi: ro,             //= Break interrupt
+      5 => thre: ro,           //= Transmitter holding register empty
+      6 => temt: ro,           //= Transmitter empty
+      7 => rxfe: ro,           //= Error in RX FIFO
+    }
+    0x28 => reg32 fdr {        //! Fractional divider
+      0..3 => divaddval,
+      4..7 => mulval,
+    }
+  });
+
+  extern {
+    #[link_name="lpc11xx_iomem_UART"] pub static UART: UART;
+  }
+}
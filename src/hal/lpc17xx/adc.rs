@@ -0,0 +1,256 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+ADC configuration.
+
+The converter has eight channels, AD0.0 to AD0.7. The pins have to be switched
+to their `ad0_N` function separately.
+
+Scans and continuous conversions use the burst mode, which converts every
+selected channel in order of channel number. Continuous conversions report
+their results from the ADC interrupt, applications using them define `isr_adc`
+and call `handle_interrupt()` from it.
+*/
+
+use core::cell::Cell;
+use core::intrinsics::abort;
+use core::option::Option::{self, Some, None};
+
+use hal::adc;
+use hal::adc::{Averaging, ConversionHandler};
+use hal::cortex_m3::irq::NoInterrupts;
+use hal::cortex_m3::nvic;
+use hal::lpc17xx::peripheral_clock::PeripheralClock;
+
+#[path="../../util/ioreg.rs"]
+#[macro_use] mod ioreg;
+
+/// Maximum ADC clock frequency.
+const MAX_ADC_CLOCK: u32 = 13_000_000;
+
+const CHANNEL_COUNT: usize = 8;
+
+const ADC_IRQ: usize = 22;
+
+#[allow(non_upper_case_globals)]
+static CRBurst: u32 = 1 << 16;
+#[allow(non_upper_case_globals)]
+static CRPowerUp: u32 = 1 << 21;
+#[allow(non_upper_case_globals)]
+static CRStartNow: u32 = 1 << 24;
+#[allow(non_upper_case_globals)]
+static CRStartMask: u32 = 0b111 << 24;
+
+#[allow(non_upper_case_globals)]
+static DRDone: u32 = 1 << 31;
+
+#[allow(non_upper_case_globals)]
+static mut Handler: Option<ConversionHandler> = None;
+
+/// The analog to digital converter.
+pub struct ADC {
+  reg: &'static reg::ADC,
+  reference_mv: u32,
+  averaging: Cell<Averaging>,
+}
+
+impl ADC {
+  /// Powers up the ADC. `reference_mv` is the voltage on VREFP in millivolts.
+  pub fn new(reference_mv: u32) -> ADC {
+    let clock = PeripheralClock::ADCClock;
+    clock.enable();
+    clock.set_divisor(1);
+
+    let pclk = clock.frequency();
+    let clkdiv = (pclk + MAX_ADC_CLOCK - 1) / MAX_ADC_CLOCK - 1;
+
+    let reg = &reg::ADC;
+    reg.set_INTEN(0);
+    reg.set_CR((clkdiv << 8) | CRPowerUp);
+
+    ADC {
+      reg: reg,
+      reference_mv: reference_mv,
+      averaging: Cell::new(Averaging::NoAveraging),
+    }
+  }
+
+  /// Sets the selected channels, leaving the clock divisor and power bits.
+  fn select(&self, mask: u32, flags: u32) {
+    let cr = self.reg.CR() & !(0xFF | CRBurst | CRStartMask);
+    self.reg.set_CR(cr | mask | flags);
+  }
+
+  fn stop_burst(&self) {
+    self.reg.set_CR(self.reg.CR() & !CRBurst);
+  }
+}
+
+fn channel_mask(channels: &[u8]) -> u32 {
+  let mut mask = 0;
+  for &channel in channels {
+    if channel as usize >= CHANNEL_COUNT {
+      unsafe { abort() };
+    }
+    mask |= 1 << channel;
+  }
+  mask
+}
+
+/// Waits for a result on the channel and returns it.
+fn wait_result(reg: &reg::ADC, channel: usize) -> u16 {
+  let mut dr;
+  loop {
+    dr = reg.DR(channel);
+    if dr & DRDone != 0 {
+      break;
+    }
+  }
+  ((dr >> 4) & 0xFFF) as u16
+}
+
+impl adc::Adc for ADC {
+  fn channel_count(&self) -> usize {
+    CHANNEL_COUNT
+  }
+
+  fn resolution(&self) -> u8 {
+    12
+  }
+
+  fn reference_mv(&self) -> u32 {
+    self.reference_mv
+  }
+
+  fn set_averaging(&self, averaging: Averaging) {
+    self.averaging.set(averaging);
+  }
+
+  fn read_channel(&self, channel: u8) -> u16 {
+    let mask = channel_mask(&[channel]);
+    let samples = self.averaging.get().samples();
+
+    let mut sum: u32 = 0;
+    for _ in 0..samples {
+      self.select(mask, CRStartNow);
+      sum += wait_result(self.reg, channel as usize) as u32;
+    }
+    self.select(0, 0);
+
+    (sum / samples) as u16
+  }
+
+  /// Converts the channels in burst mode.
+  fn scan(&self, channels: &[u8], results: &mut [u16]) {
+    let mask = channel_mask(channels);
+    let samples = self.averaging.get().samples();
+    let mut sums = [0u32; CHANNEL_COUNT];
+
+    // Discard stale results
+    for channel in 0..CHANNEL_COUNT {
+      self.reg.DR(channel);
+    }
+
+    self.select(mask, CRBurst);
+    for _ in 0..samples {
+      for channel in 0..CHANNEL_COUNT {
+        if mask & (1 << channel) != 0 {
+          sums[channel] += wait_result(self.reg, channel) as u32;
+        }
+      }
+    }
+    self.stop_burst();
+    self.select(0, 0);
+
+    for (channel, result) in channels.iter().zip(results.iter_mut()) {
+      *result = (sums[*channel as usize] / samples) as u16;
+    }
+  }
+}
+
+impl adc::ContinuousAdc for ADC {
+  /// Starts burst mode on the channels. Averaging doesn't apply, each sample
+  /// is reported.
+  fn start_continuous(&self, channels: &[u8], handler: ConversionHandler) {
+    let mask = channel_mask(channels);
+    {
+      let _crit = NoInterrupts::new();
+      unsafe { Handler = Some(handler) };
+    }
+    self.reg.set_INTEN(mask);
+    nvic::enable_irq(ADC_IRQ);
+    self.select(mask, CRBurst);
+  }
+
+  fn stop_continuous(&self) {
+    self.stop_burst();
+    self.reg.set_INTEN(0);
+    self.select(0, 0);
+    nvic::disable_irq(ADC_IRQ);
+  }
+}
+
+/// Services the interrupt of continuous conversions.
+///
+/// Unsafe because it reads the handler without a critical section, it must
+/// only be called from `isr_adc`.
+pub unsafe fn handle_interrupt() {
+  let reg = &reg::ADC;
+  let done = reg.STAT() & 0xFF;
+
+  for channel in 0..CHANNEL_COUNT {
+    if done & (1 << channel) == 0 {
+      continue;
+    }
+    // Reading the data register clears the interrupt
+    let value = ((reg.DR(channel) >> 4) & 0xFFF) as u16;
+    match Handler {
+      Some(handler) => handler(channel as u8, value),
+      None => {},
+    }
+  }
+}
+
+mod reg {
+  use volatile_cell::VolatileCell;
+
+  ioreg_old!(ADC: u32, CR, GDR, _pad, INTEN, DR0, DR1, DR2, DR3, DR4, DR5, DR6,
+      DR7, STAT, TRM);
+  reg_rw!(ADC, u32, CR, set_CR, CR);
+  reg_r!(ADC, u32, GDR, GDR);
+  reg_rw!(ADC, u32, INTEN, set_INTEN, INTEN);
+  reg_r!(ADC, u32, STAT, STAT);
+
+  impl ADC {
+    #[allow(non_snake_case)]
+    pub fn DR(&self, channel: usize) -> u32 {
+      match channel {
+        0 => self.DR0.get(),
+        1 => self.DR1.get(),
+        2 => self.DR2.get(),
+        3 => self.DR3.get(),
+        4 => self.DR4.get(),
+        5 => self.DR5.get(),
+        6 => self.DR6.get(),
+        _ => self.DR7.get(),
+      }
+    }
+  }
+
+  extern {
+    #[link_name="lpc17xx_iomem_ADC"] pub static ADC: ADC;
+  }
+}
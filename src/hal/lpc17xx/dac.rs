@@ -0,0 +1,85 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+DAC configuration.
+
+The 10-bit converter drives AOUT on P0.26, which is set up by `DAC::new`. The
+DAC is always powered and runs from its reset clock divisor.
+*/
+
+use core::option::Option::None;
+
+use hal::dac;
+use hal::lpc17xx::pin::{Pin, Port, Function};
+
+#[path="../../util/ioreg.rs"]
+#[macro_use] mod ioreg;
+
+/// The digital to analog converter.
+#[derive(Clone, Copy)]
+pub struct DAC {
+  reference_mv: u32,
+}
+
+impl DAC {
+  /// Configures the AOUT pin and sets the output to 0. `reference_mv` is the
+  /// voltage on VREFP in millivolts.
+  pub fn new(reference_mv: u32) -> DAC {
+    Pin::new(Port::Port0, 26, Function::AltFunction2, None);
+    write_raw(0);
+
+    DAC {
+      reference_mv: reference_mv,
+    }
+  }
+}
+
+fn write_raw(value: u16) {
+  reg::DAC.set_CR(((value as u32) & 0x3FF) << 6);
+}
+
+impl dac::Dac for DAC {
+  fn resolution(&self) -> u8 {
+    10
+  }
+
+  fn reference_mv(&self) -> u32 {
+    self.reference_mv
+  }
+
+  fn write(&self, value: u16) {
+    write_raw(value);
+  }
+}
+
+impl dac::Waveform for DAC {
+  fn output(value: u16) {
+    write_raw(value);
+  }
+}
+
+mod reg {
+  use volatile_cell::VolatileCell;
+
+  ioreg_old!(DAC: u32, CR, CTRL, CNTVAL);
+  reg_rw!(DAC, u32, CR,     set_CR,     CR);
+  reg_rw!(DAC, u32, CTRL,   set_CTRL,   CTRL);
+  reg_rw!(DAC, u32, CNTVAL, set_CNTVAL, CNTVAL);
+
+  extern {
+    #[link_name="lpc17xx_iomem_DAC"] pub static DAC: DAC;
+  }
+}
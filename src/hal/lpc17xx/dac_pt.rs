@@ -0,0 +1,93 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::rc::Rc;
+use syntax::ext::base::ExtCtxt;
+
+use builder::{Builder, TokenString, add_node_dependency};
+use node;
+
+pub fn attach(_: &mut Builder, _: &mut ExtCtxt, node: Rc<node::Node>) {
+  node.materializer.set(Some(verify as fn(&mut Builder, &mut ExtCtxt, Rc<node::Node>)));
+  for sub in node.subnodes().iter() {
+    add_node_dependency(&node, sub);
+    sub.materializer.set(Some(build_dac as fn(&mut Builder, &mut ExtCtxt, Rc<node::Node>)));
+  }
+}
+
+pub fn verify(_: &mut Builder, cx: &mut ExtCtxt, node: Rc<node::Node>) {
+  node.expect_no_attributes(cx);
+}
+
+fn build_dac(builder: &mut Builder, cx: &mut ExtCtxt, node: Rc<node::Node>) {
+  if !node.expect_attributes(cx, &[("reference_mv", node::IntAttribute)]) {
+    return
+  }
+
+  if node.name.is_none() {
+    cx.parse_sess().span_diagnostic.span_err(node.name_span,
+        "DAC node must have a name");
+    return
+  }
+
+  match node.path.as_str().parse::<usize>() {
+    Ok(0) => (),
+    _ => {
+      cx.parse_sess().span_diagnostic.span_err(node.path_span,
+          format!("unknown DAC `{}`, allowed values: 0",
+              node.path).as_str());
+      return
+    }
+  }
+
+  let name = TokenString(node.name.clone().unwrap());
+  let reference_mv: u32 = node.get_int_attr("reference_mv").unwrap() as u32;
+
+  node.set_type_name("zinc::hal::lpc17xx::dac::DAC".to_string());
+
+  let st = quote_stmt!(&*cx,
+      let $name = zinc::hal::lpc17xx::dac::DAC::new($reference_mv);
+  ).unwrap();
+  builder.add_main_statement(st);
+}
+
+#[cfg(test)]
+mod test {
+  use builder::Builder;
+  use test_helpers::{assert_equal_source, with_parsed, fails_to_build};
+
+  #[test]
+  fn builds_dac() {
+    with_parsed("
+      dac {
+        aout@0 {
+          reference_mv = 3300;
+        }
+      }", |cx, failed, pt| {
+      let mut builder = Builder::new(pt.clone(), cx);
+      super::build_dac(&mut builder, cx, pt.get_by_name("aout").unwrap());
+      assert!(unsafe{*failed} == false);
+      assert!(builder.main_stmts().len() == 1);
+
+      assert_equal_source(&builder.main_stmts()[0],
+          "let aout = zinc::hal::lpc17xx::dac::DAC::new(3300u32);");
+    });
+  }
+
+  #[test]
+  fn fails_on_unknown_dac() {
+    fails_to_build("lpc17xx@mcu { dac { aout@1 { reference_mv = 3300; } } }");
+  }
+}
@@ -0,0 +1,323 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+GPDMA configuration.
+
+The controller has eight channels, channel 0 having the highest priority.
+Transfers are described by `Descriptor`s, which can be chained into linked
+lists. Descriptors and buffers must live in memory reachable by the controller
+(not the local SRAM at 0x10000000 on some parts) and stay valid until the
+transfer completes.
+
+Completion callbacks are called from `handle_interrupt()`, applications using
+them define `isr_dma` and call it from there.
+*/
+
+use core::intrinsics::abort;
+
+use hal::cortex_m3::irq::NoInterrupts;
+use hal::cortex_m3::nvic;
+use hal::dma;
+use hal::lpc17xx::peripheral_clock::PeripheralClock::GPDMAClock;
+
+#[path="../../util/ioreg.rs"]
+#[macro_use] mod ioreg;
+
+/// Maximum number of elements moved by a single descriptor.
+pub const MAX_TRANSFER_SIZE: usize = 4095;
+
+/// Peripheral DMA request lines.
+#[allow(missing_docs)]
+#[derive(Clone, Copy)]
+pub enum Request {
+  SSP0Tx  = 0,
+  SSP0Rx  = 1,
+  SSP1Tx  = 2,
+  SSP1Rx  = 3,
+  ADC     = 4,
+  I2S0    = 5,
+  I2S1    = 6,
+  DAC     = 7,
+  UART0Tx = 8,
+  UART0Rx = 9,
+  UART1Tx = 10,
+  UART1Rx = 11,
+  UART2Tx = 12,
+  UART2Rx = 13,
+  UART3Tx = 14,
+  UART3Rx = 15,
+}
+
+/// Transfer direction and flow control.
+#[derive(Clone, Copy)]
+pub enum Flow {
+  /// Memory to memory, runs at full speed.
+  MemoryToMemory,
+  /// Memory to peripheral, paced by the peripheral request.
+  MemoryToPeripheral(Request),
+  /// Peripheral to memory, paced by the peripheral request.
+  PeripheralToMemory(Request),
+}
+
+/// Transfer descriptor, laid out as a GPDMA linked list item.
+#[repr(C)]
+pub struct Descriptor {
+  src: u32,
+  dst: u32,
+  next: u32,
+  control: u32,
+}
+
+impl Descriptor {
+  /// Creates a descriptor moving `count` elements of `width` from `src` to
+  /// `dst`.
+  ///
+  /// `count` must not exceed `MAX_TRANSFER_SIZE`.
+  pub fn new(src: dma::Endpoint, dst: dma::Endpoint, count: usize,
+      width: dma::Width) -> Descriptor {
+    if count > MAX_TRANSFER_SIZE {
+      unsafe { abort() };
+    }
+
+    let width = match width {
+      dma::Width::Bits8  => 0,
+      dma::Width::Bits16 => 1,
+      dma::Width::Bits32 => 2,
+    };
+    let control = count as u32 |
+        (width << ControlSWidthShift) |
+        (width << ControlDWidthShift) |
+        if src.increments() { ControlSrcIncrement } else { 0 } |
+        if dst.increments() { ControlDstIncrement } else { 0 } |
+        ControlTCInterrupt;
+
+    Descriptor {
+      src: src.address(),
+      dst: dst.address(),
+      next: 0,
+      control: control,
+    }
+  }
+
+  /// Continues the transfer with `next` once this descriptor is done.
+  ///
+  /// Only the last descriptor of a list signals completion.
+  pub fn link(&mut self, next: &'static Descriptor) {
+    self.next = next as *const Descriptor as u32;
+    self.control &= !ControlTCInterrupt;
+  }
+}
+
+/// Structure describing a GPDMA channel.
+#[derive(Clone, Copy)]
+pub struct Channel {
+  index: usize,
+  reg: &'static reg::DMACH,
+}
+
+impl Channel {
+  /// Create a channel, enabling the controller if needed.
+  pub fn new(index: usize) -> Channel {
+    let reg = match index {
+      0 => &reg::DMACH0,
+      1 => &reg::DMACH1,
+      2 => &reg::DMACH2,
+      3 => &reg::DMACH3,
+      4 => &reg::DMACH4,
+      5 => &reg::DMACH5,
+      6 => &reg::DMACH6,
+      7 => &reg::DMACH7,
+      _ => unsafe { abort() },
+    };
+
+    GPDMAClock.enable();
+    reg::GPDMA.set_Config(1);
+    nvic::enable_irq(DMAIrq);
+
+    Channel {
+      index: index,
+      reg: reg,
+    }
+  }
+
+  fn mask(&self) -> u32 {
+    1 << self.index
+  }
+
+  /// Sets the function called when a transfer on this channel completes.
+  ///
+  /// Applies to transfers started afterwards. The callback is called from
+  /// `handle_interrupt()`, which the application's `isr_dma` has to call.
+  pub fn set_callback(&self, callback: Option<dma::Callback>) {
+    let _crit = NoInterrupts::new();
+    unsafe { Callbacks[self.index] = callback };
+  }
+
+  /// Starts the transfer described by `first` and the descriptors linked to
+  /// it.
+  ///
+  /// Any transfer in progress on this channel is aborted first. The caller
+  /// must keep all buffers and linked descriptors valid until the transfer
+  /// completes, `first` itself is copied and can be dropped.
+  pub unsafe fn start(&self, first: &Descriptor, flow: Flow) {
+    self.abort();
+
+    {
+      let _crit = NoInterrupts::new();
+      Failed &= !self.mask();
+    }
+    reg::GPDMA.set_IntTCClear(self.mask());
+    reg::GPDMA.set_IntErrClr(self.mask());
+
+    let (transfer_type, src_request, dst_request) = match flow {
+      Flow::MemoryToMemory => (0, 0, 0),
+      Flow::MemoryToPeripheral(req) => (1, 0, req as u32),
+      Flow::PeripheralToMemory(req) => (2, req as u32, 0),
+    };
+    let interrupts = if Callbacks[self.index].is_some() {
+      ConfigErrorInterrupt | ConfigTCInterrupt
+    } else {
+      0
+    };
+
+    self.reg.set_SrcAddr(first.src);
+    self.reg.set_DestAddr(first.dst);
+    self.reg.set_LLI(first.next);
+    self.reg.set_Control(first.control);
+    self.reg.set_Config(ConfigEnable |
+        (src_request << 1) |
+        (dst_request << 6) |
+        (transfer_type << 11) |
+        interrupts);
+  }
+}
+
+impl dma::Channel for Channel {
+  fn is_busy(&self) -> bool {
+    reg::GPDMA.EnbldChns() & self.mask() != 0
+  }
+
+  fn has_failed(&self) -> bool {
+    (reg::GPDMA.RawIntErrStat() | unsafe { Failed }) & self.mask() != 0
+  }
+
+  fn abort(&self) {
+    let config = self.reg.Config();
+    if config & ConfigEnable == 0 {
+      return;
+    }
+
+    // Halt and let the channel drain before disabling it
+    self.reg.set_Config(config | ConfigHalt);
+    while self.reg.Config() & ConfigActive != 0 {}
+    self.reg.set_Config(config & !(ConfigEnable | ConfigHalt));
+  }
+}
+
+#[allow(non_upper_case_globals)]
+static mut Callbacks: [Option<dma::Callback>; 8] = [None; 8];
+
+/// Channels that failed, as their error flags get cleared by the ISR.
+#[allow(non_upper_case_globals)]
+static mut Failed: u32 = 0;
+
+/// Acknowledges the channel interrupts and calls their completion callbacks.
+///
+/// Unsafe because it accesses the channel state without a critical section,
+/// it must only be called from `isr_dma`.
+pub unsafe fn handle_interrupt() {
+  let tc = reg::GPDMA.IntTCStat();
+  let err = reg::GPDMA.IntErrStat();
+  reg::GPDMA.set_IntTCClear(tc);
+  reg::GPDMA.set_IntErrClr(err);
+  Failed |= err;
+
+  for i in 0..8 {
+    let mask = 1 << i;
+    if (tc | err) & mask == 0 {
+      continue;
+    }
+    if let Some(callback) = Callbacks[i] {
+      callback(if err & mask != 0 {
+        Err(dma::Error::BusError)
+      } else {
+        Ok(())
+      });
+    }
+  }
+}
+
+#[allow(non_upper_case_globals)]
+const DMAIrq: usize = 26;
+
+#[allow(non_upper_case_globals)]
+const ControlSWidthShift: u32 = 18;
+#[allow(non_upper_case_globals)]
+const ControlDWidthShift: u32 = 21;
+#[allow(non_upper_case_globals)]
+const ControlSrcIncrement: u32 = 1 << 26;
+#[allow(non_upper_case_globals)]
+const ControlDstIncrement: u32 = 1 << 27;
+#[allow(non_upper_case_globals)]
+const ControlTCInterrupt: u32 = 1 << 31;
+
+#[allow(non_upper_case_globals)]
+const ConfigEnable: u32 = 1 << 0;
+#[allow(non_upper_case_globals)]
+const ConfigErrorInterrupt: u32 = 1 << 14;
+#[allow(non_upper_case_globals)]
+const ConfigTCInterrupt: u32 = 1 << 15;
+#[allow(non_upper_case_globals)]
+const ConfigActive: u32 = 1 << 17;
+#[allow(non_upper_case_globals)]
+const ConfigHalt: u32 = 1 << 18;
+
+mod reg {
+  use volatile_cell::VolatileCell;
+
+  ioreg_old!(GPDMA: u32, IntStat, IntTCStat, IntTCClear, IntErrStat, IntErrClr,
+      RawIntTCStat, RawIntErrStat, EnbldChns, SoftBReq, SoftSReq, SoftLBReq,
+      SoftLSReq, Config, Sync);
+  reg_r!( GPDMA, u32, IntStat,                 IntStat);
+  reg_r!( GPDMA, u32, IntTCStat,               IntTCStat);
+  reg_w!( GPDMA, u32,            set_IntTCClear, IntTCClear);
+  reg_r!( GPDMA, u32, IntErrStat,              IntErrStat);
+  reg_w!( GPDMA, u32,            set_IntErrClr,  IntErrClr);
+  reg_r!( GPDMA, u32, RawIntTCStat,            RawIntTCStat);
+  reg_r!( GPDMA, u32, RawIntErrStat,           RawIntErrStat);
+  reg_r!( GPDMA, u32, EnbldChns,               EnbldChns);
+  reg_rw!(GPDMA, u32, Config,    set_Config,     Config);
+  reg_rw!(GPDMA, u32, Sync,      set_Sync,       Sync);
+
+  ioreg_old!(DMACH: u32, SrcAddr, DestAddr, LLI, Control, Config);
+  reg_rw!(DMACH, u32, SrcAddr,  set_SrcAddr,  SrcAddr);
+  reg_rw!(DMACH, u32, DestAddr, set_DestAddr, DestAddr);
+  reg_rw!(DMACH, u32, LLI,      set_LLI,      LLI);
+  reg_rw!(DMACH, u32, Control,  set_Control,  Control);
+  reg_rw!(DMACH, u32, Config,   set_Config,   Config);
+
+  extern {
+    #[link_name="lpc17xx_iomem_GPDMA"] pub static GPDMA: GPDMA;
+    #[link_name="lpc17xx_iomem_DMACH0"] pub static DMACH0: DMACH;
+    #[link_name="lpc17xx_iomem_DMACH1"] pub static DMACH1: DMACH;
+    #[link_name="lpc17xx_iomem_DMACH2"] pub static DMACH2: DMACH;
+    #[link_name="lpc17xx_iomem_DMACH3"] pub static DMACH3: DMACH;
+    #[link_name="lpc17xx_iomem_DMACH4"] pub static DMACH4: DMACH;
+    #[link_name="lpc17xx_iomem_DMACH5"] pub static DMACH5: DMACH;
+    #[link_name="lpc17xx_iomem_DMACH6"] pub static DMACH6: DMACH;
+    #[link_name="lpc17xx_iomem_DMACH7"] pub static DMACH7: DMACH;
+  }
+}
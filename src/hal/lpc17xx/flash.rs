@@ -0,0 +1,160 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Flash programming for NXP LPC17xx using the IAP routines in boot ROM.
+//!
+//! The flash is not readable while IAP erases or programs it, so interrupts
+//! are disabled for each call as the vector table and handlers live in
+//! flash. IAP also uses the top 32 bytes of the local SRAM (0x10007FE0 on
+//! 32 kB parts) and up to 128 bytes of stack. The linker script only places
+//! data and the stack in the first 8 kB of SRAM, so those bytes are free.
+//!
+//! Sectors 0 to 15 are 4 kB, sectors 16 to 29 are 32 kB. Data is programmed
+//! in 256 byte blocks, a trailing partial block is padded with `0xff`.
+
+use core::cmp;
+use core::intrinsics::transmute;
+use core::slice;
+
+use hal::cortex_m3::irq::NoInterrupts;
+use hal::flash;
+use hal::lpc17xx::system_clock::system_clock;
+
+/// Address of the IAP entry point, in thumb mode.
+const IAP_ENTRY: usize = 0x1fff_1ff1;
+
+/// Smallest block that IAP can program.
+const BLOCK_SIZE: usize = 256;
+
+const SMALL_SECTOR_SIZE: usize = 4 * 1024;
+const LARGE_SECTOR_SIZE: usize = 32 * 1024;
+const SMALL_SECTOR_COUNT: usize = 16;
+
+#[derive(Clone, Copy)]
+enum Command {
+  PrepareSectors = 50,
+  CopyRamToFlash = 51,
+  EraseSectors   = 52,
+}
+
+/// On-chip flash.
+#[derive(Clone, Copy)]
+pub struct Flash {
+  sectors: usize,
+}
+
+impl Flash {
+  /// Returns the flash of a part with `size` bytes of it.
+  pub fn new(size: usize) -> Flash {
+    let sectors = if size <= SMALL_SECTOR_COUNT * SMALL_SECTOR_SIZE {
+      size / SMALL_SECTOR_SIZE
+    } else {
+      SMALL_SECTOR_COUNT +
+          (size - SMALL_SECTOR_COUNT * SMALL_SECTOR_SIZE) / LARGE_SECTOR_SIZE
+    };
+    Flash { sectors: sectors }
+  }
+
+  fn prepare(&self, sector: usize) -> Result<(), flash::Error> {
+    iap(Command::PrepareSectors, [sector as u32, sector as u32, 0, 0])
+  }
+}
+
+impl flash::Flash for Flash {
+  fn sector_count(&self) -> usize {
+    self.sectors
+  }
+
+  fn sector_start(&self, sector: usize) -> usize {
+    if sector < SMALL_SECTOR_COUNT {
+      sector * SMALL_SECTOR_SIZE
+    } else {
+      SMALL_SECTOR_COUNT * SMALL_SECTOR_SIZE +
+          (sector - SMALL_SECTOR_COUNT) * LARGE_SECTOR_SIZE
+    }
+  }
+
+  fn sector_size(&self, sector: usize) -> usize {
+    if sector < SMALL_SECTOR_COUNT {
+      SMALL_SECTOR_SIZE
+    } else {
+      LARGE_SECTOR_SIZE
+    }
+  }
+
+  fn program_size(&self) -> usize {
+    BLOCK_SIZE
+  }
+
+  fn erase(&self, sector: usize) -> Result<(), flash::Error> {
+    if sector >= self.sectors {
+      return Err(flash::Error::InvalidAddress);
+    }
+    try!(self.prepare(sector));
+    iap(Command::EraseSectors,
+        [sector as u32, sector as u32, system_clock() / 1000, 0])
+  }
+
+  fn program(&self, address: usize, data: &[u8]) -> Result<(), flash::Error> {
+    try!(flash::check_range(self, address, data.len()));
+
+    // IAP copies from word aligned RAM
+    let mut block = [0u32; BLOCK_SIZE / 4];
+    let mut offset = 0;
+    while offset < data.len() {
+      let len = cmp::min(BLOCK_SIZE, data.len() - offset);
+      {
+        let bytes = unsafe {
+          slice::from_raw_parts_mut(block.as_mut_ptr() as *mut u8, BLOCK_SIZE)
+        };
+        for (i, byte) in bytes.iter_mut().enumerate() {
+          *byte = if i < len { data[offset + i] } else { 0xff };
+        }
+      }
+
+      let dst = address + offset;
+      // a block never spans sectors, as sectors are multiples of blocks
+      try!(self.prepare(flash::Flash::sector_at(self, dst).unwrap()));
+      try!(iap(Command::CopyRamToFlash, [dst as u32, block.as_ptr() as u32,
+          BLOCK_SIZE as u32, system_clock() / 1000]));
+      offset += BLOCK_SIZE;
+    }
+
+    flash::Flash::verify(self, address, data)
+  }
+}
+
+/// Calls an IAP command with interrupts disabled.
+fn iap(command: Command, params: [u32; 4]) -> Result<(), flash::Error> {
+  let input = [command as u32, params[0], params[1], params[2], params[3]];
+  let mut output = [0u32; 5];
+
+  {
+    let _crit = NoInterrupts::new();
+    unsafe {
+      let entry: extern "C" fn(*const u32, *mut u32) = transmute(IAP_ENTRY);
+      entry(input.as_ptr(), output.as_mut_ptr());
+    }
+  }
+
+  match output[0] {
+    0 => Ok(()),
+    // DST_ADDR_ERROR, DST_ADDR_NOT_MAPPED, INVALID_SECTOR
+    3 | 5 | 7 => Err(flash::Error::InvalidAddress),
+    // COMPARE_ERROR
+    10 => Err(flash::Error::VerifyFailed),
+    _ => Err(flash::Error::OperationFailed),
+  }
+}
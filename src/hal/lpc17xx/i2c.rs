@@ -0,0 +1,283 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+I2C configuration.
+
+This code supports master mode of all three I2C controllers. Transactions are
+polled, the state of the bus is tracked through the STAT register.
+
+SCL rate is derived from the peripheral clock, which follows `system_clock()`.
+I2C1 and I2C2 pins need external pull-ups and should be configured as open
+drain.
+*/
+
+use hal::i2c;
+use hal::lpc17xx::peripheral_clock::PeripheralClock;
+
+use self::I2CPeripheral::*;
+
+#[path="../../util/ioreg.rs"]
+#[macro_use] mod ioreg;
+
+/// Available I2C peripherals.
+#[allow(missing_docs)]
+#[derive(Clone, Copy)]
+pub enum I2CPeripheral {
+  I2C0,
+  I2C1,
+  I2C2,
+}
+
+impl I2CPeripheral {
+  fn reg(self) -> &'static reg::I2C {
+    match self {
+      I2C0 => &reg::I2C0,
+      I2C1 => &reg::I2C1,
+      I2C2 => &reg::I2C2,
+    }
+  }
+
+  fn peripheral_clock(self) -> PeripheralClock {
+    match self {
+      I2C0 => PeripheralClock::I2C0Clock,
+      I2C1 => PeripheralClock::I2C1Clock,
+      I2C2 => PeripheralClock::I2C2Clock,
+    }
+  }
+}
+
+/// Structure describing an I2C master instance.
+#[derive(Clone, Copy)]
+pub struct I2C {
+  reg: &'static reg::I2C,
+}
+
+impl I2C {
+  /// Create and setup an I2C master running at `speed` Hz.
+  pub fn new(peripheral: I2CPeripheral, speed: u32) -> I2C {
+    let clock = peripheral.peripheral_clock();
+    let i2c = I2C {
+      reg: peripheral.reg(),
+    };
+
+    clock.enable();
+    i2c.reg.set_CONCLR((CONAA | CONSI | CONSTA | CONEN) as u32);
+    i2c.set_speed(clock.frequency(), speed);
+    i2c.reg.set_CONSET(CONEN as u32);
+
+    i2c
+  }
+
+  fn set_speed(&self, pclk: u32, speed: u32) {
+    // Duty cycle is kept at 50%, both halves have a minimum of 4 clocks.
+    let mut half = pclk / speed / 2;
+    if half < 4 {
+      half = 4;
+    }
+    self.reg.set_SCLH(half);
+    self.reg.set_SCLL(half);
+  }
+
+  /// Waits for the state machine to leave the current state and returns the
+  /// new one.
+  fn wait(&self) -> Result<u8, i2c::Error> {
+    for _ in 0..TimeoutPolls {
+      if self.reg.CONSET() as u8 & CONSI == CONSI {
+        return match self.reg.STAT() as u8 {
+          STATBusError => {
+            self.reg.set_CONSET(CONSTO as u32);
+            self.reg.set_CONCLR(CONSI as u32);
+            Err(i2c::Error::BusError)
+          },
+          STATArbitrationLost => {
+            // The controller has already released the bus
+            self.reg.set_CONCLR((CONSTA | CONSI) as u32);
+            Err(i2c::Error::ArbitrationLost)
+          },
+          stat => Ok(stat),
+        };
+      }
+    }
+
+    self.stop();
+    Err(i2c::Error::Timeout)
+  }
+
+  /// Generates a (repeated) START and sends the address byte.
+  fn start(&self, addr: u8, read: bool) -> Result<(), i2c::Error> {
+    self.reg.set_CONSET(CONSTA as u32);
+    self.reg.set_CONCLR(CONSI as u32);
+    match try!(self.wait()) {
+      STATStart | STATRepeatedStart => (),
+      _ => {
+        self.stop();
+        return Err(i2c::Error::BusError);
+      },
+    }
+
+    self.reg.set_DAT(((addr << 1) | read as u8) as u32);
+    self.reg.set_CONCLR((CONSTA | CONSI) as u32);
+    match try!(self.wait()) {
+      STATAddressWriteAck | STATAddressReadAck => Ok(()),
+      STATAddressWriteNack | STATAddressReadNack => {
+        self.stop();
+        Err(i2c::Error::AddressNack)
+      },
+      _ => {
+        self.stop();
+        Err(i2c::Error::BusError)
+      },
+    }
+  }
+
+  fn stop(&self) {
+    self.reg.set_CONSET(CONSTO as u32);
+    self.reg.set_CONCLR(CONSI as u32);
+    for _ in 0..TimeoutPolls {
+      if self.reg.CONSET() as u8 & CONSTO == 0 {
+        break;
+      }
+    }
+  }
+
+  fn transmit(&self, addr: u8, data: &[u8]) -> Result<(), i2c::Error> {
+    try!(self.start(addr, false));
+
+    for &b in data {
+      self.reg.set_DAT(b as u32);
+      self.reg.set_CONCLR(CONSI as u32);
+      match try!(self.wait()) {
+        STATDataWriteAck => (),
+        STATDataWriteNack => {
+          self.stop();
+          return Err(i2c::Error::DataNack);
+        },
+        _ => {
+          self.stop();
+          return Err(i2c::Error::BusError);
+        },
+      }
+    }
+    Ok(())
+  }
+
+  fn receive(&self, addr: u8, buf: &mut [u8]) -> Result<(), i2c::Error> {
+    try!(self.start(addr, true));
+
+    let len = buf.len();
+    for i in 0..len {
+      // NACK the last byte to let the slave release the bus
+      if i + 1 == len {
+        self.reg.set_CONCLR((CONAA | CONSI) as u32);
+      } else {
+        self.reg.set_CONSET(CONAA as u32);
+        self.reg.set_CONCLR(CONSI as u32);
+      }
+      match try!(self.wait()) {
+        STATDataReadAck | STATDataReadNack => buf[i] = self.reg.DAT() as u8,
+        _ => {
+          self.stop();
+          return Err(i2c::Error::BusError);
+        },
+      }
+    }
+
+    self.stop();
+    Ok(())
+  }
+}
+
+impl i2c::I2c for I2C {
+  fn write(&self, addr: u8, data: &[u8]) -> Result<(), i2c::Error> {
+    try!(self.transmit(addr, data));
+    self.stop();
+    Ok(())
+  }
+
+  fn read(&self, addr: u8, buf: &mut [u8]) -> Result<(), i2c::Error> {
+    if buf.len() == 0 {
+      return Ok(());
+    }
+    self.receive(addr, buf)
+  }
+
+  fn write_read(&self, addr: u8, data: &[u8], buf: &mut [u8])
+      -> Result<(), i2c::Error> {
+    if buf.len() == 0 {
+      return self.write(addr, data);
+    }
+    try!(self.transmit(addr, data));
+    self.receive(addr, buf)
+  }
+}
+
+#[allow(non_upper_case_globals)]
+const TimeoutPolls: u32 = 100_000;
+
+#[allow(non_upper_case_globals)]
+const CONAA: u8 = 0x04;
+#[allow(non_upper_case_globals)]
+const CONSI: u8 = 0x08;
+#[allow(non_upper_case_globals)]
+const CONSTO: u8 = 0x10;
+#[allow(non_upper_case_globals)]
+const CONSTA: u8 = 0x20;
+#[allow(non_upper_case_globals)]
+const CONEN: u8 = 0x40;
+
+#[allow(non_upper_case_globals)]
+const STATBusError: u8 = 0x00;
+#[allow(non_upper_case_globals)]
+const STATStart: u8 = 0x08;
+#[allow(non_upper_case_globals)]
+const STATRepeatedStart: u8 = 0x10;
+#[allow(non_upper_case_globals)]
+const STATAddressWriteAck: u8 = 0x18;
+#[allow(non_upper_case_globals)]
+const STATAddressWriteNack: u8 = 0x20;
+#[allow(non_upper_case_globals)]
+const STATDataWriteAck: u8 = 0x28;
+#[allow(non_upper_case_globals)]
+const STATDataWriteNack: u8 = 0x30;
+#[allow(non_upper_case_globals)]
+const STATArbitrationLost: u8 = 0x38;
+#[allow(non_upper_case_globals)]
+const STATAddressReadAck: u8 = 0x40;
+#[allow(non_upper_case_globals)]
+const STATAddressReadNack: u8 = 0x48;
+#[allow(non_upper_case_globals)]
+const STATDataReadAck: u8 = 0x50;
+#[allow(non_upper_case_globals)]
+const STATDataReadNack: u8 = 0x58;
+
+mod reg {
+  use volatile_cell::VolatileCell;
+
+  ioreg_old!(I2C: u32, CONSET, STAT, DAT, ADR0, SCLH, SCLL, CONCLR);
+  reg_rw!(I2C, u32, CONSET, set_CONSET, CONSET);
+  reg_r!( I2C, u32, STAT,               STAT);
+  reg_rw!(I2C, u32, DAT,    set_DAT,    DAT);
+  reg_rw!(I2C, u32, ADR0,   set_ADR0,   ADR0);
+  reg_rw!(I2C, u32, SCLH,   set_SCLH,   SCLH);
+  reg_rw!(I2C, u32, SCLL,   set_SCLL,   SCLL);
+  reg_w!( I2C, u32,         set_CONCLR, CONCLR);
+
+  extern {
+    #[link_name="lpc17xx_iomem_I2C0"] pub static I2C0: I2C;
+    #[link_name="lpc17xx_iomem_I2C1"] pub static I2C1: I2C;
+    #[link_name="lpc17xx_iomem_I2C2"] pub static I2C2: I2C;
+  }
+}
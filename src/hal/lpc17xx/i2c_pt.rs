@@ -0,0 +1,143 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::rc::Rc;
+use syntax::ext::base::ExtCtxt;
+
+use builder::{Builder, TokenString, add_node_dependency};
+use node;
+
+pub fn attach(builder: &mut Builder, _: &mut ExtCtxt, node: Rc<node::Node>) {
+  node.materializer.set(Some(verify as fn(&mut Builder, &mut ExtCtxt, Rc<node::Node>)));
+
+  for sub in node.subnodes().iter() {
+    add_node_dependency(&node, sub);
+    let sda_node_name = sub.get_ref_attr("sda").unwrap();
+    let scl_node_name = sub.get_ref_attr("scl").unwrap();
+    let sda_node = builder.pt().get_by_name(sda_node_name.as_str()).unwrap();
+    let scl_node = builder.pt().get_by_name(scl_node_name.as_str()).unwrap();
+    add_node_dependency(sub, &sda_node);
+    add_node_dependency(sub, &scl_node);
+    super::add_node_dependency_on_clock(builder, sub);
+
+    sub.materializer.set(Some(build_i2c as fn(&mut Builder, &mut ExtCtxt, Rc<node::Node>)));
+    sub.mutator.set(Some(mutate_pins as fn(&mut Builder, &mut ExtCtxt, Rc<node::Node>)));
+  }
+}
+
+pub fn verify(_: &mut Builder, cx: &mut ExtCtxt, node: Rc<node::Node>) {
+  node.expect_no_attributes(cx);
+}
+
+pub fn mutate_pins(builder: &mut Builder, _: &mut ExtCtxt, sub: Rc<node::Node>) {
+  let sda_node_name = sub.get_ref_attr("sda").unwrap();
+  let scl_node_name = sub.get_ref_attr("scl").unwrap();
+
+  build_i2c_gpio(builder, sub.path.as_str(), sda_node_name.as_str(), "sda");
+  build_i2c_gpio(builder, sub.path.as_str(), scl_node_name.as_str(), "scl");
+}
+
+pub fn build_i2c(builder: &mut Builder, cx: &mut ExtCtxt,
+    sub: Rc<node::Node>) {
+  let i2c_peripheral_str = format!("I2CPeripheral::I2C{}",
+      match sub.path.as_str().parse::<usize>().unwrap() {
+        0...2 => sub.path.clone(),
+        other => {
+          cx.parse_sess().span_diagnostic.span_err(sub.path_span,
+              format!("unknown I2C `{}`, allowed values: 0, 1, 2",
+                  other).as_str());
+          return
+        }
+      });
+  let i2c_peripheral = TokenString(i2c_peripheral_str);
+
+  if sub.name.is_none() {
+    cx.parse_sess().span_diagnostic.span_err(sub.name_span,
+        "I2C node must have a name");
+    return
+  }
+
+  if !sub.expect_attributes(cx, &[
+      ("speed", node::IntAttribute),
+      ("sda", node::RefAttribute),
+      ("scl", node::RefAttribute)]) {
+    return
+  }
+
+  let speed: u32 = sub.get_int_attr("speed").unwrap() as u32;
+
+  sub.set_type_name("zinc::hal::lpc17xx::i2c::I2C".to_string());
+  let i2c_name = TokenString(sub.name.clone().unwrap());
+
+  let st = quote_stmt!(&*cx,
+      let $i2c_name = zinc::hal::lpc17xx::i2c::I2C::new(
+          zinc::hal::lpc17xx::i2c::$i2c_peripheral,
+          $speed)
+  ).unwrap();
+  builder.add_main_statement(st);
+}
+
+pub fn build_i2c_gpio(builder: &Builder, i2c_idx: &str, name: &str,
+    signal: &str) {
+  let node = builder.pt().get_by_name(name).unwrap();
+  let function = format!("{}{}", signal, i2c_idx);
+  node.attributes.borrow_mut().insert("function".to_string(),
+        Rc::new(node::Attribute::new_nosp(node::StrValue(function))));
+}
+
+#[cfg(test)]
+mod test {
+  use builder::Builder;
+  use test_helpers::{assert_equal_source, with_parsed};
+
+  #[test]
+  fn builds_i2c() {
+    with_parsed("
+      mcu {
+        clock {
+          system_frequency = 100_000_000;
+        }
+      }
+      i2c {
+        i2c@1 {
+          speed = 400000;
+          sda = &i2c_sda;
+          scl = &i2c_scl;
+        }
+      }
+      gpio {
+        i2c_sda@19;
+        i2c_scl@20;
+      }
+      ", |cx, failed, pt| {
+      let mut builder = Builder::new(pt.clone(), cx);
+      super::mutate_pins(&mut builder, cx, pt.get_by_name("i2c").unwrap());
+      super::build_i2c(&mut builder, cx, pt.get_by_name("i2c").unwrap());
+      assert!(unsafe{*failed} == false);
+      assert!(builder.main_stmts().len() == 1);
+
+      assert_equal_source(&builder.main_stmts()[0],
+          "let i2c = zinc::hal::lpc17xx::i2c::I2C::new(
+               zinc::hal::lpc17xx::i2c::I2CPeripheral::I2C1,
+               400000u32);");
+
+      let sda_node = pt.get_by_name("i2c_sda").unwrap();
+      assert!(sda_node.get_string_attr("function").unwrap() == "sda1".to_string());
+
+      let scl_node = pt.get_by_name("i2c_scl").unwrap();
+      assert!(scl_node.get_string_attr("function").unwrap() == "scl1".to_string());
+    });
+  }
+}
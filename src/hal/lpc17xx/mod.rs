@@ -17,8 +17,16 @@
 
 pub mod system_clock;
 pub mod peripheral_clock;
+pub mod adc;
+pub mod dac;
+pub mod dma;
+pub mod flash;
+pub mod i2c;
 pub mod pin;
+pub mod power;
 pub mod pwm;
-// pub mod ssp;
+pub mod rtc;
+pub mod ssp;
 pub mod timer;
 pub mod uart;
+pub mod watchdog;
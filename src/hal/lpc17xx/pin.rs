@@ -18,11 +18,19 @@ Pin configuration.
 
 Some pins that could be configured here may be missing from actual MCU depending
 on the package.
+
+Pins on ports 0 and 2 can raise edge interrupts through `InterruptPin`. These
+share the EINT3 vector, the application defines `isr_eint_3` and calls
+`handle_interrupt()` from it to dispatch to the registered per-pin handlers.
 */
 
 use core::intrinsics::abort;
 use core::option::Option;
 
+use hal::cortex_m3::irq::NoInterrupts;
+use hal::cortex_m3::nvic;
+use hal::pin::{Edge, PinHandler};
+
 use self::Port::*;
 
 #[path="../../util/ioreg.rs"]
@@ -121,6 +129,14 @@ impl Pin {
     }
   }
 
+  fn intreg(&self) -> (usize, &reg::GpioInt) {
+    match self.port {
+      Port0 => (0, &reg::GPIOINT_0),
+      Port2 => (1, &reg::GPIOINT_2),
+      _     => unsafe { abort() },
+    }
+  }
+
   fn get_pinsel_reg_and_offset(&self) -> (u8, &reg::PINSEL) {
     match self.port {
       Port0 => match self.pin {
@@ -301,6 +317,113 @@ impl ::hal::pin::Adc for Pin {
   }
 }
 
+impl ::hal::pin::InterruptPin for Pin {
+  /// Registers the handler and enables the interrupt for the given edge.
+  ///
+  /// Only pins on ports 0 and 2 support interrupts.
+  fn set_interrupt(&self, edge: Edge, handler: PinHandler) {
+    let (index, _) = self.intreg();
+    {
+      let _crit = NoInterrupts::new();
+      unsafe {
+        PinInterrupts[index][self.pin as usize] = PinInterrupt {
+          handler: Some(handler),
+          edge: edge,
+        };
+      }
+    }
+    self.clear_interrupt();
+    self.enable_interrupt();
+    nvic::enable_irq(EINT3_IRQ);
+  }
+
+  fn enable_interrupt(&self) {
+    let (index, reg) = self.intreg();
+    let bit: u32 = 1 << (self.pin as usize);
+    let _crit = NoInterrupts::new();
+    let edge = unsafe { PinInterrupts[index][self.pin as usize].edge };
+
+    let (rising, falling) = match edge {
+      Edge::Rising  => (true, false),
+      Edge::Falling => (false, true),
+      Edge::Both    => (true, true),
+    };
+    if rising {
+      reg.set_EnR(reg.EnR() | bit);
+    } else {
+      reg.set_EnR(reg.EnR() & !bit);
+    }
+    if falling {
+      reg.set_EnF(reg.EnF() | bit);
+    } else {
+      reg.set_EnF(reg.EnF() & !bit);
+    }
+  }
+
+  fn disable_interrupt(&self) {
+    let (_, reg) = self.intreg();
+    let mask: u32 = !(1 << (self.pin as usize));
+    let _crit = NoInterrupts::new();
+    reg.set_EnR(reg.EnR() & mask);
+    reg.set_EnF(reg.EnF() & mask);
+  }
+
+  fn clear_interrupt(&self) {
+    let (_, reg) = self.intreg();
+    reg.set_Clr(1 << (self.pin as usize));
+  }
+}
+
+#[derive(Clone, Copy)]
+struct PinInterrupt {
+  handler: Option<PinHandler>,
+  edge: Edge,
+}
+
+const PIN_INTERRUPT_INIT: PinInterrupt = PinInterrupt {
+  handler: None,
+  edge: Edge::Both,
+};
+
+const EINT3_IRQ: usize = 21;
+
+#[allow(non_upper_case_globals)]
+static mut PinInterrupts: [[PinInterrupt; 32]; 2] =
+    [[PIN_INTERRUPT_INIT; 32]; 2];
+
+#[allow(non_upper_case_globals)]
+static IntStatusP0: u32 = 1 << 0;
+#[allow(non_upper_case_globals)]
+static IntStatusP2: u32 = 1 << 2;
+
+unsafe fn dispatch_port(index: usize, reg: &reg::GpioInt) {
+  let pending = reg.StatR() | reg.StatF();
+  reg.set_Clr(pending);
+
+  for pin in 0..32 {
+    if pending & (1 << pin) != 0 {
+      match PinInterrupts[index][pin].handler {
+        Some(handler) => handler(),
+        None => {},
+      }
+    }
+  }
+}
+
+/// Dispatches the pending GPIO interrupts of ports 0 and 2.
+///
+/// Unsafe because it reads the handlers without a critical section, it must
+/// only be called from `isr_eint_3`.
+pub unsafe fn handle_interrupt() {
+  let status = reg::GPIOINT.value();
+  if status & IntStatusP0 != 0 {
+    dispatch_port(0, &reg::GPIOINT_0);
+  }
+  if status & IntStatusP2 != 0 {
+    dispatch_port(1, &reg::GPIOINT_2);
+  }
+}
+
 /// Sets the state of trace port interface.
 pub fn set_trace_port_interface_enabled(enabled: bool) {
   let value: u32 = if enabled { 0b1000 } else { 0 };
@@ -351,6 +474,22 @@ mod reg {
     #[link_name="lpc17xx_iomem_GPIO4"] pub static GPIO_4: Gpio;
   }
 
+  ioreg_old!(GPIOINTSTATUS: u32, value);
+  reg_r!(GPIOINTSTATUS, u32, value, value);
+
+  ioreg_old!(GpioInt: u32, StatR, StatF, Clr, EnR, EnF);
+  reg_r!(GpioInt, u32, StatR, StatR);
+  reg_r!(GpioInt, u32, StatF, StatF);
+  reg_w!(GpioInt, u32, set_Clr, Clr);
+  reg_rw!(GpioInt, u32, EnR, set_EnR, EnR);
+  reg_rw!(GpioInt, u32, EnF, set_EnF, EnF);
+
+  extern {
+    #[link_name="lpc17xx_iomem_GPIOINT"]  pub static GPIOINT:   GPIOINTSTATUS;
+    #[link_name="lpc17xx_iomem_GPIOINT0"] pub static GPIOINT_0: GpioInt;
+    #[link_name="lpc17xx_iomem_GPIOINT2"] pub static GPIOINT_2: GpioInt;
+  }
+
 
   ioreg_old!(PCONP: u32, value);
   ioreg_old!(PCLKSEL0: u32, value);
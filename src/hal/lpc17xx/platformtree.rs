@@ -20,9 +20,12 @@ use builder::{Builder, add_node_dependency};
 use node;
 
 mod system_clock_pt;
+mod dac_pt;
+mod i2c_pt;
 mod timer_pt;
 mod pin_pt;
 mod uart_pt;
+mod watchdog_pt;
 
 mod pinmap;
 
@@ -36,6 +39,9 @@ pub fn attach(builder: &mut Builder, cx: &mut ExtCtxt, node: Rc<node::Node>) {
       "timer" => timer_pt::attach(builder, cx, sub.clone()),
       "uart"  => uart_pt::attach(builder, cx, sub.clone()),
       "gpio"  => pin_pt::attach(builder, cx, sub.clone()),
+      "i2c"   => i2c_pt::attach(builder, cx, sub.clone()),
+      "dac"   => dac_pt::attach(builder, cx, sub.clone()),
+      "watchdog" => watchdog_pt::attach(builder, cx, sub.clone()),
       _ => (),
     }
   }
@@ -43,7 +49,8 @@ pub fn attach(builder: &mut Builder, cx: &mut ExtCtxt, node: Rc<node::Node>) {
 
 fn verify(_: &mut Builder, cx: &mut ExtCtxt, node: Rc<node::Node>) {
   node.expect_no_attributes(cx);
-  node.expect_subnodes(cx, &["clock", "timer", "uart", "gpio"]);
+  node.expect_subnodes(cx, &["clock", "timer", "uart", "gpio", "i2c", "dac",
+      "watchdog"]);
 }
 
 pub fn add_node_dependency_on_clock(builder: &mut Builder,
@@ -0,0 +1,146 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Low-power modes for NXP LPC17xx.
+//!
+//! Sleep, deep-sleep, power-down and deep power-down are selected with PCON
+//! and SCR.SLEEPDEEP and entered with `wfi`. Any enabled interrupt wakes the
+//! MCU from sleep. Deep-sleep and power-down stop the main oscillator and the
+//! PLL, so the MCU wakes up on the IRC and the clock is set up again with
+//! `system_clock::init_clock()`. Only the sources in `WakeUp` can wake it
+//! from these modes. Deep power-down can only be left through the RTC alarm
+//! or a reset, both of which restart the MCU. An enabled interrupt that is
+//! pending makes `wfi` return without powering down, so entering it retries
+//! until the MCU is off.
+
+use hal::cortex_m3::{nvic, scb};
+use hal::lpc17xx::system_clock::{Clock, init_clock};
+use hal::power::{self, Mode};
+use util::support::wfi;
+
+/// Sources that wake the MCU from deep-sleep and power-down.
+#[derive(Clone, Copy)]
+pub enum WakeUp {
+  /// Port 0 and 2 GPIO interrupts, configured with `InterruptPin`.
+  Gpio,
+  /// External interrupt pin EINT0 to EINT3.
+  ExternalInterrupt(u8),
+  /// RTC alarm and counter increment interrupts.
+  Rtc,
+  /// Brown-out detection interrupt.
+  BrownOut,
+  /// Watchdog interrupt, when the watchdog runs on the IRC.
+  Watchdog,
+}
+
+impl WakeUp {
+  fn irq(self) -> usize {
+    match self {
+      WakeUp::Watchdog             => 0,
+      WakeUp::Rtc                  => 17,
+      WakeUp::ExternalInterrupt(n) => 18 + (n as usize & 3),
+      WakeUp::Gpio                 => 21,
+      WakeUp::BrownOut             => 23,
+    }
+  }
+}
+
+/// Power mode control.
+#[derive(Clone, Copy)]
+pub struct Power {
+  clock: Clock,
+}
+
+impl Power {
+  /// Creates power mode control that restores `clock` after waking up.
+  pub fn new(clock: Clock) -> Power {
+    Power {
+      clock: clock,
+    }
+  }
+
+  /// Lets `source` wake the MCU up by enabling its interrupt.
+  pub fn enable_wakeup(&self, source: WakeUp) {
+    nvic::enable_irq(source.irq());
+  }
+
+  /// Stops `source` from waking the MCU up.
+  pub fn disable_wakeup(&self, source: WakeUp) {
+    nvic::disable_irq(source.irq());
+  }
+
+  /// Returns true if the MCU was restarted by leaving deep power-down, and
+  /// clears the flag.
+  pub fn woke_from_deep_power_down(&self) -> bool {
+    let woke = reg::PCON.pcon.dpdflag();
+    if woke {
+      reg::PCON.pcon.clear_dpdflag();
+    }
+    woke
+  }
+}
+
+impl power::LowPower for Power {
+  fn enter(&self, mode: Mode) {
+    use self::reg::PCON_pcon_pm::*;
+
+    let (pm, deep) = match mode {
+      Mode::Sleep         => (Normal, false),
+      Mode::DeepSleep     => (Normal, true),
+      Mode::PowerDown     => (PowerDown, true),
+      Mode::DeepPowerDown => (DeepPowerDown, true),
+    };
+
+    reg::PCON.pcon.set_pm(pm);
+    scb::set_sleep_deep(deep);
+    if let Mode::DeepPowerDown = mode {
+      loop {
+        wfi();
+      }
+    }
+    wfi();
+    scb::set_sleep_deep(false);
+
+    if deep {
+      init_clock(&self.clock);
+    }
+  }
+}
+
+mod reg {
+  use core::ops::Drop;
+  use volatile_cell::VolatileCell;
+
+  ioregs!(PCON = {
+    0x00 => reg32 pcon {      //! Power mode control
+      0..1 => pm {            //= Mode entered by wfi with SLEEPDEEP set
+        0 => Normal,          //= Sleep or deep-sleep
+        1 => PowerDown,
+        3 => DeepPowerDown,
+      },
+      2    => bodrpm,         //= Brown-out detection off in power-down
+      3    => bogd,           //= Brown-out detection off globally
+      4    => bord,           //= Brown-out reset disabled
+      8    => smflag: set_to_clear,   //= Sleep entered
+      9    => dsflag: set_to_clear,   //= Deep-sleep entered
+      10   => pdflag: set_to_clear,   //= Power-down entered
+      11   => dpdflag: set_to_clear,  //= Deep power-down entered
+    }
+  });
+
+  extern {
+    #[link_name="lpc17xx_iomem_PCON"] pub static PCON: PCON;
+  }
+}
@@ -0,0 +1,220 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Real-time clock for NXP LPC17xx.
+//!
+//! The RTC runs from the 32 kHz oscillator in the battery-backed domain and
+//! keeps the calendar in hardware, along with five general purpose registers.
+//! Applications using the alarm define `isr_rtc` and call `handle_interrupt()`
+//! from it, which calls the alarm handler.
+
+use core::option::Option::{self, Some, None};
+
+use hal::cortex_m3::nvic;
+use hal::lpc17xx::peripheral_clock::PeripheralClock::RTCClock;
+use hal::rtc;
+use hal::rtc::{AlarmHandler, DateTime};
+
+const RTC_IRQ: usize = 17;
+
+/// Number of general purpose registers.
+const GPREG_COUNT: usize = 5;
+
+/// Alarm mask comparing every field but the day of week and day of year.
+#[allow(non_upper_case_globals)]
+static AlarmCompareDate: u32 = 0b0011_0000;
+
+/// Alarm mask disabling all comparisons.
+#[allow(non_upper_case_globals)]
+static AlarmDisabled: u32 = 0b1111_1111;
+
+#[allow(non_upper_case_globals)]
+static mut Alarm: Option<AlarmHandler> = None;
+
+/// The real-time clock.
+#[derive(Clone, Copy)]
+pub struct RTC;
+
+impl RTC {
+  /// Enables the RTC. The calendar keeps running from the battery if it was
+  /// already set.
+  pub fn new() -> RTC {
+    RTCClock.enable();
+
+    reg::RTC.ciir.set_imask(0);
+    reg::RTC.ccr
+      .set_ccalen(true)  // no calibration
+      .set_clken(true);
+
+    RTC
+  }
+}
+
+impl rtc::Rtc for RTC {
+  fn now(&self) -> DateTime {
+    // The consolidated registers are read twice in case a second ticked
+    // in between.
+    loop {
+      let time = reg::RTC.ctime0.get();
+      let date = reg::RTC.ctime1.get();
+      if reg::RTC.ctime0.get().raw() != time.raw() {
+        continue;
+      }
+
+      return DateTime {
+        year: date.year() as u16,
+        month: date.month() as u8,
+        day: date.dom() as u8,
+        hour: time.hours() as u8,
+        minute: time.minutes() as u8,
+        second: time.seconds() as u8,
+      };
+    }
+  }
+
+  fn set(&self, time: DateTime) {
+    let rtc = &reg::RTC;
+
+    rtc.ccr
+      .set_clken(false)
+      .set_ctcrst(true);
+
+    rtc.sec.set_value(time.second as u32);
+    rtc.min.set_value(time.minute as u32);
+    rtc.hour.set_value(time.hour as u32);
+    rtc.dom.set_value(time.day as u32);
+    rtc.dow.set_value(time.day_of_week() as u32);
+    rtc.doy.set_value(time.day_of_year() as u32);
+    rtc.month.set_value(time.month as u32);
+    rtc.year.set_value(time.year as u32);
+
+    rtc.ccr
+      .set_ctcrst(false)
+      .set_clken(true);
+  }
+
+  fn set_alarm(&self, time: DateTime, handler: AlarmHandler) {
+    let rtc = &reg::RTC;
+
+    rtc.amr.set_mask(AlarmDisabled);
+    unsafe {
+      Alarm = Some(handler);
+    }
+
+    rtc.alsec.set_value(time.second as u32);
+    rtc.almin.set_value(time.minute as u32);
+    rtc.alhour.set_value(time.hour as u32);
+    rtc.aldom.set_value(time.day as u32);
+    rtc.almon.set_value(time.month as u32);
+    rtc.alyear.set_value(time.year as u32);
+
+    rtc.ilr.clear_rtcalf();
+    rtc.amr.set_mask(AlarmCompareDate);
+    nvic::enable_irq(RTC_IRQ);
+  }
+
+  fn cancel_alarm(&self) {
+    reg::RTC.amr.set_mask(AlarmDisabled);
+    nvic::disable_irq(RTC_IRQ);
+    unsafe {
+      Alarm = None;
+    }
+  }
+
+  fn backup_register_count(&self) -> usize {
+    GPREG_COUNT
+  }
+
+  fn read_backup(&self, index: usize) -> u32 {
+    reg::RTC.gpreg[index].value()
+  }
+
+  fn write_backup(&self, index: usize, value: u32) {
+    reg::RTC.gpreg[index].set_value(value);
+  }
+}
+
+/// Acknowledges the RTC alarm interrupt and calls the alarm handler.
+///
+/// Unsafe because it reads the handler without a critical section, it must
+/// only be called from `isr_rtc`.
+pub unsafe fn handle_interrupt() {
+  if !reg::RTC.ilr.rtcalf() {
+    return;
+  }
+  reg::RTC.ilr.clear_rtcalf();
+
+  match Alarm {
+    Some(handler) => handler(),
+    None => {},
+  }
+}
+
+mod reg {
+  use core::ops::Drop;
+  use volatile_cell::VolatileCell;
+
+  ioregs!(RTC = {
+    0x00 => reg32 ilr {      //! Interrupt location
+      0 => rtccif: set_to_clear,  //= Counter increment interrupt
+      1 => rtcalf: set_to_clear,  //= Alarm interrupt
+    }
+    0x08 => reg32 ccr {      //! Clock control
+      0 => clken,            //= Clock enable
+      1 => ctcrst,           //= Reset the sub-second counter
+      4 => ccalen,           //= Calibration counter disable
+    }
+    0x0c => reg32 ciir {     //! Counter increment interrupts
+      0..7 => imask,
+    }
+    0x10 => reg32 amr {      //! Alarm mask, set bits are not compared
+      0..7 => mask,
+    }
+    0x14 => reg32 ctime0 {   //! Consolidated time 0
+      0..5   => seconds: ro,
+      8..13  => minutes: ro,
+      16..20 => hours: ro,
+      24..26 => dow: ro,
+    }
+    0x18 => reg32 ctime1 {   //! Consolidated time 1
+      0..4   => dom: ro,
+      8..11  => month: ro,
+      16..27 => year: ro,
+    }
+    0x20 => reg32 sec    { 0..5  => value, }
+    0x24 => reg32 min    { 0..5  => value, }
+    0x28 => reg32 hour   { 0..4  => value, }
+    0x2c => reg32 dom    { 0..4  => value, }
+    0x30 => reg32 dow    { 0..2  => value, }
+    0x34 => reg32 doy    { 0..8  => value, }
+    0x38 => reg32 month  { 0..3  => value, }
+    0x3c => reg32 year   { 0..11 => value, }
+    0x44 => reg32 gpreg[5] {  //! General purpose, battery-backed
+      0..31 => value,
+    }
+    0x60 => reg32 alsec  { 0..5  => value, }
+    0x64 => reg32 almin  { 0..5  => value, }
+    0x68 => reg32 alhour { 0..4  => value, }
+    0x6c => reg32 aldom  { 0..4  => value, }
+    0x70 => reg32 aldow  { 0..2  => value, }
+    0x74 => reg32 aldoy  { 0..8  => value, }
+    0x78 => reg32 almon  { 0..3  => value, }
+    0x7c => reg32 alyear { 0..11 => value, }
+  });
+
+  extern {
+    #[link_name="lpc17xx_iomem_RTC"] pub static RTC: RTC;
+  }
+}
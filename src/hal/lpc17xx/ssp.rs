@@ -18,21 +18,34 @@ SSP configuration.
 
 Currently supports only SPI mode. Note that `SPI` is not the same peripheral and
 it's currently not supported at all.
+
+`SPIConf` sets up the SSP together with its pins, `SSP::new()` leaves the
+pins to the caller. The chip-select line is never managed here, it must be
+configured and used externally.
+
+Blocks of 8-bit frames can be moved with the GPDMA through `write_dma()` and
+`transfer_dma()`.
 */
 
 use core::intrinsics::abort;
 
-use hal::lpc17xx::peripheral_clock::{PeripheralClock, SSP0Clock, SSP1Clock};
+use hal::dma::{self, Channel as DmaChannel};
+use hal::lpc17xx::dma::{Channel, Descriptor, Flow, Request, MAX_TRANSFER_SIZE};
+use hal::lpc17xx::peripheral_clock::PeripheralClock;
+use hal::lpc17xx::peripheral_clock::PeripheralClock::{SSP0Clock, SSP1Clock};
+use hal::lpc17xx::pin::{Pin, Port, Function};
 use hal::lpc17xx::system_clock::system_clock;
-use hal::pin::PinConf_;
 use hal::spi;
 
-#[path="../../util/ioreg.rs"] mod ioreg;
+use self::SSPPeripheral::*;
+
+#[path="../../util/ioreg.rs"]
+#[macro_use] mod ioreg;
 
 /// SPI configuration.
 ///
 /// This configuration doesn't manage the chip-select pin, it must be configured
-/// and used externally via GPIOConf.
+/// and used externally as a GPIO pin.
 pub struct SPIConf {
   /// Peripheral to use, mcu-specific.
   pub peripheral: SSPPeripheral,
@@ -45,47 +58,45 @@ pub struct SPIConf {
   /// The divisor is currently hardcoded and is equal to 1.
   pub frequency: u32,
 
-  /// MOSI pin to use, supports NotConnected pin.
-  pub mosi: PinConf_,
-  /// MISO pin to use, supports NotConnected pin.
-  pub miso: PinConf_,
-  /// SCLK pin to use, supports NotConnected pin.
-  pub sclk: PinConf_,
+  /// MOSI pin to use as port, pin index and function, `None` if not
+  /// connected.
+  pub mosi: Option<(Port, u8, Function)>,
+  /// MISO pin to use as port, pin index and function, `None` if not
+  /// connected.
+  pub miso: Option<(Port, u8, Function)>,
+  /// SCLK pin to use as port, pin index and function, `None` if not
+  /// connected.
+  pub sclk: Option<(Port, u8, Function)>,
 }
 
 impl SPIConf {
   /// Returns a platform-specific object, that implements SPI trait.
   pub fn setup(&self) -> SSP {
-    let ssp = SSP {
-      peripheral: self.peripheral,
-      reg: self.peripheral.reg(),
-    };
-
-    let clock = self.peripheral.peripheral_clock();
-    clock.enable();
-    clock.set_divisor(1);
-    ssp.set_format(self.bits, self.mode);
-    ssp.set_frequency(self.frequency);
-
-    self.mosi.setup();
-    self.miso.setup();
-    self.sclk.setup();
+    let ssp = SSP::new(self.peripheral, self.bits, self.mode, self.frequency);
+
+    for pin in [self.mosi, self.miso, self.sclk].iter() {
+      match *pin {
+        Some((port, index, function)) => {
+          Pin::new(port, index, function, None);
+        },
+        None => {},
+      }
+    }
 
     ssp
   }
 }
 
-/// Opaque object that manages the configured peripheral.
-#[allow(dead_code)]
-pub struct SSP {
-  peripheral: SSPPeripheral, // TODO(farcaller): clean up the warning
-  reg: &'static reg::SSP,
+/// Available SSP peripherals.
+#[allow(missing_docs)]
+#[derive(Clone, Copy)]
+pub enum SSPPeripheral {
+  SSP0,
+  SSP1,
 }
 
-pub enum SSPPeripheral {SSP0, SSP1}
-
 impl SSPPeripheral {
-  fn reg(self) -> &reg::SSP {
+  fn reg(self) -> &'static reg::SSP {
     match self {
       SSP0 => &reg::SSP0,
       SSP1 => &reg::SSP1,
@@ -98,9 +109,116 @@ impl SSPPeripheral {
       SSP1 => SSP1Clock,
     }
   }
+
+  fn dma_requests(self) -> (Request, Request) {
+    match self {
+      SSP0 => (Request::SSP0Tx, Request::SSP0Rx),
+      SSP1 => (Request::SSP1Tx, Request::SSP1Rx),
+    }
+  }
+}
+
+/// Structure describing an SSP instance in SPI master mode.
+#[derive(Clone, Copy)]
+pub struct SSP {
+  peripheral: SSPPeripheral,
+  reg: &'static reg::SSP,
 }
 
 impl SSP {
+  /// Create and setup an SSP.
+  ///
+  /// `mode` is the SPI mode, see
+  /// http://en.wikipedia.org/wiki/Serial_Peripheral_Interface_Bus#Mode_numbers
+  /// for explanation. `frequency` must be lower than core clock.
+  pub fn new(peripheral: SSPPeripheral, bits: u8, mode: u8, frequency: u32)
+      -> SSP {
+    let ssp = SSP {
+      peripheral: peripheral,
+      reg: peripheral.reg(),
+    };
+
+    let clock = peripheral.peripheral_clock();
+    clock.enable();
+    clock.set_divisor(1);
+    ssp.set_format(bits, mode);
+    ssp.set_frequency(frequency);
+
+    ssp
+  }
+
+  /// Writes `data` through `channel`, blocking until all of it has been sent.
+  ///
+  /// Received frames are discarded.
+  pub fn write_dma(&self, channel: &Channel, data: &[u8])
+      -> Result<(), dma::Error> {
+    let (tx_request, _) = self.peripheral.dma_requests();
+    let mut result = Ok(());
+
+    self.reg.set_DMACR(DMACRTx);
+    for chunk in data.chunks(MAX_TRANSFER_SIZE) {
+      let desc = Descriptor::new(
+          dma::Endpoint::Buffer(chunk.as_ptr() as u32),
+          dma::Endpoint::Fixed(self.data_address()),
+          chunk.len(), dma::Width::Bits8);
+      unsafe { channel.start(&desc, Flow::MemoryToPeripheral(tx_request)) };
+      result = channel.wait();
+      if result.is_err() {
+        break;
+      }
+    }
+    self.reg.set_DMACR(0);
+
+    while !self.written() {}
+    while self.readable() {
+      self.reg.DR();
+    }
+    // The ignored frames overflowed the receive FIFO
+    self.reg.set_ICR(ICRRxOverrun);
+    result
+  }
+
+  /// Sends `buf` through `tx` and replaces its contents with the frames
+  /// received through `rx`, blocking until done.
+  ///
+  /// `rx` should have a higher priority (lower index) than `tx`, so that the
+  /// receive FIFO never overflows.
+  pub fn transfer_dma(&self, tx: &Channel, rx: &Channel, buf: &mut [u8])
+      -> Result<(), dma::Error> {
+    let (tx_request, rx_request) = self.peripheral.dma_requests();
+    let mut result = Ok(());
+
+    self.reg.set_DMACR(DMACRTx | DMACRRx);
+    for chunk in buf.chunks_mut(MAX_TRANSFER_SIZE) {
+      let addr = chunk.as_mut_ptr() as u32;
+      let rx_desc = Descriptor::new(
+          dma::Endpoint::Fixed(self.data_address()),
+          dma::Endpoint::Buffer(addr),
+          chunk.len(), dma::Width::Bits8);
+      // Every frame is sent before the one received in its place is stored.
+      let tx_desc = Descriptor::new(
+          dma::Endpoint::Buffer(addr),
+          dma::Endpoint::Fixed(self.data_address()),
+          chunk.len(), dma::Width::Bits8);
+      unsafe {
+        rx.start(&rx_desc, Flow::PeripheralToMemory(rx_request));
+        tx.start(&tx_desc, Flow::MemoryToPeripheral(tx_request));
+      }
+      result = tx.wait().and(rx.wait());
+      if result.is_err() {
+        tx.abort();
+        rx.abort();
+        break;
+      }
+    }
+    self.reg.set_DMACR(0);
+    result
+  }
+
+  fn data_address(&self) -> u32 {
+    self.reg as *const reg::SSP as u32 + DROffset
+  }
+
   #[allow(non_snake_case)]
   fn set_format(&self, bits: u8, mode: u8) {
     let slave = false;
@@ -200,6 +318,43 @@ impl SSP {
   }
 }
 
+impl spi::Frames for SSP {
+  /// The receive FIFO holds `FifoDepth` frames, so it never overflows.
+  fn frames_in_flight(&self) -> usize {
+    FifoDepth
+  }
+
+  fn tx_ready(&self) -> bool {
+    self.writeable()
+  }
+
+  fn rx_ready(&self) -> bool {
+    self.readable()
+  }
+
+  fn busy(&self) -> bool {
+    !self.written()
+  }
+
+  fn write_frame(&self, value: u8) {
+    self.reg.set_DR(value as u32);
+  }
+
+  fn read_frame(&self) -> u8 {
+    self.reg.DR() as u8
+  }
+}
+
+impl spi::Configure for SSP {
+  type Settings = spi::Settings;
+
+  fn configure(&self, settings: spi::Settings) {
+    while !self.written() {}
+    self.set_format(8, settings.mode);
+    self.set_frequency(settings.frequency);
+  }
+}
+
 impl spi::Spi for SSP {
   fn write(&self, value: u8) {
     loop { if self.writeable() {
@@ -219,22 +374,52 @@ impl spi::Spi for SSP {
     }
     (self.reg.DR() & 0xff) as u8
   }
+
+  fn flush(&self) {
+    while !self.written() {}
+  }
+
+  fn write_all(&self, data: &[u8]) {
+    spi::write_frames(self, data);
+  }
+
+  fn transfer_in_place(&self, buf: &mut [u8]) {
+    spi::transfer_frames(self, buf);
+  }
+
+  fn read_into(&self, buf: &mut [u8], fill: u8) {
+    spi::read_frames(self, buf, fill);
+  }
 }
 
+#[allow(non_upper_case_globals)]
+static FifoDepth: usize = 8;
+
+#[allow(non_upper_case_globals)]
+static DROffset: u32 = 0x08;
+
+#[allow(non_upper_case_globals)]
+static DMACRRx: u32 = 0b01;
+#[allow(non_upper_case_globals)]
+static DMACRTx: u32 = 0b10;
+
+#[allow(non_upper_case_globals)]
+static ICRRxOverrun: u32 = 0b01;
+
 mod reg {
   use volatile_cell::VolatileCell;
 
-  ioreg_old!(SSP: u32, CR0, CR1, DR, SR, CPSR, IMSC, RIS, MIS, ICR, DMACR)
-  reg_rw!(SSP, u32, CR0,   set_CR0,   CR0)
-  reg_rw!(SSP, u32, CR1,   set_CR1,   CR1)
-  reg_rw!(SSP, u32, DR,    set_DR,    DR)
-  reg_r!( SSP, u32, SR,               SR)
-  reg_rw!(SSP, u32, CPSR,  set_CPSR,  CPSR)
-  reg_rw!(SSP, u32, IMSC,  set_IMSC,  IMSC)
-  reg_rw!(SSP, u32, RIS,   set_RIS,   RIS)
-  reg_rw!(SSP, u32, MIS,   set_MIS,   MIS)
-  reg_rw!(SSP, u32, ICR,   set_ICR,   ICR)
-  reg_rw!(SSP, u32, DMACR, set_DMACR, DMACR)
+  ioreg_old!(SSP: u32, CR0, CR1, DR, SR, CPSR, IMSC, RIS, MIS, ICR, DMACR);
+  reg_rw!(SSP, u32, CR0,   set_CR0,   CR0);
+  reg_rw!(SSP, u32, CR1,   set_CR1,   CR1);
+  reg_rw!(SSP, u32, DR,    set_DR,    DR);
+  reg_r!( SSP, u32, SR,               SR);
+  reg_rw!(SSP, u32, CPSR,  set_CPSR,  CPSR);
+  reg_rw!(SSP, u32, IMSC,  set_IMSC,  IMSC);
+  reg_rw!(SSP, u32, RIS,   set_RIS,   RIS);
+  reg_rw!(SSP, u32, MIS,   set_MIS,   MIS);
+  reg_rw!(SSP, u32, ICR,   set_ICR,   ICR);
+  reg_rw!(SSP, u32, DMACR, set_DMACR, DMACR);
 
   extern {
     #[link_name="lpc17xx_iomem_SSP0"] pub static SSP0: SSP;
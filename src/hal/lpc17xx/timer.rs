@@ -17,9 +17,26 @@
 Timer configuration.
 
 This code supports all four primary timers of the MCU.
+
+Each timer provides four alarms on its match registers. Their handlers are
+called from `handle_interrupt()`, which the application calls from the
+`isr_timer_0` to `isr_timer_3` handlers of the timers it uses.
+
+The two capture inputs of each timer (CAPn.0 and CAPn.1) are exposed through
+`timer::Capture`. The matching pin has to be switched to its `capN_M` function
+separately, e.g. P0.4 to `AltFunction3` for CAP2.0. When capturing both edges,
+the timer listens for one direction at a time and switches after each capture,
+starting with a rising edge.
 */
 
+use core::intrinsics::abort;
+
+use hal::cortex_m3::irq::NoInterrupts;
+use hal::cortex_m3::nvic;
+use hal::lpc17xx::peripheral_clock::PeripheralClock;
+use hal::pin::Edge;
 use hal::timer;
+use hal::timer::{AlarmAction, AlarmHandler, CaptureEvent};
 
 use self::TimerPeripheral::*;
 
@@ -36,6 +53,30 @@ pub enum TimerPeripheral {
   Timer3,
 }
 
+impl TimerPeripheral {
+  fn reg(self) -> &'static reg::TIMER {
+    match self {
+      Timer0 => &reg::TIMER0,
+      Timer1 => &reg::TIMER1,
+      Timer2 => &reg::TIMER2,
+      Timer3 => &reg::TIMER3,
+    }
+  }
+
+  fn peripheral_clock(self) -> PeripheralClock {
+    match self {
+      Timer0 => PeripheralClock::TIM0Clock,
+      Timer1 => PeripheralClock::TIM1Clock,
+      Timer2 => PeripheralClock::TIM2Clock,
+      Timer3 => PeripheralClock::TIM3Clock,
+    }
+  }
+
+  fn irq(self) -> usize {
+    self as usize + 1
+  }
+}
+
 /// Configuration for timer.
 #[derive(Clone, Copy)]
 pub struct TimerConf {
@@ -50,19 +91,15 @@ pub struct TimerConf {
 /// Struct describing a timer instance.
 #[derive(Clone, Copy)]
 pub struct Timer {
+  peripheral: TimerPeripheral,
   reg: &'static reg::TIMER,
 }
 
 impl Timer {
   /// Create an start a timer.
   pub fn new(peripheral: TimerPeripheral, counter: u32, divisor: u8) -> Timer {
-    use hal::lpc17xx::peripheral_clock::PeripheralClock as Clock;
-    let (clock, reg) = match peripheral {
-      Timer0 => (Clock::TIM0Clock, &reg::TIMER0),
-      Timer1 => (Clock::TIM1Clock, &reg::TIMER1),
-      Timer2 => (Clock::TIM2Clock, &reg::TIMER2),
-      Timer3 => (Clock::TIM3Clock, &reg::TIMER3),
-    };
+    let clock = peripheral.peripheral_clock();
+    let reg = peripheral.reg();
 
     clock.enable();
     clock.set_divisor(divisor);
@@ -73,9 +110,30 @@ impl Timer {
     reg.set_TCR(1);
 
     Timer {
+      peripheral: peripheral,
       reg: reg,
     }
   }
+
+  fn arm(&self, alarm: usize, ticks: u32, handler: AlarmHandler,
+      period: Option<u32>) {
+    if alarm >= ALARM_COUNT {
+      unsafe { abort() };
+    }
+
+    let _crit = NoInterrupts::new();
+    unsafe {
+      Alarms[self.peripheral as usize][alarm] = Alarm {
+        handler: Some(handler),
+        period: period,
+        rearmed: true,
+      };
+    }
+    set_match(self.reg, alarm, self.reg.TC().wrapping_add(ticks));
+    self.reg.set_IR(1 << alarm);
+    self.reg.set_MCR(self.reg.MCR() | (MCRInterrupt << (alarm * 3)));
+    nvic::enable_irq(self.peripheral.irq());
+  }
 }
 
 impl timer::Timer for Timer {
@@ -85,6 +143,284 @@ impl timer::Timer for Timer {
   }
 }
 
+impl timer::Alarms for Timer {
+  fn alarm_count(&self) -> usize {
+    ALARM_COUNT
+  }
+
+  fn set_alarm(&self, alarm: usize, ticks: u32, handler: AlarmHandler) {
+    self.arm(alarm, ticks, handler, None);
+  }
+
+  fn set_periodic_alarm(&self, alarm: usize, period: u32,
+      handler: AlarmHandler) {
+    self.arm(alarm, period, handler, Some(period));
+  }
+
+  fn cancel_alarm(&self, alarm: usize) {
+    if alarm >= ALARM_COUNT {
+      unsafe { abort() };
+    }
+
+    let _crit = NoInterrupts::new();
+    disable_match(self.reg, alarm);
+    unsafe { Alarms[self.peripheral as usize][alarm].handler = None };
+  }
+}
+
+impl timer::Capture for Timer {
+  fn capture_count(&self) -> usize {
+    CAPTURE_COUNT
+  }
+
+  fn start_capture(&self, channel: usize, edge: Edge) {
+    if channel >= CAPTURE_COUNT {
+      unsafe { abort() };
+    }
+
+    let _crit = NoInterrupts::new();
+    let first = match edge {
+      Edge::Both => Edge::Rising,
+      e          => e,
+    };
+    unsafe {
+      Captures[self.peripheral as usize][channel] = CaptureQueue {
+        mode: Some(edge),
+        next: first,
+        ..CAPTURE_QUEUE_INIT
+      };
+    }
+    set_capture_edge(self.reg, channel, Some(first));
+    self.reg.set_IR(IRCapture << channel);
+    nvic::enable_irq(self.peripheral.irq());
+  }
+
+  fn stop_capture(&self, channel: usize) {
+    if channel >= CAPTURE_COUNT {
+      unsafe { abort() };
+    }
+
+    let _crit = NoInterrupts::new();
+    set_capture_edge(self.reg, channel, None);
+    unsafe { Captures[self.peripheral as usize][channel].mode = None };
+  }
+
+  fn read_capture(&self, channel: usize) -> Option<CaptureEvent> {
+    if channel >= CAPTURE_COUNT {
+      unsafe { abort() };
+    }
+
+    let _crit = NoInterrupts::new();
+    unsafe { Captures[self.peripheral as usize][channel].pop() }
+  }
+
+  fn capture_overruns(&self, channel: usize) -> usize {
+    if channel >= CAPTURE_COUNT {
+      unsafe { abort() };
+    }
+
+    unsafe { Captures[self.peripheral as usize][channel].overruns }
+  }
+}
+
+const ALARM_COUNT: usize = 4;
+
+#[derive(Clone, Copy)]
+struct Alarm {
+  handler: Option<AlarmHandler>,
+  period: Option<u32>,
+  /// Set when the alarm is armed, so that the ISR can tell if the handler
+  /// re-armed it.
+  rearmed: bool,
+}
+
+const ALARM_INIT: Alarm = Alarm {
+  handler: None,
+  period: None,
+  rearmed: false,
+};
+
+#[allow(non_upper_case_globals)]
+static mut Alarms: [[Alarm; ALARM_COUNT]; 4] = [[ALARM_INIT; ALARM_COUNT]; 4];
+
+const CAPTURE_COUNT: usize = 2;
+const CAPTURE_QUEUE_SIZE: usize = 16;
+
+/// Queue of captured events for one channel.
+///
+/// One slot is kept free to tell a full queue from an empty one.
+#[derive(Clone, Copy)]
+struct CaptureQueue {
+  events: [CaptureEvent; CAPTURE_QUEUE_SIZE],
+  head: usize,
+  tail: usize,
+  overruns: usize,
+  /// Edges requested by the user, `None` if the channel is stopped.
+  mode: Option<Edge>,
+  /// Edge the hardware is currently listening for.
+  next: Edge,
+}
+
+impl CaptureQueue {
+  fn push(&mut self, event: CaptureEvent) {
+    let next = (self.head + 1) % CAPTURE_QUEUE_SIZE;
+    if next == self.tail {
+      self.overruns += 1;
+      return;
+    }
+    self.events[self.head] = event;
+    self.head = next;
+  }
+
+  fn pop(&mut self) -> Option<CaptureEvent> {
+    if self.head == self.tail {
+      return None;
+    }
+    let event = self.events[self.tail];
+    self.tail = (self.tail + 1) % CAPTURE_QUEUE_SIZE;
+    Some(event)
+  }
+}
+
+const CAPTURE_EVENT_INIT: CaptureEvent = CaptureEvent {
+  timestamp: 0,
+  edge: Edge::Rising,
+};
+
+const CAPTURE_QUEUE_INIT: CaptureQueue = CaptureQueue {
+  events: [CAPTURE_EVENT_INIT; CAPTURE_QUEUE_SIZE],
+  head: 0,
+  tail: 0,
+  overruns: 0,
+  mode: None,
+  next: Edge::Rising,
+};
+
+#[allow(non_upper_case_globals)]
+static mut Captures: [[CaptureQueue; CAPTURE_COUNT]; 4] =
+    [[CAPTURE_QUEUE_INIT; CAPTURE_COUNT]; 4];
+
+#[allow(non_upper_case_globals)]
+static MCRInterrupt: u32 = 0b001;
+
+#[allow(non_upper_case_globals)]
+static CCRRising: u32 = 0b001;
+#[allow(non_upper_case_globals)]
+static CCRFalling: u32 = 0b010;
+#[allow(non_upper_case_globals)]
+static CCRInterrupt: u32 = 0b100;
+
+/// IR bit of the first capture channel.
+#[allow(non_upper_case_globals)]
+static IRCapture: u32 = 1 << 4;
+
+fn get_capture(reg: &reg::TIMER, channel: usize) -> u32 {
+  match channel {
+    0 => reg.CR0(),
+    _ => reg.CR1(),
+  }
+}
+
+/// Makes the channel capture on the given edge, or disables it.
+fn set_capture_edge(reg: &reg::TIMER, channel: usize, edge: Option<Edge>) {
+  let bits = match edge {
+    Some(Edge::Rising)  => CCRRising | CCRInterrupt,
+    Some(Edge::Falling) => CCRFalling | CCRInterrupt,
+    Some(Edge::Both)    => CCRRising | CCRFalling | CCRInterrupt,
+    None                => 0,
+  };
+  let shift = channel * 3;
+  reg.set_CCR((reg.CCR() & !(0b111 << shift)) | (bits << shift));
+}
+
+fn get_match(reg: &reg::TIMER, alarm: usize) -> u32 {
+  match alarm {
+    0 => reg.MR0(),
+    1 => reg.MR1(),
+    2 => reg.MR2(),
+    _ => reg.MR3(),
+  }
+}
+
+fn set_match(reg: &reg::TIMER, alarm: usize, value: u32) {
+  match alarm {
+    0 => reg.set_MR0(value),
+    1 => reg.set_MR1(value),
+    2 => reg.set_MR2(value),
+    _ => reg.set_MR3(value),
+  }
+}
+
+fn disable_match(reg: &reg::TIMER, alarm: usize) {
+  reg.set_MCR(reg.MCR() & !(MCRInterrupt << (alarm * 3)));
+}
+
+/// Services the alarms and captures of `peripheral`.
+///
+/// Unsafe because it accesses their state without a critical section, it
+/// must only be called from the interrupt handler of the timer.
+pub unsafe fn handle_interrupt(peripheral: TimerPeripheral) {
+  let reg = peripheral.reg();
+  let ir = reg.IR();
+
+  for channel in 0..CAPTURE_COUNT {
+    if ir & (IRCapture << channel) == 0 {
+      continue;
+    }
+    reg.set_IR(IRCapture << channel);
+
+    let queue = &mut Captures[peripheral as usize][channel];
+    let mode = match queue.mode {
+      Some(mode) => mode,
+      None => continue,
+    };
+    queue.push(CaptureEvent {
+      timestamp: get_capture(reg, channel),
+      edge: queue.next,
+    });
+    if mode == Edge::Both {
+      queue.next = match queue.next {
+        Edge::Rising => Edge::Falling,
+        _            => Edge::Rising,
+      };
+      set_capture_edge(reg, channel, Some(queue.next));
+    }
+  }
+
+  for alarm in 0..ALARM_COUNT {
+    if ir & (1 << alarm) == 0 {
+      continue;
+    }
+    reg.set_IR(1 << alarm);
+
+    let state = &mut Alarms[peripheral as usize][alarm];
+    let handler = match state.handler {
+      Some(handler) => handler,
+      None => {
+        disable_match(reg, alarm);
+        continue;
+      },
+    };
+    state.rearmed = false;
+    let action = handler();
+
+    // The handler might have re-armed or cancelled the alarm itself
+    if state.rearmed || state.handler.is_none() {
+      continue;
+    }
+    match (action, state.period) {
+      (AlarmAction::Continue, Some(period)) =>
+          set_match(reg, alarm, get_match(reg, alarm).wrapping_add(period)),
+      (AlarmAction::Reschedule(ticks), _) =>
+          set_match(reg, alarm, reg.TC().wrapping_add(ticks)),
+      _ => {
+        disable_match(reg, alarm);
+        state.handler = None;
+      },
+    }
+  }
+}
+
 mod reg {
   use volatile_cell::VolatileCell;
 
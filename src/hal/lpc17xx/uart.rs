@@ -18,16 +18,26 @@ UART configuration.
 
 This code doesn't support UART1, while it really should (UART1 has more features
 than other UARTs in MCU).
+
+`UART` busy-waits on the hardware for every byte, or moves whole blocks with
+the GPDMA through `write_dma()` and `read_dma()`. `BufferedUART` queues data in
+software ring buffers that are serviced from the UART interrupts instead.
 */
 
 use core::intrinsics::abort;
+use core::option::Option::{self, Some, None};
 
 use hal::lpc17xx::peripheral_clock::PeripheralClock;
 use hal::lpc17xx::peripheral_clock::PeripheralClock::UART0Clock;
 use hal::lpc17xx::peripheral_clock::PeripheralClock::UART2Clock;
 use hal::lpc17xx::peripheral_clock::PeripheralClock::UART3Clock;
-use drivers::chario::CharIO;
+use drivers::chario::{CharIO, CharInput};
+use hal::cortex_m3::irq::NoInterrupts;
+use hal::cortex_m3::nvic;
+use hal::dma::{self, Channel as DmaChannel};
+use hal::lpc17xx::dma::{Channel, Descriptor, Flow, Request, MAX_TRANSFER_SIZE};
 use hal::uart;
+use util::ring_buffer::{RingBuffer, RING_BUFFER_INIT};
 
 use self::UARTPeripheral::*;
 
@@ -131,6 +141,7 @@ enum FIFOTriggerLevel {
 /// Structure describing a UART instance.
 #[derive(Clone)]
 pub struct UART {
+  peripheral: UARTPeripheral,
   reg: &'static reg::UART,
   clock: PeripheralClock,
 }
@@ -151,6 +162,30 @@ impl UARTPeripheral {
       UART3 => UART3Clock,
     }
   }
+
+  fn dma_requests(self) -> (Request, Request) {
+    match self {
+      UART0 => (Request::UART0Tx, Request::UART0Rx),
+      UART2 => (Request::UART2Tx, Request::UART2Rx),
+      UART3 => (Request::UART3Tx, Request::UART3Rx),
+    }
+  }
+
+  fn irq(self) -> usize {
+    match self {
+      UART0 => 5,
+      UART2 => 7,
+      UART3 => 8,
+    }
+  }
+
+  unsafe fn buffers(self) -> &'static mut UARTBuffers {
+    match self {
+      UART0 => &mut Buffers[0],
+      UART2 => &mut Buffers[1],
+      UART3 => &mut Buffers[2],
+    }
+  }
 }
 
 impl UART {
@@ -158,6 +193,7 @@ impl UART {
   pub fn new(peripheral: UARTPeripheral, baudrate: u32, word_len: u8,
       parity: uart::Parity, stop_bits: u8) -> UART {
     let uart = UART {
+      peripheral: peripheral,
       reg: peripheral.reg(),
       clock: peripheral.peripheral_clock(),
     };
@@ -166,11 +202,66 @@ impl UART {
     uart.set_baud_rate(baudrate);
     uart.set_mode(WordLen::from_u8(word_len), parity,
         StopBit::from_u8(stop_bits));
-    uart.set_fifo_enabled(true, true);
+    uart.set_fifo_enabled(true, true, false);
 
     uart
   }
 
+  /// Writes `data` through `channel`, blocking until all of it has been queued
+  /// in the TX FIFO.
+  ///
+  /// The FIFO DMA mode is only enabled for the duration of the transfer.
+  pub fn write_dma(&self, channel: &Channel, data: &[u8])
+      -> Result<(), dma::Error> {
+    let (tx_request, _) = self.peripheral.dma_requests();
+    let mut result = Ok(());
+
+    self.set_fifo_enabled(true, false, true);
+    for chunk in data.chunks(MAX_TRANSFER_SIZE) {
+      let desc = Descriptor::new(
+          dma::Endpoint::Buffer(chunk.as_ptr() as u32),
+          dma::Endpoint::Fixed(self.data_address()),
+          chunk.len(), dma::Width::Bits8);
+      unsafe { channel.start(&desc, Flow::MemoryToPeripheral(tx_request)) };
+      result = channel.wait();
+      if result.is_err() {
+        break;
+      }
+    }
+    self.set_fifo_enabled(true, false, false);
+    result
+  }
+
+  /// Fills `buf` with data received through `channel`, blocking until it is
+  /// full.
+  ///
+  /// The FIFO DMA mode is only enabled for the duration of the transfer.
+  pub fn read_dma(&self, channel: &Channel, buf: &mut [u8])
+      -> Result<(), dma::Error> {
+    let (_, rx_request) = self.peripheral.dma_requests();
+    let mut result = Ok(());
+
+    self.set_fifo_enabled(true, false, true);
+    for chunk in buf.chunks_mut(MAX_TRANSFER_SIZE) {
+      let desc = Descriptor::new(
+          dma::Endpoint::Fixed(self.data_address()),
+          dma::Endpoint::Buffer(chunk.as_mut_ptr() as u32),
+          chunk.len(), dma::Width::Bits8);
+      unsafe { channel.start(&desc, Flow::PeripheralToMemory(rx_request)) };
+      result = channel.wait();
+      if result.is_err() {
+        break;
+      }
+    }
+    self.set_fifo_enabled(true, false, false);
+    result
+  }
+
+  /// RBR and THR share the first word of the block.
+  fn data_address(&self) -> u32 {
+    self.reg as *const reg::UART as u32
+  }
+
   fn uart_clock(&self) -> u32 {
     self.clock.frequency()
   }
@@ -203,7 +294,7 @@ impl UART {
     (*(self.reg)).set_LCR(new_lcr as u32);
   }
 
-  fn set_fifo_enabled(&self, enabled: bool, reset: bool) {
+  fn set_fifo_enabled(&self, enabled: bool, reset: bool, dma: bool) {
     use self::FIFOEnabled::*;
     use self::FIFODmaMode::*;
     use self::FIFOTriggerLevel::*;
@@ -213,7 +304,10 @@ impl UART {
     } | match reset {
       true  => FIFOResetTx & FIFOResetRx,
       false => 0,
-    } | FDDisabled as u8 | FT1char as u8;
+    } | match dma {
+      true  => FDEnabled as u8,
+      false => FDDisabled as u8,
+    } | FT1char as u8;
 
     (*(self.reg)).set_FCR(val as u32);
   }
@@ -289,6 +383,233 @@ impl CharIO for UART {
   }
 }
 
+impl CharInput for UART {
+  fn try_getc(&self) -> Option<char> {
+    if self.reg.LSR() as u8 & LSRRxDataReady == LSRRxDataReady {
+      Some(self.reg.RBR() as u8 as char)
+    } else {
+      None
+    }
+  }
+}
+
+/// Receive line errors latched by a `BufferedUART`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LineErrors {
+  /// A received character had a wrong parity bit.
+  pub parity: bool,
+  /// A received character had no valid stop bit.
+  pub framing: bool,
+  /// The receive line was held low for longer than a character.
+  pub break_interrupt: bool,
+}
+
+/// Software buffers of one `BufferedUART`, shared with its ISR.
+struct UARTBuffers {
+  tx: RingBuffer,
+  rx: RingBuffer,
+  overruns: u32,
+  line_errors: u8,
+}
+
+const UART_BUFFERS_INIT: UARTBuffers = UARTBuffers {
+  tx: RING_BUFFER_INIT,
+  rx: RING_BUFFER_INIT,
+  overruns: 0,
+  line_errors: 0,
+};
+
+impl UARTBuffers {
+  /// Reads LSR, which clears its error bits, and latches the errors.
+  ///
+  /// Every LSR read of a `BufferedUART` has to go through here, or errors
+  /// could be lost to whichever of the ISR and the caller reads it first.
+  fn line_status(&mut self, reg: &reg::UART) -> u8 {
+    let lsr = reg.LSR() as u8;
+    if lsr & LSROverrun == LSROverrun {
+      self.overruns += 1;
+    }
+    self.line_errors |= lsr & LSRLineErrors;
+    lsr
+  }
+}
+
+#[allow(non_upper_case_globals)]
+static mut Buffers: [UARTBuffers; 3] = [
+  UART_BUFFERS_INIT,
+  UART_BUFFERS_INIT,
+  UART_BUFFERS_INIT,
+];
+
+/// Interrupt-driven UART instance.
+///
+/// Output is queued in a TX ring buffer and moved to the hardware FIFO by the
+/// UART interrupt, so `putc` only blocks while the buffer is full. Received
+/// bytes are queued by the same interrupt until read through `CharInput`.
+///
+/// The application has to call `handle_interrupt()` from the interrupt handler
+/// of the UART (`isr_uart_0`, `isr_uart_2` or `isr_uart_3`), which platform
+/// tree defines for UARTs with `buffered = true`. `putc` must not be called
+/// with interrupts disabled, as it would wait forever on a full buffer.
+#[derive(Clone, Copy)]
+pub struct BufferedUART {
+  reg: &'static reg::UART,
+  peripheral: UARTPeripheral,
+}
+
+impl BufferedUART {
+  /// Create and setup a buffered UART.
+  pub fn new(peripheral: UARTPeripheral, baudrate: u32, word_len: u8,
+      parity: uart::Parity, stop_bits: u8) -> BufferedUART {
+    let uart = UART::new(peripheral, baudrate, word_len, parity, stop_bits);
+
+    {
+      let _crit = NoInterrupts::new();
+      let buffers = unsafe { peripheral.buffers() };
+      buffers.tx.clear();
+      buffers.rx.clear();
+      buffers.overruns = 0;
+      buffers.line_errors = 0;
+    }
+
+    uart.reg.set_IER((IERRxData | IERTHREmpty | IERRxLineStatus) as u32);
+    nvic::enable_irq(peripheral.irq());
+
+    BufferedUART {
+      reg: uart.reg,
+      peripheral: peripheral,
+    }
+  }
+
+  /// Returns the number of received bytes waiting to be read.
+  pub fn rx_pending(&self) -> usize {
+    let _crit = NoInterrupts::new();
+    unsafe { self.peripheral.buffers() }.rx.len()
+  }
+
+  /// Returns the number of bytes queued for transmission.
+  pub fn tx_pending(&self) -> usize {
+    let _crit = NoInterrupts::new();
+    unsafe { self.peripheral.buffers() }.tx.len()
+  }
+
+  /// Returns the number of received bytes lost so far, either because the RX
+  /// buffer was full or because the hardware FIFO overflowed.
+  pub fn overruns(&self) -> u32 {
+    let _crit = NoInterrupts::new();
+    unsafe { self.peripheral.buffers() }.overruns
+  }
+
+  /// Resets the overrun counter.
+  pub fn reset_overruns(&self) {
+    let _crit = NoInterrupts::new();
+    unsafe { self.peripheral.buffers() }.overruns = 0;
+  }
+
+  /// Returns the receive line errors seen since the last reset.
+  pub fn line_errors(&self) -> LineErrors {
+    let _crit = NoInterrupts::new();
+    let errors = unsafe { self.peripheral.buffers() }.line_errors;
+    LineErrors {
+      parity: errors & LSRParityError != 0,
+      framing: errors & LSRFramingError != 0,
+      break_interrupt: errors & LSRBreakInterrupt != 0,
+    }
+  }
+
+  /// Resets the latched receive line errors.
+  pub fn reset_line_errors(&self) {
+    let _crit = NoInterrupts::new();
+    unsafe { self.peripheral.buffers() }.line_errors = 0;
+  }
+
+  /// Waits until all queued output has left the transmitter.
+  pub fn flush(&self) {
+    wait_for!(self.tx_pending() == 0 &&
+        self.line_status() & LSRTxEmpty == LSRTxEmpty);
+  }
+
+  fn line_status(&self) -> u8 {
+    let _crit = NoInterrupts::new();
+    unsafe { self.peripheral.buffers() }.line_status(self.reg)
+  }
+}
+
+impl CharIO for BufferedUART {
+  fn putc(&self, value: char) {
+    loop {
+      let _crit = NoInterrupts::new();
+      let buffers = unsafe { self.peripheral.buffers() };
+
+      // The THRE interrupt only fires when the FIFO drains, so an idle
+      // transmitter has to be kicked directly.
+      if buffers.tx.is_empty() &&
+          buffers.line_status(self.reg) & LSRTHREmpty == LSRTHREmpty {
+        self.reg.set_THR(value as u32);
+        return;
+      }
+      if buffers.tx.push(value as u8) {
+        return;
+      }
+    }
+  }
+}
+
+impl CharInput for BufferedUART {
+  fn try_getc(&self) -> Option<char> {
+    let _crit = NoInterrupts::new();
+    unsafe { self.peripheral.buffers() }.rx.pop().map(|b| b as char)
+  }
+}
+
+/// Services the interrupt of a `BufferedUART`.
+///
+/// Unsafe because it accesses the buffers without a critical section, it must
+/// only be called from the UART's interrupt handler, e.g.
+///
+/// ```ignore
+/// #[no_mangle]
+/// pub unsafe extern fn isr_uart_0() {
+///   uart::handle_interrupt(UARTPeripheral::UART0);
+/// }
+/// ```
+pub unsafe fn handle_interrupt(peripheral: UARTPeripheral) {
+  let reg = peripheral.reg();
+  let buffers = peripheral.buffers();
+
+  loop {
+    let iir = reg.IIR();
+    if iir & 1 != 0 {
+      // no interrupt pending
+      break;
+    }
+    match (iir >> 1) & 0b111 {
+      // receive line status, cleared by reading LSR
+      0b011 => {
+        buffers.line_status(reg);
+      },
+      // receive data available or character timeout
+      0b010 | 0b110 => {
+        while buffers.line_status(reg) & LSRRxDataReady == LSRRxDataReady {
+          if !buffers.rx.push(reg.RBR() as u8) {
+            buffers.overruns += 1;
+          }
+        }
+      },
+      // THRE, cleared by reading IIR
+      0b001 => {
+        for _ in 0..TxFIFODepth {
+          match buffers.tx.pop() {
+            Some(b) => reg.set_THR(b as u32),
+            None => break,
+          }
+        }
+      },
+      _ => {},
+    }
+  }
+}
+
 #[allow(non_upper_case_globals)]
 static FIFOResetRx: u8 = 0b1_0;
 #[allow(non_upper_case_globals)]
@@ -297,8 +618,32 @@ static FIFOResetTx: u8 = 0b1_0_0;
 #[allow(non_upper_case_globals)]
 static LCRModeMask: u8 = 0b1_11_1_1_11;
 
+#[allow(non_upper_case_globals)]
+static LSRRxDataReady: u8 = 0x01;
+#[allow(non_upper_case_globals)]
+static LSROverrun: u8 = 0x02;
+#[allow(non_upper_case_globals)]
+static LSRParityError: u8 = 0x04;
+#[allow(non_upper_case_globals)]
+static LSRFramingError: u8 = 0x08;
+#[allow(non_upper_case_globals)]
+static LSRBreakInterrupt: u8 = 0x10;
+#[allow(non_upper_case_globals)]
+static LSRLineErrors: u8 = 0x1c;
 #[allow(non_upper_case_globals)]
 static LSRTHREmpty: u8 = 0x20;
+#[allow(non_upper_case_globals)]
+static LSRTxEmpty: u8 = 0x40;
+
+#[allow(non_upper_case_globals)]
+static IERRxData: u8 = 0b001;
+#[allow(non_upper_case_globals)]
+static IERTHREmpty: u8 = 0b010;
+#[allow(non_upper_case_globals)]
+static IERRxLineStatus: u8 = 0b100;
+
+#[allow(non_upper_case_globals)]
+static TxFIFODepth: usize = 16;
 
 mod reg {
   use volatile_cell::VolatileCell;
@@ -95,11 +95,19 @@ pub fn build_uart(builder: &mut Builder, cx: &mut ExtCtxt,
       }.to_string());
   let stop_bits = mode.as_str().chars().nth(2).unwrap().to_digit(10).unwrap() as u8;
 
-  sub.set_type_name("zinc::hal::lpc17xx::uart::UART".to_string());
+  let buffered = sub.get_bool_attr("buffered").unwrap_or(false);
+  let uart_type = TokenString(
+      if buffered {
+        "BufferedUART"
+      } else {
+        "UART"
+      }.to_string());
+
+  sub.set_type_name(format!("zinc::hal::lpc17xx::uart::{}", uart_type.0));
   let uart_name = TokenString(sub.name.clone().unwrap());
 
   let st = quote_stmt!(&*cx,
-      let $uart_name = zinc::hal::lpc17xx::uart::UART::new(
+      let $uart_name = zinc::hal::lpc17xx::uart::$uart_type::new(
           zinc::hal::lpc17xx::uart::$uart_peripheral,
           $baud_rate,
           $word_len,
@@ -107,6 +115,18 @@ pub fn build_uart(builder: &mut Builder, cx: &mut ExtCtxt,
           $stop_bits)
   ).unwrap();
   builder.add_main_statement(st);
+
+  if buffered {
+    let isr_name = TokenString(format!("isr_uart_{}", sub.path));
+    let isr = quote_item!(&*cx,
+        #[no_mangle]
+        pub unsafe extern fn $isr_name() {
+          zinc::hal::lpc17xx::uart::handle_interrupt(
+              zinc::hal::lpc17xx::uart::$uart_peripheral);
+        }
+    ).unwrap();
+    builder.add_type_item(isr.and_then(|i| i));
+  }
 }
 
 pub fn build_uart_gpio(builder: &Builder, uart_idx: usize, name: &str,
@@ -123,7 +143,7 @@ pub fn build_uart_gpio(builder: &Builder, uart_idx: usize, name: &str,
 #[cfg(test)]
 mod test {
   use builder::Builder;
-  use test_helpers::{assert_equal_source, with_parsed};
+  use test_helpers::{assert_equal_items, assert_equal_source, with_parsed};
 
   #[test]
   fn builds_uart() {
@@ -159,6 +179,7 @@ mod test {
                8u8,
                zinc::hal::uart::Parity::Disabled,
                1u8);");
+      assert!(builder.type_items().len() == 1);
 
       let tx_node = pt.get_by_name("uart_tx").unwrap();
       assert!(tx_node.get_string_attr("direction").unwrap() == "out".to_string());
@@ -169,4 +190,49 @@ mod test {
       assert!(rx_node.get_string_attr("function").unwrap() == "rxd0".to_string());
     });
   }
+
+  #[test]
+  fn builds_buffered_uart() {
+    with_parsed("
+      mcu {
+        clock {
+          system_frequency = 100_000_000;
+        }
+      }
+      uart {
+        uart@2 {
+          baud_rate = 115200;
+          mode = \"8N1\";
+          tx = &uart_tx;
+          rx = &uart_rx;
+          buffered = true;
+        }
+      }
+      gpio {
+        uart_tx@10;
+        uart_rx@11;
+      }
+      ", |cx, failed, pt| {
+      let mut builder = Builder::new(pt.clone(), cx);
+      super::build_uart(&mut builder, cx, pt.get_by_name("uart").unwrap());
+      assert!(unsafe{*failed} == false);
+      assert!(builder.main_stmts().len() == 1);
+
+      assert_equal_source(&builder.main_stmts()[0],
+          "let uart = zinc::hal::lpc17xx::uart::BufferedUART::new(
+               zinc::hal::lpc17xx::uart::UARTPeripheral::UART2,
+               115200u32,
+               8u8,
+               zinc::hal::uart::Parity::Disabled,
+               1u8);");
+
+      assert!(builder.type_items().len() == 2);
+      assert_equal_items(&builder.type_items()[1],
+          "#[no_mangle]
+           pub unsafe extern \"C\" fn isr_uart_2() {
+             zinc::hal::lpc17xx::uart::handle_interrupt(
+                 zinc::hal::lpc17xx::uart::UARTPeripheral::UART2);
+           }");
+    });
+  }
 }
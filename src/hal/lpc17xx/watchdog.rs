@@ -0,0 +1,117 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Watchdog timer for NXP LPC17xx.
+//!
+//! The watchdog is clocked from the 4 MHz internal RC oscillator through its
+//! fixed divide by 4 prescaler, so it counts microseconds. Once enabled it
+//! cannot be disabled until the next reset.
+
+use hal::cortex_m3::irq::NoInterrupts;
+use hal::watchdog;
+
+/// Watchdog ticks per millisecond.
+const TICKS_PER_MS: u32 = 1000;
+
+/// Smallest count accepted by WDTC.
+const MIN_COUNT: u32 = 0xff;
+
+/// Set once the timeout flag was seen.
+#[allow(non_upper_case_globals)]
+static mut WatchdogReset: bool = false;
+
+/// Watchdog timer.
+#[derive(Clone, Copy)]
+pub struct Watchdog;
+
+impl Watchdog {
+  /// Returns the watchdog timer.
+  ///
+  /// The timeout flag is only cleared by software or a power-on reset, so it
+  /// is latched here and cleared.
+  pub fn new() -> Watchdog {
+    latch_reset();
+    Watchdog
+  }
+}
+
+impl watchdog::Watchdog for Watchdog {
+  fn start(&self, timeout_ms: u32) {
+    let count = timeout_ms.saturating_mul(TICKS_PER_MS);
+
+    reg::WDT.wdclksel.set_wdsel(reg::WDT_wdclksel_wdsel::IRC);
+    reg::WDT.wdtc.set_count(if count < MIN_COUNT { MIN_COUNT } else { count });
+    reg::WDT.wdmod
+      .set_wden(true)
+      .set_wdreset(true);
+
+    // the watchdog starts counting on the first feed
+    self.feed();
+  }
+
+  fn feed(&self) {
+    // an interrupted feed sequence resets the MCU right away
+    let _crit = NoInterrupts::new();
+    reg::WDT.wdfeed.set_feed(0xaa);
+    reg::WDT.wdfeed.set_feed(0x55);
+  }
+
+  fn caused_reset(&self) -> bool {
+    latch_reset();
+    unsafe { WatchdogReset }
+  }
+}
+
+fn latch_reset() {
+  if reg::WDT.wdmod.wdtof() {
+    unsafe { WatchdogReset = true };
+    reg::WDT.wdmod.set_wdtof(false);
+  }
+}
+
+mod reg {
+  use core::ops::Drop;
+  use volatile_cell::VolatileCell;
+
+  ioregs!(WDT = {
+    0x00 => reg32 wdmod {    //! Mode
+      0 => wden,             //= Enable, cannot be cleared by software
+      1 => wdreset,          //= Reset on timeout
+      2 => wdtof,            //= Timeout flag, set on a watchdog reset
+      3 => wdint: ro,        //= Warning interrupt flag
+    }
+    0x04 => reg32 wdtc {     //! Timeout count
+      0..31 => count,
+    }
+    0x08 => reg32 wdfeed {   //! Feed sequence
+      0..7 => feed: wo,
+    }
+    0x0c => reg32 wdtv {     //! Current count
+      0..31 => value: ro,
+    }
+    0x10 => reg32 wdclksel { //! Clock source
+      0..1 => wdsel {
+        0 => IRC,
+        1 => PCLK,
+        2 => RTC,
+      },
+      31   => wdlock,
+    }
+  });
+
+  extern {
+    #[link_name="lpc17xx_iomem_WDT"] pub static WDT: WDT;
+  }
+}
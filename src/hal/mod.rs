@@ -22,6 +22,7 @@ and each such struct has a `setup()` method that configures the hardware
 */
 
 #[cfg(feature = "mcu_am335x")] pub mod am335x;
+#[cfg(feature = "mcu_lpc11xx")] pub mod lpc11xx;
 #[cfg(feature = "mcu_lpc17xx")] pub mod lpc17xx;
 #[cfg(feature = "mcu_stm32f1")] pub mod stm32f1;
 #[cfg(feature = "mcu_stm32f4")] pub mod stm32f4;
@@ -36,8 +37,15 @@ and each such struct has a `setup()` method that configures the hardware
           feature = "cpu_cortex-m4",
           feature = "cpu_cortex-m7"))]
 mod cortex_common;
+#[cfg(any(feature = "mcu_stm32f1",
+          feature = "mcu_stm32f4",
+          feature = "mcu_stm32f7",
+          feature = "mcu_stm32l1"))]
+mod stm32_common;
 #[cfg(feature = "cpu_cortex-a8")]
 pub mod cortex_a8;
+#[cfg(feature = "cpu_cortex-m0")]
+pub mod cortex_m0;
 #[cfg(feature = "cpu_cortex-m3")]
 pub mod cortex_m3;
 #[cfg(feature = "cpu_cortex-m4")]
@@ -45,13 +53,22 @@ pub mod cortex_m4;
 #[cfg(feature = "cpu_cortex-m7")]
 pub mod cortex_m7;
 
+pub mod adc;
+pub mod dac;
+pub mod dma;
+pub mod flash;
+pub mod i2c;
 pub mod mem_init;
 pub mod pin;
+pub mod power;
 pub mod pwm;
+pub mod rtc;
 pub mod spi;
 pub mod stack;
+pub mod time;
 pub mod timer;
 pub mod uart;
+pub mod watchdog;
 
 #[cfg(target_os = "none")]
 pub mod isr;
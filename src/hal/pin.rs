@@ -53,7 +53,41 @@ pub trait Gpio {
 }
 
 /// Analog Input
+///
+/// See `hal::adc::Adc` for multi-channel converters.
 pub trait Adc {
   /// Read analog input value
   fn read(&self) -> u32;
 }
+
+/// Signal edge that triggers a pin interrupt or a timer capture.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Edge {
+  /// Low to high transition.
+  Rising,
+  /// High to low transition.
+  Falling,
+  /// Any transition.
+  Both,
+}
+
+/// Handler invoked from the interrupt context when a pin triggers.
+pub type PinHandler = fn();
+
+/// GPIO pin that can raise an interrupt on a signal edge.
+///
+/// Handlers are called from the MCU's `handle_interrupt()`, which the
+/// application calls from the pin interrupt vectors it uses.
+pub trait InterruptPin {
+  /// Registers `handler` to be called on `edge` and enables the interrupt.
+  fn set_interrupt(&self, edge: Edge, handler: PinHandler);
+
+  /// Enables the interrupt with the previously configured edge.
+  fn enable_interrupt(&self);
+
+  /// Disables the interrupt, the handler stays registered.
+  fn disable_interrupt(&self);
+
+  /// Clears a pending interrupt flag for this pin.
+  fn clear_interrupt(&self);
+}
@@ -0,0 +1,44 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Common definitions for low-power modes.
+//!
+//! Each mode saves more power than the previous one and takes longer to wake
+//! up from. What a mode maps to and which sources can wake the MCU from it
+//! are specific to each MCU.
+
+/// Low-power modes, from the lightest to the deepest.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mode {
+  /// Core clock stopped, peripherals keep running. Any enabled interrupt
+  /// wakes the core.
+  Sleep,
+  /// Core and most clocks stopped, RAM and registers are retained.
+  DeepSleep,
+  /// As `DeepSleep`, with regulators and flash in their lowest power state.
+  PowerDown,
+  /// Everything but the wake-up logic is powered off. Waking up resets the
+  /// MCU, so entering this mode does not return.
+  DeepPowerDown,
+}
+
+/// Low-power mode control.
+pub trait LowPower {
+  /// Enters `mode` and waits for a wake-up source.
+  ///
+  /// The clock configuration is restored after waking up, before this
+  /// returns.
+  fn enter(&self, mode: Mode);
+}
@@ -57,3 +57,102 @@ pub trait PWMOutput {
     self.set_pulsewidth_us(pulsewidth_us)
   }
 }
+
+/// Splits a PWM period into a prescaler and a reload value.
+///
+/// `timer_clock` is the counter input frequency in Hz and `max_reload` the
+/// largest number of counts the timer supports per period. The prescaler is
+/// kept as small as possible so that the pulse width has the finest
+/// resolution. Both values are returned as divisors, i.e. at least 1, so
+/// timers that count from zero take them minus one.
+pub fn period_to_counts(timer_clock: u32, period_us: u32, max_reload: u32)
+    -> (u32, u32) {
+  let ticks = us_to_ticks(timer_clock, period_us);
+  let max_reload = if max_reload == 0 { 1 } else { max_reload as u64 };
+  let prescaler = (ticks + max_reload - 1) / max_reload;
+  let prescaler = if prescaler == 0 { 1 } else { prescaler };
+  let reload = ticks / prescaler;
+  let reload = if reload == 0 { 1 } else { reload };
+  (prescaler as u32, reload as u32)
+}
+
+/// Converts a duration to timer counts after the prescaler.
+pub fn us_to_counts(timer_clock: u32, prescaler: u32, us: u32) -> u32 {
+  let prescaler = if prescaler == 0 { 1 } else { prescaler as u64 };
+  (us_to_ticks(timer_clock, us) / prescaler) as u32
+}
+
+/// Converts timer counts after the prescaler back to a duration.
+pub fn counts_to_us(timer_clock: u32, prescaler: u32, counts: u32) -> u32 {
+  if timer_clock == 0 {
+    return 0;
+  }
+  (counts as u64 * prescaler as u64 * 1_000_000 / timer_clock as u64) as u32
+}
+
+/// Encodes a dead time, in timer clock cycles, to the 8-bit dead-time
+/// generator format of STM32 advanced timers.
+///
+/// Dead times that cannot be represented exactly are rounded up to the next
+/// supported value, and clamped to the maximum of 1008 cycles.
+pub fn dead_time_generator(ticks: u32) -> u8 {
+  match ticks {
+    0...127    => ticks as u8,
+    128...254  => 0b1000_0000 | ((ticks + 1) / 2 - 64) as u8,
+    255...504  => 0b1100_0000 | ((ticks + 7) / 8 - 32) as u8,
+    505...1008 => 0b1110_0000 | ((ticks + 15) / 16 - 32) as u8,
+    _          => 0xff,
+  }
+}
+
+fn us_to_ticks(timer_clock: u32, us: u32) -> u64 {
+  timer_clock as u64 * us as u64 / 1_000_000
+}
+
+#[cfg(test)]
+mod test {
+  use super::{period_to_counts, us_to_counts, counts_to_us,
+              dead_time_generator};
+
+  #[test]
+  fn period_without_prescaler() {
+    // 20 kHz from 84 MHz fits a 16-bit reload directly.
+    assert_eq!(period_to_counts(84_000_000, 50, 0xffff), (1, 4200));
+  }
+
+  #[test]
+  fn period_with_prescaler() {
+    // 20 ms at 84 MHz is 1_680_000 ticks, too much for 16 bits.
+    let (prescaler, reload) = period_to_counts(84_000_000, 20_000, 0xffff);
+    assert_eq!(prescaler, 26);
+    assert_eq!(reload, 64615);
+    assert!(reload <= 0xffff);
+  }
+
+  #[test]
+  fn period_never_zero() {
+    assert_eq!(period_to_counts(1_000_000, 0, 0xffff), (1, 1));
+  }
+
+  #[test]
+  fn pulse_width_round_trip() {
+    let (prescaler, _) = period_to_counts(84_000_000, 20_000, 0xffff);
+    let counts = us_to_counts(84_000_000, prescaler, 1_500);
+    assert_eq!(counts, 4846);
+    assert_eq!(counts_to_us(84_000_000, prescaler, counts), 1_499);
+  }
+
+  #[test]
+  fn dead_time_ranges() {
+    assert_eq!(dead_time_generator(0), 0);
+    assert_eq!(dead_time_generator(127), 127);
+    assert_eq!(dead_time_generator(128), 0b1000_0000);
+    assert_eq!(dead_time_generator(129), 0b1000_0001);
+    assert_eq!(dead_time_generator(254), 0b1011_1111);
+    assert_eq!(dead_time_generator(256), 0b1100_0000);
+    assert_eq!(dead_time_generator(504), 0b1101_1111);
+    assert_eq!(dead_time_generator(512), 0b1110_0000);
+    assert_eq!(dead_time_generator(1008), 0b1111_1111);
+    assert_eq!(dead_time_generator(5000), 0xff);
+  }
+}
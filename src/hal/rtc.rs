@@ -0,0 +1,255 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Real-time clocks.
+//!
+//! `DateTime` is a calendar date and time without time zone, which converts
+//! to and from Unix timestamps for the years 1970 to 2105.
+
+/// Handler called from the RTC interrupt when an alarm fires.
+///
+/// The application routes the interrupt to the MCU's `handle_interrupt()`.
+pub type AlarmHandler = fn();
+
+/// Days between 0000-03-01 and 1970-01-01 in the proleptic Gregorian
+/// calendar.
+const UNIX_EPOCH_DAYS: u32 = 719_468;
+
+/// Days in a 400 years cycle.
+const DAYS_PER_ERA: u32 = 146_097;
+
+const SECONDS_PER_DAY: u32 = 86_400;
+
+/// Calendar date and time.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DateTime {
+  /// Year, e.g. 2016.
+  pub year: u16,
+  /// Month of the year, 1 to 12.
+  pub month: u8,
+  /// Day of the month, 1 to 31.
+  pub day: u8,
+  /// Hour, 0 to 23.
+  pub hour: u8,
+  /// Minute, 0 to 59.
+  pub minute: u8,
+  /// Second, 0 to 59.
+  pub second: u8,
+}
+
+impl DateTime {
+  /// Creates a new date and time.
+  pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8)
+      -> DateTime {
+    DateTime {
+      year: year,
+      month: month,
+      day: day,
+      hour: hour,
+      minute: minute,
+      second: second,
+    }
+  }
+
+  /// Converts a Unix timestamp, in seconds since 1970-01-01 00:00:00.
+  pub fn from_unix(timestamp: u32) -> DateTime {
+    let days = timestamp / SECONDS_PER_DAY;
+    let seconds = timestamp % SECONDS_PER_DAY;
+
+    // Days since 0000-03-01, so that leap days end the year
+    let days = days + UNIX_EPOCH_DAYS;
+    let era = days / DAYS_PER_ERA;
+    let day_of_era = days - era * DAYS_PER_ERA;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 -
+        day_of_era / 146096) / 365;
+    let day_of_year = day_of_era -
+        (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+      shifted_month + 3
+    } else {
+      shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    DateTime {
+      year: year as u16,
+      month: month as u8,
+      day: day as u8,
+      hour: (seconds / 3600) as u8,
+      minute: (seconds / 60 % 60) as u8,
+      second: (seconds % 60) as u8,
+    }
+  }
+
+  /// Converts to a Unix timestamp.
+  ///
+  /// Dates before 1970 return 0, dates after 2106-02-07 06:28:15 wrap.
+  pub fn to_unix(&self) -> u32 {
+    let days = self.days_since_epoch();
+    if days < 0 {
+      return 0;
+    }
+    (days as u32).wrapping_mul(SECONDS_PER_DAY)
+      .wrapping_add(self.hour as u32 * 3600)
+      .wrapping_add(self.minute as u32 * 60)
+      .wrapping_add(self.second as u32)
+  }
+
+  /// Day of the week, 0 for Sunday to 6 for Saturday.
+  pub fn day_of_week(&self) -> u8 {
+    // 1970-01-01 was a Thursday
+    ((self.days_since_epoch() % 7 + 11) % 7) as u8
+  }
+
+  /// Day of the year, 1 to 366.
+  pub fn day_of_year(&self) -> u16 {
+    let mut day = self.day as u16;
+    for month in 1..self.month {
+      day += days_in_month(self.year, month) as u16;
+    }
+    day
+  }
+
+  /// Returns true if all fields are in range for a real date and time.
+  pub fn is_valid(&self) -> bool {
+    self.month >= 1 && self.month <= 12 &&
+        self.day >= 1 && self.day <= days_in_month(self.year, self.month) &&
+        self.hour < 24 && self.minute < 60 && self.second < 60
+  }
+
+  fn days_since_epoch(&self) -> i32 {
+    let year = self.year as i32 - if self.month <= 2 { 1 } else { 0 };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let shifted_month = (self.month as i32 + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + self.day as i32 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 +
+        day_of_year;
+    era * DAYS_PER_ERA as i32 + day_of_era - UNIX_EPOCH_DAYS as i32
+  }
+}
+
+/// Returns true for leap years of the Gregorian calendar.
+pub fn is_leap_year(year: u16) -> bool {
+  (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Number of days in a month, 1 to 12, of the given year.
+pub fn days_in_month(year: u16, month: u8) -> u8 {
+  match month {
+    4 | 6 | 9 | 11 => 30,
+    2 => if is_leap_year(year) { 29 } else { 28 },
+    _ => 31,
+  }
+}
+
+/// Real-time clock with a calendar, one alarm and battery-backed registers.
+pub trait Rtc {
+  /// Returns the current date and time.
+  fn now(&self) -> DateTime;
+
+  /// Sets the current date and time.
+  fn set(&self, time: DateTime);
+
+  /// Calls `handler` from the RTC interrupt once `time` is reached.
+  ///
+  /// Setting a new alarm replaces the previous one.
+  fn set_alarm(&self, time: DateTime, handler: AlarmHandler);
+
+  /// Cancels the pending alarm, if any.
+  fn cancel_alarm(&self);
+
+  /// Number of 32-bit registers kept while the MCU is powered down.
+  fn backup_register_count(&self) -> usize;
+
+  /// Reads a backup register.
+  fn read_backup(&self, index: usize) -> u32;
+
+  /// Writes a backup register.
+  fn write_backup(&self, index: usize, value: u32);
+}
+
+#[cfg(test)]
+mod test {
+  use super::{DateTime, is_leap_year, days_in_month};
+
+  #[test]
+  fn converts_epoch() {
+    assert_eq!(DateTime::from_unix(0), DateTime::new(1970, 1, 1, 0, 0, 0));
+    assert_eq!(DateTime::new(1970, 1, 1, 0, 0, 0).to_unix(), 0);
+  }
+
+  #[test]
+  fn converts_timestamps() {
+    let cases = [
+      (1_000_000_000, DateTime::new(2001, 9, 9, 1, 46, 40)),
+      (1_456_747_200, DateTime::new(2016, 2, 29, 12, 0, 0)),
+      (951_868_799,   DateTime::new(2000, 2, 29, 23, 59, 59)),
+      (4_294_967_295, DateTime::new(2106, 2, 7, 6, 28, 15)),
+    ];
+    for &(timestamp, time) in cases.iter() {
+      assert_eq!(DateTime::from_unix(timestamp), time);
+      assert_eq!(time.to_unix(), timestamp);
+    }
+  }
+
+  #[test]
+  fn round_trips_timestamps() {
+    let mut timestamp = 0u32;
+    while timestamp < 4_294_000_000 {
+      assert_eq!(DateTime::from_unix(timestamp).to_unix(), timestamp);
+      timestamp += 86_400 + 3_661;
+    }
+  }
+
+  #[test]
+  fn clamps_dates_before_epoch() {
+    assert_eq!(DateTime::new(1969, 12, 31, 23, 59, 59).to_unix(), 0);
+  }
+
+  #[test]
+  fn computes_day_of_week() {
+    assert_eq!(DateTime::new(1970, 1, 1, 0, 0, 0).day_of_week(), 4);
+    assert_eq!(DateTime::new(2016, 2, 29, 0, 0, 0).day_of_week(), 1);
+    assert_eq!(DateTime::new(1969, 12, 28, 0, 0, 0).day_of_week(), 0);
+  }
+
+  #[test]
+  fn computes_day_of_year() {
+    assert_eq!(DateTime::new(2015, 12, 31, 0, 0, 0).day_of_year(), 365);
+    assert_eq!(DateTime::new(2016, 12, 31, 0, 0, 0).day_of_year(), 366);
+    assert_eq!(DateTime::new(2016, 3, 1, 0, 0, 0).day_of_year(), 61);
+  }
+
+  #[test]
+  fn knows_leap_years() {
+    assert!(is_leap_year(2000));
+    assert!(is_leap_year(2016));
+    assert!(!is_leap_year(1900));
+    assert!(!is_leap_year(2015));
+    assert_eq!(days_in_month(2016, 2), 29);
+    assert_eq!(days_in_month(2100, 2), 28);
+  }
+
+  #[test]
+  fn validates_fields() {
+    assert!(DateTime::new(2016, 2, 29, 23, 59, 59).is_valid());
+    assert!(!DateTime::new(2015, 2, 29, 0, 0, 0).is_valid());
+    assert!(!DateTime::new(2016, 13, 1, 0, 0, 0).is_valid());
+    assert!(!DateTime::new(2016, 1, 1, 24, 0, 0).is_valid());
+  }
+}
@@ -21,8 +21,36 @@ SPIConf is a MCU-specific struct.
 As SPI performs read and write as one operation, special care should be taken if
 `write()` and `read()` methods are used with several devices on one SPI
 peripheral. The best way is to always use `transfer()`.
+
+The block methods (`write_all()`, `transfer_in_place()` and `read_into()`) move
+one byte at a time by default, drivers override them with `write_frames()`,
+`transfer_frames()` and `read_frames()` to keep the hardware FIFOs busy. Those wait
+for frames sent by previous `write()` calls to finish and discard the data they
+left in the receive path.
 */
 
+use core::ptr;
+
+/// Common transfer settings.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Settings {
+  /// SPI mode, see http://en.wikipedia.org/wiki/Serial_Peripheral_Interface_Bus#Mode_numbers for explanation.
+  pub mode: u8,
+  /// SPI bus frequency in Hz.
+  pub frequency: u32,
+}
+
+/// SPI peripheral whose transfer settings can be changed at runtime.
+///
+/// This allows devices with different requirements to share a bus.
+pub trait Configure: Spi {
+  /// MCU-specific transfer settings.
+  type Settings: Copy + PartialEq;
+
+  /// Applies `settings`, must not be called in the middle of a transfer.
+  fn configure(&self, settings: Self::Settings);
+}
+
 /// SPI trait.
 pub trait Spi {
   /// Writes a byte over SPI.
@@ -43,4 +71,241 @@ pub trait Spi {
     self.write(value);
     self.read()
   }
+
+  /// Waits until all written bytes have been shifted out.
+  fn flush(&self);
+
+  /// Writes all bytes of `data`, discarding the bytes read.
+  fn write_all(&self, data: &[u8]) {
+    for &b in data {
+      self.transfer(b);
+    }
+  }
+
+  /// Writes all bytes of `buf`, replacing each of them with the byte read in
+  /// its place.
+  fn transfer_in_place(&self, buf: &mut [u8]) {
+    for b in buf.iter_mut() {
+      *b = self.transfer(*b);
+    }
+  }
+
+  /// Fills `buf` with bytes read while writing `fill`.
+  fn read_into(&self, buf: &mut [u8], fill: u8) {
+    for b in buf.iter_mut() {
+      *b = self.transfer(fill);
+    }
+  }
+}
+
+/// Frame access to the data path of an SPI peripheral, used by the block
+/// transfer helpers.
+pub trait Frames {
+  /// Number of frames that can be sent ahead of the ones received without
+  /// the receive path overrunning.
+  fn frames_in_flight(&self) -> usize;
+
+  /// Returns true if a frame can be written.
+  fn tx_ready(&self) -> bool;
+
+  /// Returns true if a received frame is waiting.
+  fn rx_ready(&self) -> bool;
+
+  /// Returns true while frames are queued for sending or being shifted out.
+  fn busy(&self) -> bool;
+
+  /// Writes a frame, only called when `tx_ready()` is true.
+  fn write_frame(&self, value: u8);
+
+  /// Reads a received frame, only called when `rx_ready()` is true.
+  fn read_frame(&self) -> u8;
+}
+
+/// Writes all bytes of `data`, discarding the bytes read.
+pub fn write_frames<F: Frames>(spi: &F, data: &[u8]) {
+  unsafe { exchange(spi, data.len(), data.as_ptr(), ptr::null_mut(), 0) };
+}
+
+/// Writes all bytes of `buf`, replacing each of them with the byte read in
+/// its place.
+pub fn transfer_frames<F: Frames>(spi: &F, buf: &mut [u8]) {
+  let p = buf.as_mut_ptr();
+  unsafe { exchange(spi, buf.len(), p, p, 0) };
+}
+
+/// Fills `buf` with bytes read while writing `fill`.
+pub fn read_frames<F: Frames>(spi: &F, buf: &mut [u8], fill: u8) {
+  unsafe { exchange(spi, buf.len(), ptr::null(), buf.as_mut_ptr(), fill) };
+}
+
+/// Moves `len` frames, sending them from `tx` (or `fill` if it's null) and
+/// storing the received ones into `rx` (unless it's null).
+///
+/// A frame is only read back after it was written, so `tx` and `rx` can be
+/// the same buffer.
+unsafe fn exchange<F: Frames>(spi: &F, len: usize, tx: *const u8, rx: *mut u8,
+    fill: u8) {
+  // drop frames left over by write(), including the ones still on the wire
+  while spi.busy() || spi.rx_ready() {
+    if spi.rx_ready() {
+      spi.read_frame();
+    }
+  }
+
+  let in_flight = spi.frames_in_flight();
+  let mut sent = 0;
+  let mut received = 0;
+  while received < len {
+    if sent < len && sent - received < in_flight && spi.tx_ready() {
+      let value = if tx.is_null() {
+        fill
+      } else {
+        *tx.offset(sent as isize)
+      };
+      spi.write_frame(value);
+      sent += 1;
+    }
+    if spi.rx_ready() {
+      let value = spi.read_frame();
+      if !rx.is_null() {
+        *rx.offset(received as isize) = value;
+      }
+      received += 1;
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use core::cell::{Cell, RefCell};
+  use std::vec::Vec;
+
+  use super::{Frames, write_frames, transfer_frames, read_frames};
+
+  /// Peripheral that answers each frame with the frame plus one, after
+  /// `latency` polls of `rx_ready()`.
+  ///
+  /// Frames passed to `shift()` are still on the wire, each one lands in
+  /// `pending` after `latency` polls of `busy()`.
+  struct Loopback {
+    depth: usize,
+    latency: usize,
+    pending: RefCell<Vec<u8>>,
+    shifting: RefCell<Vec<u8>>,
+    busy_polls: Cell<usize>,
+    polls: Cell<usize>,
+    written: RefCell<Vec<u8>>,
+    max_in_flight: Cell<usize>,
+  }
+
+  impl Loopback {
+    fn new(depth: usize, latency: usize, leftover: &[u8]) -> Loopback {
+      Loopback {
+        depth: depth,
+        latency: latency,
+        pending: RefCell::new(leftover.to_vec()),
+        shifting: RefCell::new(Vec::new()),
+        busy_polls: Cell::new(0),
+        polls: Cell::new(0),
+        written: RefCell::new(Vec::new()),
+        max_in_flight: Cell::new(0),
+      }
+    }
+
+    fn shift(&self, frames: &[u8]) {
+      self.shifting.borrow_mut().extend_from_slice(frames);
+    }
+  }
+
+  impl Frames for Loopback {
+    fn frames_in_flight(&self) -> usize {
+      self.depth
+    }
+
+    fn tx_ready(&self) -> bool {
+      true
+    }
+
+    fn rx_ready(&self) -> bool {
+      self.polls.set(self.polls.get() + 1);
+      !self.pending.borrow().is_empty() && self.polls.get() > self.latency
+    }
+
+    fn busy(&self) -> bool {
+      if !self.shifting.borrow().is_empty() {
+        self.busy_polls.set(self.busy_polls.get() + 1);
+        if self.busy_polls.get() > self.latency {
+          self.busy_polls.set(0);
+          let frame = self.shifting.borrow_mut().remove(0);
+          self.pending.borrow_mut().push(frame);
+        }
+        return true;
+      }
+      // a pending frame is still on the wire until rx_ready() reports it
+      !self.pending.borrow().is_empty() && self.polls.get() < self.latency
+    }
+
+    fn write_frame(&self, value: u8) {
+      self.written.borrow_mut().push(value);
+      self.pending.borrow_mut().push(value.wrapping_add(1));
+      let in_flight = self.pending.borrow().len();
+      if in_flight > self.max_in_flight.get() {
+        self.max_in_flight.set(in_flight);
+      }
+    }
+
+    fn read_frame(&self) -> u8 {
+      self.polls.set(0);
+      self.pending.borrow_mut().remove(0)
+    }
+  }
+
+  #[test]
+  fn writes_and_drops_received_frames() {
+    let spi = Loopback::new(4, 2, &[]);
+    write_frames(&spi, &[1, 2, 3]);
+    assert_eq!(*spi.written.borrow(), [1, 2, 3]);
+    assert!(spi.pending.borrow().is_empty());
+  }
+
+  #[test]
+  fn transfers_in_place() {
+    let spi = Loopback::new(4, 2, &[]);
+    let mut buf = [10, 20, 30, 40, 50];
+    transfer_frames(&spi, &mut buf);
+    assert_eq!(*spi.written.borrow(), [10, 20, 30, 40, 50]);
+    assert_eq!(buf, [11, 21, 31, 41, 51]);
+  }
+
+  #[test]
+  fn reads_with_fill_after_dropping_leftovers() {
+    let spi = Loopback::new(1, 0, &[0xaa]);
+    let mut buf = [0; 3];
+    read_frames(&spi, &mut buf, 0xff);
+    assert_eq!(*spi.written.borrow(), [0xff, 0xff, 0xff]);
+    assert_eq!(buf, [0, 0, 0]);
+  }
+
+  #[test]
+  fn waits_for_frames_on_the_wire_before_dropping_leftovers() {
+    let spi = Loopback::new(2, 3, &[0xaa]);
+    spi.shift(&[0xbb, 0xcc]);
+    let mut buf = [1, 2];
+    transfer_frames(&spi, &mut buf);
+    assert_eq!(*spi.written.borrow(), [1, 2]);
+    assert_eq!(buf, [2, 3]);
+    assert!(spi.pending.borrow().is_empty());
+  }
+
+  #[test]
+  fn keeps_frames_in_flight_within_limit() {
+    let spi = Loopback::new(1, 3, &[]);
+    let mut buf = [0; 8];
+    transfer_frames(&spi, &mut buf);
+    assert_eq!(spi.max_in_flight.get(), 1);
+
+    let spi = Loopback::new(4, 3, &[]);
+    transfer_frames(&spi, &mut buf);
+    assert_eq!(spi.max_in_flight.get(), 4);
+  }
 }
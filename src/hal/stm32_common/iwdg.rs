@@ -0,0 +1,74 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Independent watchdog of ST STM32.
+//!
+//! The IWDG register block is the same on all families, only the nominal
+//! frequency of the LSI it counts from differs. Each MCU's `Watchdog` starts
+//! and feeds its IWDG through these functions.
+
+use hal::watchdog;
+
+#[path="../../util/wait_for.rs"]
+#[macro_use] mod wait_for;
+
+/// Starts the watchdog, or changes the timeout of a running one.
+///
+/// `lsi_frequency` is the nominal LSI frequency in Hz. Timeouts longer than
+/// the IWDG supports are clamped to the longest one.
+pub fn start(iwdg: &reg::IWDG, lsi_frequency: u32, timeout_ms: u32) {
+  use self::reg::IWDG_kr_key::*;
+  let (shift, reload) = watchdog::prescale(lsi_frequency, timeout_ms,
+      2, 8, 0xfff);
+
+  iwdg.kr.set_key(Start);
+  iwdg.kr.set_key(Unlock);
+  wait_for!(!iwdg.sr.pvu() && !iwdg.sr.rvu());
+  iwdg.pr.set_prescaler((shift - 2) as u32);
+  iwdg.rlr.set_reload(reload - 1);
+  wait_for!(!iwdg.sr.pvu() && !iwdg.sr.rvu());
+  iwdg.kr.set_key(Reload);
+}
+
+/// Reloads the watchdog counter.
+pub fn feed(iwdg: &reg::IWDG) {
+  iwdg.kr.set_key(reg::IWDG_kr_key::Reload);
+}
+
+#[allow(missing_docs)]
+pub mod reg {
+  use core::ops::Drop;
+  use volatile_cell::VolatileCell;
+
+  ioregs!(IWDG = {
+    0x00 => reg32 kr {     //! Key
+      0..15 => key: wo {
+        0x5555 => Unlock,
+        0xaaaa => Reload,
+        0xcccc => Start,
+      },
+    }
+    0x04 => reg32 pr {     //! Prescaler, divides by 4 << prescaler
+      0..2 => prescaler,
+    }
+    0x08 => reg32 rlr {    //! Reload
+      0..11 => reload,
+    }
+    0x0c => reg32 sr {     //! Status
+      0 => pvu: ro,
+      1 => rvu: ro,
+    }
+  });
+}
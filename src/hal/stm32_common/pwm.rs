@@ -0,0 +1,242 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! PWM on the general purpose and advanced timers of ST STM32.
+//!
+//! The timer register block is the same on all families, each MCU provides
+//! its timers through `Timer` and exports `PWM` for them.
+
+use hal::pwm;
+
+/// Timer that can generate PWM, implemented by each MCU's `PwmTimer`.
+pub trait Timer: Copy {
+  /// Registers of the timer.
+  fn reg(self) -> &'static reg::TIM;
+  /// Enables the clock of the timer.
+  fn enable_clock(self);
+  /// Largest number of counts per period.
+  fn max_reload(self) -> u32;
+  /// Returns true for advanced timers, which have complementary outputs.
+  fn is_advanced(self) -> bool;
+}
+
+/// Timer channel driving the output.
+#[allow(missing_docs)]
+#[derive(Clone, Copy)]
+pub enum PwmChannel {
+  Channel1 = 0,
+  Channel2,
+  Channel3,
+  Channel4,
+}
+
+/// Counter alignment of the PWM signal.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Alignment {
+  /// Counter counts up, pulses start at the beginning of the period.
+  Edge,
+  /// Counter counts up and down, pulses are centered in the period.
+  Center,
+}
+
+/// PWM configuration errors.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Error {
+  /// The channel has no complementary output.
+  NoComplementaryOutput,
+}
+
+#[allow(non_upper_case_globals)]
+static OutputPwmMode1: u32 = 0b0110_1000;  // OCxM = PWM mode 1, OCxPE
+#[allow(non_upper_case_globals)]
+static CaptureCompareEnable: u32 = 1 << 0;
+#[allow(non_upper_case_globals)]
+static CaptureCompareNEnable: u32 = 1 << 2;
+#[allow(non_upper_case_globals)]
+static CenterAligned1: u32 = 1;
+
+/// PWM output on a timer channel.
+#[derive(Clone, Copy)]
+pub struct PWM<T: Timer> {
+  timer: T,
+  channel: PwmChannel,
+  alignment: Alignment,
+  timer_clock: u32,
+  prescaler: u32,
+  period_us: u32,
+  pulsewidth_us: u32,
+}
+
+impl<T: Timer> PWM<T> {
+  /// Creates a new PWM output and starts its timer.
+  ///
+  /// `timer_clock` is the timer input frequency in Hz, which is twice the APB
+  /// clock when the APB prescaler is not 1. The output starts with a zero
+  /// pulse width.
+  pub fn new(timer: T, channel: PwmChannel, alignment: Alignment,
+      timer_clock: u32, period_us: u32) -> PWM<T> {
+    timer.enable_clock();
+
+    let reg = timer.reg();
+    let index = channel as usize;
+
+    reg.cr1
+      .set_counter_enable(false)
+      .set_auto_reload_preload(true)
+      .set_center_mode(match alignment {
+        Alignment::Edge   => 0,
+        Alignment::Center => CenterAligned1,
+      });
+    reg.ccmr[index / 2].set_oc(index % 2, OutputPwmMode1);
+    reg.ccer.set_cc(index, reg.ccer.cc(index) | CaptureCompareEnable);
+    if timer.is_advanced() {
+      reg.bdtr.set_main_output_enable(true);
+    }
+
+    let mut pwm = PWM {
+      timer: timer,
+      channel: channel,
+      alignment: alignment,
+      timer_clock: timer_clock,
+      prescaler: 1,
+      period_us: period_us,
+      pulsewidth_us: 0,
+    };
+
+    pwm.update_period();
+    pwm.update_pulsewidth();
+    reg.cr1.set_counter_enable(true);
+    pwm
+  }
+
+  /// Enables the complementary output of this channel.
+  ///
+  /// The complementary output goes active `dead_time_ns` nanoseconds after
+  /// the main output goes inactive and vice versa. Only channels 1 to 3 of
+  /// advanced timers have complementary outputs, and the dead time is shared
+  /// by all channels of the timer.
+  pub fn enable_complementary(&self, dead_time_ns: u32) -> Result<(), Error> {
+    if !self.timer.is_advanced() ||
+        self.channel as u8 > PwmChannel::Channel3 as u8 {
+      return Err(Error::NoComplementaryOutput);
+    }
+
+    let reg = self.timer.reg();
+    let index = self.channel as usize;
+    let ticks = self.timer_clock as u64 * dead_time_ns as u64 / 1_000_000_000;
+
+    reg.bdtr.set_dead_time(pwm::dead_time_generator(ticks as u32) as u32);
+    reg.ccer.set_cc(index, reg.ccer.cc(index) | CaptureCompareNEnable);
+    Ok(())
+  }
+
+  /// Disables the complementary output of this channel.
+  pub fn disable_complementary(&self) {
+    let reg = self.timer.reg();
+    let index = self.channel as usize;
+
+    reg.ccer.set_cc(index, reg.ccer.cc(index) & !CaptureCompareNEnable);
+  }
+
+  /// Counter frequency as seen by the period, halved when counting up and
+  /// down.
+  fn counting_clock(&self) -> u32 {
+    match self.alignment {
+      Alignment::Edge   => self.timer_clock,
+      Alignment::Center => self.timer_clock / 2,
+    }
+  }
+
+  fn update_period(&mut self) {
+    let reg = self.timer.reg();
+    let (prescaler, reload) = pwm::period_to_counts(self.counting_clock(),
+        self.period_us, self.timer.max_reload());
+
+    self.prescaler = prescaler;
+    reg.psc.set_prescaler(prescaler - 1);
+    reg.arr.set_reload(match self.alignment {
+      Alignment::Edge   => reload - 1,
+      Alignment::Center => reload,
+    });
+    // load the preloaded prescaler and reload right away
+    reg.egr.set_update_generation(true);
+  }
+
+  fn update_pulsewidth(&self) {
+    let reg = self.timer.reg();
+    let counts = pwm::us_to_counts(self.counting_clock(), self.prescaler,
+        self.pulsewidth_us);
+
+    reg.ccr[self.channel as usize].set_value(counts);
+  }
+}
+
+impl<T: Timer> pwm::PWMOutput for PWM<T> {
+  fn set_period_us(&mut self, period_us: u32) {
+    self.period_us = period_us;
+    self.update_period();
+    self.update_pulsewidth();
+  }
+
+  fn get_period_us(&self) -> u32 {
+    self.period_us
+  }
+
+  fn set_pulsewidth_us(&mut self, pulsewidth_us: u32) {
+    self.pulsewidth_us = pulsewidth_us;
+    self.update_pulsewidth();
+  }
+
+  fn get_pulsewidth_us(&self) -> u32 {
+    self.pulsewidth_us
+  }
+}
+
+/// Timer registers, the statics are defined by each MCU.
+#[allow(missing_docs)]
+pub mod reg {
+  use core::ops::Drop;
+  use volatile_cell::VolatileCell;
+
+  ioregs!(TIM = {
+    0x00 => reg32 cr1 {    //! Control 1
+      0    => counter_enable,
+      5..6 => center_mode,
+      7    => auto_reload_preload,
+    }
+    0x14 => reg32 egr {    //! Event generation
+      0 => update_generation: wo,
+    }
+    0x18 => reg32 ccmr[2] {  //! Capture/compare mode, two channels each
+      0..15 => oc[2],
+    }
+    0x20 => reg32 ccer {   //! Capture/compare enable, CCxE CCxP CCxNE CCxNP
+      0..15 => cc[4],
+    }
+    0x28 => reg32 psc {    //! Prescaler
+      0..15 => prescaler,
+    }
+    0x2c => reg32 arr {    //! Auto-reload, 16 bits on most timers
+      0..31 => reload,
+    }
+    0x34 => reg32 ccr[4] {   //! Capture/compare value
+      0..31 => value,
+    }
+    0x44 => reg32 bdtr {   //! Break and dead-time, advanced timers only
+      0..7 => dead_time,
+      15   => main_output_enable,
+    }
+  });
+}
@@ -0,0 +1,103 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! PWM support for ST STM32F1.
+//!
+//! Each of the four channels of TIM1 to TIM5 and TIM8 can drive a PWM output.
+//! Channels of the same timer share its period. Output pins have to be
+//! configured separately as alternate function outputs, e.g.
+//! `PinConf::OutPushPullAlt50MHz`.
+//!
+//! TIM1 and TIM8 are advanced timers, their channels 1 to 3 also drive
+//! complementary outputs with dead-time insertion for half bridges.
+
+use hal::stm32_common::pwm;
+use hal::stm32f1::peripheral_clock as pc;
+
+pub use hal::stm32_common::pwm::{PwmChannel, Alignment, Error};
+
+use self::PwmTimer::*;
+
+/// PWM output on a timer channel, created with `PWM::new()`.
+pub type PWM = pwm::PWM<PwmTimer>;
+
+/// Timers that can generate PWM.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum PwmTimer {
+  Timer1,
+  Timer2,
+  Timer3,
+  Timer4,
+  Timer5,
+  Timer8,
+}
+
+impl PwmTimer {
+  /// Returns true for TIM1 and TIM8, which have complementary outputs.
+  pub fn is_advanced(self) -> bool {
+    match self {
+      Timer1 | Timer8 => true,
+      _ => false,
+    }
+  }
+}
+
+impl pwm::Timer for PwmTimer {
+  fn reg(self) -> &'static pwm::reg::TIM {
+    match self {
+      Timer1 => &reg::TIM1,
+      Timer2 => &reg::TIM2,
+      Timer3 => &reg::TIM3,
+      Timer4 => &reg::TIM4,
+      Timer5 => &reg::TIM5,
+      Timer8 => &reg::TIM8,
+    }
+  }
+
+  fn enable_clock(self) {
+    let clock = match self {
+      Timer1 => pc::PeripheralClock::Apb2(pc::BusApb2::Tim1),
+      Timer2 => pc::PeripheralClock::Apb1(pc::BusApb1::Tim2),
+      Timer3 => pc::PeripheralClock::Apb1(pc::BusApb1::Tim3),
+      Timer4 => pc::PeripheralClock::Apb1(pc::BusApb1::Tim4),
+      Timer5 => pc::PeripheralClock::Apb1(pc::BusApb1::Tim5),
+      Timer8 => pc::PeripheralClock::Apb2(pc::BusApb2::Tim8),
+    };
+    clock.enable();
+  }
+
+  fn max_reload(self) -> u32 {
+    // all STM32F1 timers are 16 bit
+    0xffff
+  }
+
+  fn is_advanced(self) -> bool {
+    PwmTimer::is_advanced(self)
+  }
+}
+
+mod reg {
+  use hal::stm32_common::pwm::reg::TIM;
+
+  extern {
+    #[link_name="stm32f1_iomem_TIM1"] pub static TIM1: TIM;
+    #[link_name="stm32f1_iomem_TIM2"] pub static TIM2: TIM;
+    #[link_name="stm32f1_iomem_TIM3"] pub static TIM3: TIM;
+    #[link_name="stm32f1_iomem_TIM4"] pub static TIM4: TIM;
+    #[link_name="stm32f1_iomem_TIM5"] pub static TIM5: TIM;
+    #[link_name="stm32f1_iomem_TIM8"] pub static TIM8: TIM;
+  }
+}
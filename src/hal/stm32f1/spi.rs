@@ -15,6 +15,7 @@
 
 //! Serial Peripheral Interface for STM32F1.
 
+use core::intrinsics::abort;
 use core::result::Result;
 use core::result::Result::{Ok, Err};
 use core::marker::Copy;
@@ -76,7 +77,7 @@ pub enum DataFormat {
 
 #[allow(missing_docs)]
 #[repr(u8)]
-#[derive(Clone, Copy)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum ClockPhase {
   Edge1 = 0,
   Edge2 = 1,
@@ -84,12 +85,25 @@ pub enum ClockPhase {
 
 #[allow(missing_docs)]
 #[repr(u8)]
-#[derive(Clone, Copy)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum ClockPolarity {
   Low = 0,
   High = 1,
 }
 
+/// Transfer settings applied through `Configure`.
+#[derive(PartialEq, Clone, Copy)]
+pub struct Settings {
+  /// Clock phase.
+  pub phase: ClockPhase,
+  /// Clock polarity.
+  pub polarity: ClockPolarity,
+  /// Baud rate prescaler, the bus runs at the APB clock divided by
+  /// `1 << prescaler_shift`. Valid values are 1 to 8, `configure()` aborts
+  /// on others.
+  pub prescaler_shift: u8,
+}
+
 /// SPI initialization errors.
 #[repr(u8)]
 #[derive(Clone, Copy)]
@@ -193,6 +207,55 @@ impl Spi {
   }
 }
 
+impl ::hal::spi::Frames for Spi {
+  /// The data register holds a single received frame, a second frame in
+  /// flight would overrun it if the CPU is held up.
+  fn frames_in_flight(&self) -> usize {
+    1
+  }
+
+  fn tx_ready(&self) -> bool {
+    self.reg.sr.transmit_buffer_empty()
+  }
+
+  fn rx_ready(&self) -> bool {
+    self.reg.sr.receive_buffer_not_empty()
+  }
+
+  /// BSY alone may read clear between two frames, so a frame still waiting
+  /// in the transmit buffer counts as well.
+  fn busy(&self) -> bool {
+    !self.reg.sr.transmit_buffer_empty() || self.reg.sr.busy_flag()
+  }
+
+  fn write_frame(&self, value: u8) {
+    self.reg.dr.set_data(value as u16);
+  }
+
+  fn read_frame(&self) -> u8 {
+    self.reg.dr.data() as u8
+  }
+}
+
+impl ::hal::spi::Configure for Spi {
+  type Settings = Settings;
+
+  /// Aborts if `settings.prescaler_shift` is not between 1 and 8.
+  fn configure(&self, settings: Settings) {
+    if settings.prescaler_shift<1 || settings.prescaler_shift>8 {
+      unsafe { abort() };
+    }
+
+    wait_for!(!self.reg.sr.busy_flag());
+
+    self.reg.cr1.set_spi_enable(false);
+    self.reg.cr1.set_baud_rate(settings.prescaler_shift as u16 - 1);
+    self.reg.cr1.set_clock_phase(settings.phase as usize != 0);
+    self.reg.cr1.set_clock_polarity(settings.polarity as usize != 0);
+    self.reg.cr1.set_spi_enable(true);
+  }
+}
+
 impl ::hal::spi::Spi for Spi {
   fn write(&self, value: u8) {
     wait_for!(self.reg.sr.transmit_buffer_empty());
@@ -203,6 +266,23 @@ impl ::hal::spi::Spi for Spi {
     wait_for!(self.reg.sr.receive_buffer_not_empty());
     self.reg.dr.data() as u8
   }
+
+  fn flush(&self) {
+    wait_for!(self.reg.sr.transmit_buffer_empty());
+    wait_for!(!self.reg.sr.busy_flag());
+  }
+
+  fn write_all(&self, data: &[u8]) {
+    ::hal::spi::write_frames(self, data);
+  }
+
+  fn transfer_in_place(&self, buf: &mut [u8]) {
+    ::hal::spi::transfer_frames(self, buf);
+  }
+
+  fn read_into(&self, buf: &mut [u8], fill: u8) {
+    ::hal::spi::read_frames(self, buf, fill);
+  }
 }
 
 mod reg {
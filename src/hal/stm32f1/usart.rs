@@ -21,8 +21,9 @@ Universal synchronous asynchronous receiver transmitter (USART).
 use core::fmt;
 use core::result::Result;
 use core::intrinsics::abort;
+use core::option::Option::{self, Some, None};
 
-use drivers::chario::CharIO;
+use drivers::chario::{CharIO, CharInput};
 use hal::uart;
 use hal::stm32f1::init;
 
@@ -129,6 +130,16 @@ impl CharIO for Usart {
   }
 }
 
+impl CharInput for Usart {
+  fn try_getc(&self) -> Option<char> {
+    if self.reg.sr.read_data_not_empty() {
+      Some(self.reg.dr.data() as u8 as char)
+    } else {
+      None
+    }
+  }
+}
+
 impl fmt::Write for Usart {
   fn write_str(&mut self, s: &str) -> fmt::Result {
     use core::str::StrExt;
@@ -165,8 +176,8 @@ mod reg {
     },
     0x0C => reg16 cr1 { // control 1
       0 => send_break : rw,
-      1 => receiver_enable : rw,
-      2 => receiver_wakeup : rw,
+      1 => receiver_wakeup : rw,
+      2 => receiver_enable : rw,
       3 => transmitter_enable : rw,
       4 => int_idle_enable : rw,
       5 => int_read_data_not_empty_enable : rw,
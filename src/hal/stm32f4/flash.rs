@@ -0,0 +1,235 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Flash programming for ST STM32F4.
+//!
+//! Flash reads stall while the controller erases or programs, so the code
+//! that starts an operation and waits for it runs from RAM with interrupts
+//! disabled. It is placed in `.data`, so the application must have called
+//! `hal::mem_init::init_data()` before erasing or programming, as with any
+//! other initialized data.
+//!
+//! Main flash has four 16 kB sectors, one 64 kB sector and up to seven 128 kB
+//! sectors. 2 MB parts repeat this layout in a second bank.
+
+use core::intrinsics::{volatile_load, volatile_store};
+
+use hal::cortex_m4::irq::NoInterrupts;
+use hal::flash;
+use hal::stm32f4::init::reg::FLASH;
+
+const FLASH_BASE: usize = 0x0800_0000;
+const BANK_SIZE: usize = 1024 * 1024;
+const SECTORS_PER_BANK: usize = 12;
+
+/// Flash SR and CR, for code running from RAM.
+const FLASH_SR: usize = 0x4002_3c0c;
+const FLASH_CR: usize = 0x4002_3c10;
+
+const KEY1: u32 = 0x4567_0123;
+const KEY2: u32 = 0xcdef_89ab;
+
+/// SR busy flag, a constant so that RAM code does not load it from flash.
+const STATUS_BUSY: u32 = 1 << 16;
+
+#[allow(non_upper_case_globals)]
+static StatusWriteProtectionError: u32 = 1 << 4;
+#[allow(non_upper_case_globals)]
+static StatusErrors: u32 = 0b1_1111_0010;  // RDERR, PGSERR, PGPERR, PGAERR,
+                                           // WRPERR, OPERR
+
+#[allow(non_upper_case_globals)]
+static ControlProgram: u32 = 1 << 0;
+#[allow(non_upper_case_globals)]
+static ControlSectorErase: u32 = 1 << 1;
+#[allow(non_upper_case_globals)]
+static ControlSectorShift: u32 = 3;
+#[allow(non_upper_case_globals)]
+static ControlSizeShift: u32 = 8;
+#[allow(non_upper_case_globals)]
+static ControlStart: u32 = 1 << 16;
+#[allow(non_upper_case_globals)]
+static ControlLock: u32 = 1 << 31;
+
+#[allow(non_upper_case_globals)]
+static AccessDataCacheEnable: u32 = 1 << 10;
+#[allow(non_upper_case_globals)]
+static AccessDataCacheReset: u32 = 1 << 12;
+
+/// Number of bytes programmed at once, limited by the supply voltage.
+#[derive(Clone, Copy)]
+pub enum Parallelism {
+  /// Byte access, 1.8 V and up.
+  X8 = 0,
+  /// Half-word access, 2.1 V and up.
+  X16 = 1,
+  /// Word access, 2.7 V and up.
+  X32 = 2,
+}
+
+/// On-chip flash.
+#[derive(Clone, Copy)]
+pub struct Flash {
+  sectors: usize,
+  parallelism: Parallelism,
+}
+
+impl Flash {
+  /// Returns the flash of a part with `size` bytes of it.
+  ///
+  /// Erasing and programming run code from RAM, so `mem_init::init_data()`
+  /// must have been called first.
+  pub fn new(size: usize, parallelism: Parallelism) -> Flash {
+    let bank = if size > BANK_SIZE { BANK_SIZE } else { size };
+    let sectors = match bank / 1024 {
+      0...64 => bank / (16 * 1024),
+      kb     => 5 + (kb - 128) / 128,
+    };
+    Flash {
+      sectors: if size > BANK_SIZE { 2 * sectors } else { sectors },
+      parallelism: parallelism,
+    }
+  }
+
+  /// Runs an operation on the unlocked controller.
+  fn operation<F: FnOnce()>(&self, op: F) -> Result<(), flash::Error> {
+    wait_ready();
+    FLASH.set_SR(StatusErrors);
+    if FLASH.CR() & ControlLock != 0 {
+      FLASH.set_KEYR(KEY1);
+      FLASH.set_KEYR(KEY2);
+    }
+
+    op();
+
+    FLASH.set_CR(ControlLock);
+    let status = FLASH.SR();
+    if status & StatusWriteProtectionError != 0 {
+      Err(flash::Error::WriteProtected)
+    } else if status & StatusErrors != 0 {
+      Err(flash::Error::OperationFailed)
+    } else {
+      Ok(())
+    }
+  }
+}
+
+impl flash::Flash for Flash {
+  fn sector_count(&self) -> usize {
+    self.sectors
+  }
+
+  fn sector_start(&self, sector: usize) -> usize {
+    let bank = sector / SECTORS_PER_BANK;
+    let offset = match sector % SECTORS_PER_BANK {
+      s @ 0...4 => s * 16 * 1024,
+      s         => (s - 4) * 128 * 1024,
+    };
+    FLASH_BASE + bank * BANK_SIZE + offset
+  }
+
+  fn sector_size(&self, sector: usize) -> usize {
+    match sector % SECTORS_PER_BANK {
+      0...3 => 16 * 1024,
+      4     => 64 * 1024,
+      _     => 128 * 1024,
+    }
+  }
+
+  fn program_size(&self) -> usize {
+    1 << self.parallelism as usize
+  }
+
+  fn erase(&self, sector: usize) -> Result<(), flash::Error> {
+    if sector >= self.sectors {
+      return Err(flash::Error::InvalidAddress);
+    }
+    // sectors of the second bank are numbered from 16
+    let number = (sector / SECTORS_PER_BANK) * 16 + sector % SECTORS_PER_BANK;
+    let control = ControlSectorErase |
+        (number as u32) << ControlSectorShift |
+        (self.parallelism as u32) << ControlSizeShift;
+
+    try!(self.operation(|| {
+      FLASH.set_CR(control);
+      unsafe { start(control | ControlStart) };
+    }));
+
+    // the data cache may hold contents of the erased sector
+    let access = FLASH.ACR();
+    FLASH.set_ACR(access & !AccessDataCacheEnable);
+    FLASH.set_ACR((access & !AccessDataCacheEnable) | AccessDataCacheReset);
+    FLASH.set_ACR(access);
+    Ok(())
+  }
+
+  fn program(&self, address: usize, data: &[u8]) -> Result<(), flash::Error> {
+    try!(flash::check_range(self, address, data.len()));
+    let size = self.program_size();
+    if data.len() % size != 0 {
+      return Err(flash::Error::InvalidAddress);
+    }
+
+    let control = ControlProgram | (self.parallelism as u32) << ControlSizeShift;
+
+    try!(self.operation(|| {
+      FLASH.set_CR(control);
+      for (i, chunk) in data.chunks(size).enumerate() {
+        let mut value = 0u32;
+        for (n, byte) in chunk.iter().enumerate() {
+          value |= (*byte as u32) << (8 * n);
+        }
+        unsafe { write(address + i * size, value, size) };
+        if FLASH.SR() & StatusErrors != 0 {
+          break;
+        }
+      }
+    }));
+
+    flash::Flash::verify(self, address, data)
+  }
+}
+
+fn wait_ready() {
+  while FLASH.SR() & STATUS_BUSY != 0 {}
+}
+
+/// Starts an erase and waits for it to finish, from RAM.
+///
+/// Unsafe because it is only in RAM once `mem_init::init_data()` copied
+/// `.data` there, calling it before that runs it from the flash it stalls.
+#[link_section=".data.ramfunc"]
+#[inline(never)]
+unsafe fn start(control: u32) {
+  let _crit = NoInterrupts::new();
+  volatile_store(FLASH_CR as *mut u32, control);
+  while volatile_load(FLASH_SR as *const u32) & STATUS_BUSY != 0 {}
+}
+
+/// Writes `size` bytes of `value` to flash and waits for it, from RAM.
+///
+/// Unsafe because it is only in RAM once `mem_init::init_data()` copied
+/// `.data` there, calling it before that runs it from the flash it stalls.
+#[link_section=".data.ramfunc"]
+#[inline(never)]
+unsafe fn write(address: usize, value: u32, size: usize) {
+  let _crit = NoInterrupts::new();
+  match size {
+    1 => volatile_store(address as *mut u8, value as u8),
+    2 => volatile_store(address as *mut u16, value as u16),
+    _ => volatile_store(address as *mut u32, value),
+  }
+  while volatile_load(FLASH_SR as *const u32) & STATUS_BUSY != 0 {}
+}
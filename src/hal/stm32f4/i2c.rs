@@ -1,9 +1,8 @@
 //! I2C Module
 //! Wannes
 
-use hal::pin::Gpio;
-use core::intrinsics::abort;
-use super::{pin, timer, peripheral_clock};
+use hal::i2c;
+use super::{pin, peripheral_clock};
 
 #[path = "../../util/ioreg.rs"]
 #[macro_use]
@@ -13,6 +12,9 @@ mod ioreg;
 #[macro_use]
 mod wait_for;
 
+/// Number of status polls before a transaction is aborted with a timeout.
+const TIMEOUT_POLLS: u32 = 100_000;
+
 #[allow(missing_docs)]
 #[derive(Clone, Copy)]
 pub enum I2CBus {
@@ -33,6 +35,61 @@ impl I2CBus {
     }
 }
 
+/// Status flag a master transaction waits on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Event {
+    Start,
+    Address,
+    TxEmpty,
+    ByteFinished,
+    RxNotEmpty,
+}
+
+/// Bus operations the master transactions are built from.
+trait Master {
+    /// Generates a (repeated) START and sends the address byte.
+    fn start(&self, addr: u8, read: bool) -> Result<(), i2c::Error>;
+
+    /// Clears ADDR, which releases the clock after the address phase.
+    fn clear_address(&self);
+
+    /// Waits for `event`, aborting the transaction on a bus error.
+    ///
+    /// A NACK from the slave is reported as `nack`.
+    fn wait(&self, event: Event, nack: i2c::Error) -> Result<(), i2c::Error>;
+
+    /// Writes a byte to the data register.
+    fn write_data(&self, value: u8);
+
+    /// Generates a STOP.
+    fn stop(&self);
+}
+
+/// Sends the address and `data`, leaving the bus to the caller.
+fn transmit<M: Master>(master: &M, addr: u8, data: &[u8])
+        -> Result<(), i2c::Error> {
+    try!(master.start(addr, false));
+    master.clear_address();
+    if data.is_empty() {
+        // BTF is never set without a data byte
+        return Ok(());
+    }
+
+    for &b in data {
+        try!(master.wait(Event::TxEmpty, i2c::Error::DataNack));
+        master.write_data(b);
+    }
+    master.wait(Event::ByteFinished, i2c::Error::DataNack)
+}
+
+/// Writes `data` to the device at `addr` and releases the bus.
+fn send<M: Master>(master: &M, addr: u8, data: &[u8])
+        -> Result<(), i2c::Error> {
+    try!(transmit(master, addr, data));
+    master.stop();
+    Ok(())
+}
+
 /// Bus configuration
 #[derive(Clone, Copy)]
 pub struct I2C {
@@ -85,6 +142,8 @@ impl I2C {
             // Rise time (see doc, FREQ + 1)
             self.reg.trise.set_trise(freqrange + 1);
 
+            // Standard mode
+            self.reg.ccr.set_master_mode(false);
             // As our base clock is 16MHz, divide it by 80 (0x50) to get to 200KHz
             self.reg.ccr.set_ccr(res);
         } else {
@@ -103,16 +162,13 @@ impl I2C {
         self.reg.cr1.set_enable_ack(true);
     }
 
-    /// Read from bus
-    pub fn read(&self) -> u8 {
-        let mut val: u8 = 0;
-
+    /// Read a byte sent to our own address, returns 0 on STOP.
+    pub fn slave_read(&self) -> u8 {
         loop {
             let sr1 = self.reg.sr1.get();
 
             if sr1.byte_transfer_finished() || sr1.data_register_not_empty() {
-                val = self.reg.dr.data() as u8;
-                return val;
+                return self.reg.dr.data() as u8;
             }
 
             if sr1.address() {
@@ -132,37 +188,229 @@ impl I2C {
                 self.reg.sr1.set_bus_error(false);
             }
         }
+    }
+
+    fn receive(&self, addr: u8, buf: &mut [u8]) -> Result<(), i2c::Error> {
+        let result = self.receive_bytes(addr, buf);
+        // Leave ACK enabled for the next transfer and for slave mode
+        self.reg.cr1.set_ack_position(false).set_enable_ack(true);
+        result
+    }
+
+    /// Master receiver sequence from the reference manual.
+    ///
+    /// The last two bytes are read only once BTF stalls the bus, so that the
+    /// NACK and STOP are set before the slave sends any further byte.
+    fn receive_bytes(&self, addr: u8, buf: &mut [u8])
+            -> Result<(), i2c::Error> {
+        let len = buf.len();
+
+        try!(self.start(addr, true));
+        match len {
+            1 => {
+                // NACK the only byte and stop right after it
+                self.reg.cr1.set_enable_ack(false);
+                self.clear_address();
+                self.stop();
+                try!(self.wait(Event::RxNotEmpty, i2c::Error::DataNack));
+                buf[0] = self.reg.dr.data() as u8;
+            }
+            2 => {
+                // NACK the byte after the one in the shift register
+                self.reg.cr1.set_ack_position(true).set_enable_ack(false);
+                self.clear_address();
+                try!(self.wait(Event::ByteFinished, i2c::Error::DataNack));
+                self.stop();
+                buf[0] = self.reg.dr.data() as u8;
+                buf[1] = self.reg.dr.data() as u8;
+            }
+            _ => {
+                self.reg.cr1.set_enable_ack(true);
+                self.clear_address();
+                for i in 0..len - 3 {
+                    try!(self.wait(Event::RxNotEmpty, i2c::Error::DataNack));
+                    buf[i] = self.reg.dr.data() as u8;
+                }
+
+                // Byte N-2 is in DR and N-1 in the shift register
+                try!(self.wait(Event::ByteFinished, i2c::Error::DataNack));
+                self.reg.cr1.set_enable_ack(false);
+                buf[len - 3] = self.reg.dr.data() as u8;
+
+                // Byte N-1 is in DR and N, NACKed, in the shift register
+                try!(self.wait(Event::ByteFinished, i2c::Error::DataNack));
+                self.stop();
+                buf[len - 2] = self.reg.dr.data() as u8;
+
+                try!(self.wait(Event::RxNotEmpty, i2c::Error::DataNack));
+                buf[len - 1] = self.reg.dr.data() as u8;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Master for I2C {
+    fn wait(&self, event: Event, nack: i2c::Error) -> Result<(), i2c::Error> {
+        for _ in 0..TIMEOUT_POLLS {
+            let sr1 = self.reg.sr1.get();
+
+            if sr1.arbitration_lost() {
+                // Hardware has already fallen back to slave mode
+                self.reg.sr1.set_arbitration_lost(false);
+                return Err(i2c::Error::ArbitrationLost);
+            }
+            if sr1.bus_error() {
+                self.reg.sr1.set_bus_error(false);
+                self.stop();
+                return Err(i2c::Error::BusError);
+            }
+            if sr1.acknowledge_failure() {
+                self.reg.sr1.set_acknowledge_failure(false);
+                self.stop();
+                return Err(nack);
+            }
+
+            let done = match event {
+                Event::Start => sr1.start_bit(),
+                Event::Address => sr1.address(),
+                Event::TxEmpty => sr1.data_register_empty(),
+                Event::ByteFinished => sr1.byte_transfer_finished(),
+                Event::RxNotEmpty => sr1.data_register_not_empty(),
+            };
+            if done {
+                return Ok(());
+            }
+        }
 
-        0
+        self.stop();
+        Err(i2c::Error::Timeout)
     }
 
-    /// Start mastering the bus
-    pub fn start(&self) {
-        // Standard mode
-        self.reg.ccr.set_master_mode(false);
-        // Set START bit
+    fn start(&self, addr: u8, read: bool) -> Result<(), i2c::Error> {
         self.reg.cr1.set_start(true);
-        // Wait for START condition generation
-        wait_for!(self.reg.sr1.start_bit());
-        wait_for!(self.reg.sr2.master_slave_mode());
-        // We have become master!
+        try!(self.wait(Event::Start, i2c::Error::BusError));
+
+        self.reg.dr.set_data(((addr << 1) | read as u8) as u32);
+        self.wait(Event::Address, i2c::Error::AddressNack)
     }
 
-    /// Writes something on the I2C bus
-    pub fn write(&self, addr: u8, value: u8) {
-        let write_addr = addr << 1;
-        self.reg.dr.set_data(write_addr as u32);
-        wait_for!(self.reg.sr1.address());
-        wait_for!(self.reg.sr1.data_register_empty());
+    fn clear_address(&self) {
+        self.reg.sr1.get();
+        self.reg.sr2.get();
+    }
 
+    fn write_data(&self, value: u8) {
         self.reg.dr.set_data(value as u32);
-        wait_for!(self.reg.sr1.data_register_empty());
+    }
 
-        // Set STOP bit
+    fn stop(&self) {
         self.reg.cr1.set_stop(true);
     }
 }
 
+impl i2c::I2c for I2C {
+    fn write(&self, addr: u8, data: &[u8]) -> Result<(), i2c::Error> {
+        send(self, addr, data)
+    }
+
+    fn read(&self, addr: u8, buf: &mut [u8]) -> Result<(), i2c::Error> {
+        if buf.len() == 0 {
+            return Ok(());
+        }
+        self.receive(addr, buf)
+    }
+
+    fn write_read(&self, addr: u8, data: &[u8], buf: &mut [u8])
+            -> Result<(), i2c::Error> {
+        if buf.len() == 0 {
+            return self.write(addr, data);
+        }
+        try!(transmit(self, addr, data));
+        self.receive(addr, buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::cell::RefCell;
+    use std::vec::Vec;
+
+    use hal::i2c;
+    use super::{Event, Master, send, transmit};
+
+    #[derive(PartialEq, Debug)]
+    enum Op {
+        Start(u8, bool),
+        ClearAddress,
+        Wait(Event),
+        Data(u8),
+        Stop,
+    }
+
+    /// Bus that completes every operation, logging it.
+    struct MockMaster {
+        ops: RefCell<Vec<Op>>,
+    }
+
+    impl MockMaster {
+        fn new() -> MockMaster {
+            MockMaster { ops: RefCell::new(Vec::new()) }
+        }
+    }
+
+    impl Master for MockMaster {
+        fn start(&self, addr: u8, read: bool) -> Result<(), i2c::Error> {
+            self.ops.borrow_mut().push(Op::Start(addr, read));
+            Ok(())
+        }
+
+        fn clear_address(&self) {
+            self.ops.borrow_mut().push(Op::ClearAddress);
+        }
+
+        fn wait(&self, event: Event, _: i2c::Error)
+                -> Result<(), i2c::Error> {
+            self.ops.borrow_mut().push(Op::Wait(event));
+            Ok(())
+        }
+
+        fn write_data(&self, value: u8) {
+            self.ops.borrow_mut().push(Op::Data(value));
+        }
+
+        fn stop(&self) {
+            self.ops.borrow_mut().push(Op::Stop);
+        }
+    }
+
+    #[test]
+    fn waits_for_last_byte_before_stop() {
+        let master = MockMaster::new();
+        assert_eq!(send(&master, 0x50, &[1, 2]), Ok(()));
+        assert_eq!(*master.ops.borrow(), [Op::Start(0x50, false),
+            Op::ClearAddress, Op::Wait(Event::TxEmpty), Op::Data(1),
+            Op::Wait(Event::TxEmpty), Op::Data(2),
+            Op::Wait(Event::ByteFinished), Op::Stop]);
+    }
+
+    #[test]
+    fn stops_empty_write_after_address() {
+        let master = MockMaster::new();
+        assert_eq!(send(&master, 0x50, &[]), Ok(()));
+        assert_eq!(*master.ops.borrow(), [Op::Start(0x50, false),
+            Op::ClearAddress, Op::Stop]);
+    }
+
+    #[test]
+    fn leaves_bus_to_caller_after_transmit() {
+        let master = MockMaster::new();
+        assert_eq!(transmit(&master, 0x50, &[]), Ok(()));
+        assert_eq!(*master.ops.borrow(), [Op::Start(0x50, false),
+            Op::ClearAddress]);
+    }
+}
+
 #[allow(dead_code)]
 mod reg {
     use core::ops::Drop;
@@ -180,7 +428,7 @@ mod reg {
             8 => start,
             9 => stop,
             10 => enable_ack,
-            11 => ack,
+            11 => ack_position,
             12 => packet_error_checking,
             13 => smbus_alert,
             15 => swreset,
@@ -101,6 +101,14 @@ pub fn apb_low_clock() -> u32 {
   unsafe { APBLowClock }
 }
 
+static mut APBHighClock: u32 = 0;
+
+/// Returns APB2 clock frequency according to configuration.
+#[inline(always)]
+pub fn apb_high_clock() -> u32 {
+  unsafe { APBHighClock }
+}
+
 impl SysConf {
   /// Performs the MCU initialization.
   pub fn setup(&self) {
@@ -120,6 +128,7 @@ impl ClockConf {
         unsafe {
           SystemClock = 16_000_000;
           APBLowClock = 16_000_000;  // no divisor
+          APBHighClock = 16_000_000;
         };
       },
       SystemClockHSE(freq) => {
@@ -133,6 +142,7 @@ impl ClockConf {
           unsafe {
             SystemClock = freq;
             APBLowClock = freq;  // no divisor
+            APBHighClock = freq;
           };
         }
       },
@@ -153,7 +163,8 @@ impl ClockConf {
 
         // TODO(farcaller): this should be configureable via ClockConf
         let apb_low_divisor = 4;
-        self.set_clock_divisors(1, apb_low_divisor, 2);
+        let apb_high_divisor = 2;
+        self.set_clock_divisors(1, apb_low_divisor, apb_high_divisor);
         pll_conf.setup();
         // TODO(farcaller): this doesn't really belong here.
         self.setup_flash(sysfreq);
@@ -161,6 +172,7 @@ impl ClockConf {
         unsafe {
           SystemClock = sysfreq;
           APBLowClock = sysfreq / apb_low_divisor as u32;
+          APBHighClock = sysfreq / apb_high_divisor as u32;
         };
       },
     }
@@ -0,0 +1,195 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ISR Data for stm32f4
+
+use core::option::Option::{self, Some};
+
+extern {
+  fn isr_wwdg();
+  fn isr_pvd();
+  fn isr_tamp_stamp();
+  fn isr_rtc_wkup();
+  fn isr_flash();
+  fn isr_rcc();
+  fn isr_exti_0();
+  fn isr_exti_1();
+  fn isr_exti_2();
+  fn isr_exti_3();
+  fn isr_exti_4();
+  fn isr_dma1_stream_0();
+  fn isr_dma1_stream_1();
+  fn isr_dma1_stream_2();
+  fn isr_dma1_stream_3();
+  fn isr_dma1_stream_4();
+  fn isr_dma1_stream_5();
+  fn isr_dma1_stream_6();
+  fn isr_adc();
+  fn isr_can1_tx();
+  fn isr_can1_rx0();
+  fn isr_can1_rx1();
+  fn isr_can1_sce();
+  fn isr_exti_9_5();
+  fn isr_tim1_brk_tim9();
+  fn isr_tim1_up_tim10();
+  fn isr_tim1_trg_com_tim11();
+  fn isr_tim1_cc();
+  fn isr_tim2();
+  fn isr_tim3();
+  fn isr_tim4();
+  fn isr_i2c1_ev();
+  fn isr_i2c1_er();
+  fn isr_i2c2_ev();
+  fn isr_i2c2_er();
+  fn isr_spi1();
+  fn isr_spi2();
+  fn isr_usart1();
+  fn isr_usart2();
+  fn isr_usart3();
+  fn isr_exti_15_10();
+  fn isr_rtc_alarm();
+  fn isr_otg_fs_wkup();
+  fn isr_tim8_brk_tim12();
+  fn isr_tim8_up_tim13();
+  fn isr_tim8_trg_com_tim14();
+  fn isr_tim8_cc();
+  fn isr_dma1_stream_7();
+  fn isr_fsmc();
+  fn isr_sdio();
+  fn isr_tim5();
+  fn isr_spi3();
+  fn isr_uart4();
+  fn isr_uart5();
+  fn isr_tim6_dac();
+  fn isr_tim7();
+  fn isr_dma2_stream_0();
+  fn isr_dma2_stream_1();
+  fn isr_dma2_stream_2();
+  fn isr_dma2_stream_3();
+  fn isr_dma2_stream_4();
+  fn isr_eth();
+  fn isr_eth_wkup();
+  fn isr_can2_tx();
+  fn isr_can2_rx0();
+  fn isr_can2_rx1();
+  fn isr_can2_sce();
+  fn isr_otg_fs();
+  fn isr_dma2_stream_5();
+  fn isr_dma2_stream_6();
+  fn isr_dma2_stream_7();
+  fn isr_usart6();
+  fn isr_i2c3_ev();
+  fn isr_i2c3_er();
+  fn isr_otg_hs_ep1_out();
+  fn isr_otg_hs_ep1_in();
+  fn isr_otg_hs_wkup();
+  fn isr_otg_hs();
+  fn isr_dcmi();
+  fn isr_cryp();
+  fn isr_hash_rng();
+  fn isr_fpu();
+}
+
+#[allow(non_upper_case_globals)]
+const ISRCount: usize = 82;
+
+#[allow(non_upper_case_globals)]
+#[link_section=".isr_vector_nvic"]
+#[no_mangle]
+pub static NVICVectors: [Option<unsafe extern fn()>; ISRCount] = [
+  // s.a. RM0090, table 61 (chapter 12.2)
+  Some(isr_wwdg),
+  Some(isr_pvd),
+  Some(isr_tamp_stamp),
+  Some(isr_rtc_wkup),
+  Some(isr_flash),
+  Some(isr_rcc),
+  Some(isr_exti_0),
+  Some(isr_exti_1),
+  Some(isr_exti_2),
+  Some(isr_exti_3),
+  Some(isr_exti_4),
+  Some(isr_dma1_stream_0),
+  Some(isr_dma1_stream_1),
+  Some(isr_dma1_stream_2),
+  Some(isr_dma1_stream_3),
+  Some(isr_dma1_stream_4),
+  Some(isr_dma1_stream_5),
+  Some(isr_dma1_stream_6),
+  Some(isr_adc),
+  Some(isr_can1_tx),
+  Some(isr_can1_rx0),
+  Some(isr_can1_rx1),
+  Some(isr_can1_sce),
+  Some(isr_exti_9_5),
+  Some(isr_tim1_brk_tim9),
+  Some(isr_tim1_up_tim10),
+  Some(isr_tim1_trg_com_tim11),
+  Some(isr_tim1_cc),
+  Some(isr_tim2),
+  Some(isr_tim3),
+  Some(isr_tim4),
+  Some(isr_i2c1_ev),
+  Some(isr_i2c1_er),
+  Some(isr_i2c2_ev),
+  Some(isr_i2c2_er),
+  Some(isr_spi1),
+  Some(isr_spi2),
+  Some(isr_usart1),
+  Some(isr_usart2),
+  Some(isr_usart3),
+  Some(isr_exti_15_10),
+  Some(isr_rtc_alarm),
+  Some(isr_otg_fs_wkup),
+  Some(isr_tim8_brk_tim12),
+  Some(isr_tim8_up_tim13),
+  Some(isr_tim8_trg_com_tim14),
+  Some(isr_tim8_cc),
+  Some(isr_dma1_stream_7),
+  Some(isr_fsmc),
+  Some(isr_sdio),
+  Some(isr_tim5),
+  Some(isr_spi3),
+  Some(isr_uart4),
+  Some(isr_uart5),
+  Some(isr_tim6_dac),
+  Some(isr_tim7),
+  Some(isr_dma2_stream_0),
+  Some(isr_dma2_stream_1),
+  Some(isr_dma2_stream_2),
+  Some(isr_dma2_stream_3),
+  Some(isr_dma2_stream_4),
+  Some(isr_eth),
+  Some(isr_eth_wkup),
+  Some(isr_can2_tx),
+  Some(isr_can2_rx0),
+  Some(isr_can2_rx1),
+  Some(isr_can2_sce),
+  Some(isr_otg_fs),
+  Some(isr_dma2_stream_5),
+  Some(isr_dma2_stream_6),
+  Some(isr_dma2_stream_7),
+  Some(isr_usart6),
+  Some(isr_i2c3_ev),
+  Some(isr_i2c3_er),
+  Some(isr_otg_hs_ep1_out),
+  Some(isr_otg_hs_ep1_in),
+  Some(isr_otg_hs_wkup),
+  Some(isr_otg_hs),
+  Some(isr_dcmi),
+  Some(isr_cryp),
+  Some(isr_hash_rng),
+  Some(isr_fpu),
+];
@@ -15,8 +15,13 @@
 
 //! HAL for STM32F4.
 
+pub mod adc;
+pub mod flash;
 pub mod init;
 pub mod peripheral_clock;
 pub mod pin;
+pub mod pwm;
 pub mod timer;
 pub mod i2c;
+pub mod usart;
+pub mod watchdog;
@@ -17,8 +17,15 @@
 //!
 //! Some pins that could be configured here may be missing from actual MCU
 //! depending on the package.
-
-use hal::pin::{Gpio, GpioDirection, GpioLevel};
+//!
+//! Edge interrupts are routed through EXTI. A line can only be connected to one
+//! port at a time, so `PA0` and `PB0` cannot both raise interrupts. The
+//! application defines the `isr_exti_*` handlers it needs and calls
+//! `handle_interrupt()` from them.
+
+use hal::cortex_m4::irq::NoInterrupts;
+use hal::cortex_m4::nvic;
+use hal::pin::{Gpio, GpioDirection, GpioLevel, Edge, PinHandler, InterruptPin};
 use super::peripheral_clock;
 use core::intrinsics::abort;
 
@@ -165,6 +172,91 @@ impl Gpio for Pin {
   }
 }
 
+impl InterruptPin for Pin {
+  /// Connects the EXTI line to this pin's port, registers the handler and
+  /// enables the interrupt.
+  fn set_interrupt(&self, edge: Edge, handler: PinHandler) {
+    let line = self.pin as usize;
+
+    peripheral_clock::PeripheralClock::SYSCFGClock.enable();
+    reg::SYSCFG.exticr[line / 4].set_exti(line % 4, self.port as u32);
+
+    {
+      let _crit = NoInterrupts::new();
+      unsafe {
+        PinInterrupts[line] = PinInterrupt {
+          handler: Some(handler),
+          edge: edge,
+        };
+      }
+    }
+    self.clear_interrupt();
+    self.enable_interrupt();
+    nvic::enable_irq(exti_irq(line));
+  }
+
+  fn enable_interrupt(&self) {
+    let line = self.pin as usize;
+    let edge = unsafe { PinInterrupts[line].edge };
+    let exti = &reg::EXTI;
+
+    exti.rtsr.set_tr(line, edge != Edge::Falling);
+    exti.ftsr.set_tr(line, edge != Edge::Rising);
+    exti.imr.set_mr(line, true);
+  }
+
+  fn disable_interrupt(&self) {
+    reg::EXTI.imr.set_mr(self.pin as usize, false);
+  }
+
+  fn clear_interrupt(&self) {
+    reg::EXTI.pr.ignoring_state().clear_pr(self.pin as usize);
+  }
+}
+
+#[derive(Clone, Copy)]
+struct PinInterrupt {
+  handler: Option<PinHandler>,
+  edge: Edge,
+}
+
+const PIN_INTERRUPT_INIT: PinInterrupt = PinInterrupt {
+  handler: None,
+  edge: Edge::Both,
+};
+
+#[allow(non_upper_case_globals)]
+static mut PinInterrupts: [PinInterrupt; 16] = [PIN_INTERRUPT_INIT; 16];
+
+fn exti_irq(line: usize) -> usize {
+  match line {
+    0...4   => 6 + line,
+    5...9   => 23,
+    10...15 => 40,
+    _       => unsafe { abort() },
+  }
+}
+
+/// Dispatches the pending interrupts of EXTI lines `first` to `last`.
+///
+/// `isr_exti_0` to `isr_exti_4` serve a single line, `isr_exti_9_5` lines 5 to
+/// 9 and `isr_exti_15_10` lines 10 to 15.
+///
+/// Unsafe because it reads the handlers without a critical section, it must
+/// only be called from the EXTI interrupt handlers.
+pub unsafe fn handle_interrupt(first: usize, last: usize) {
+  let exti = &reg::EXTI;
+  for line in first..last + 1 {
+    if exti.pr.pr(line) {
+      exti.pr.ignoring_state().clear_pr(line);
+      match PinInterrupts[line].handler {
+        Some(handler) => handler(),
+        None => {},
+      }
+    }
+  }
+}
+
 /// Alternate Function Pin
 pub struct AlternateFunctionPin {
   /// Pin
@@ -298,4 +390,36 @@ mod reg {
     // define_reg!(GPIO_J: GPIO @ 0x40022400)
     // define_reg!(GPIO_K: GPIO @ 0x40022800)
   }
+
+  ioregs!(SYSCFG = {
+    0x08 => reg32 exticr[4] {  //! External interrupt configuration
+      0..15 => exti[4]
+    }
+  });
+
+  ioregs!(EXTI = {
+    0x00 => reg32 imr {        //! Interrupt mask
+      0..22 => mr[23]
+    }
+    0x04 => reg32 emr {        //! Event mask
+      0..22 => mr[23]
+    }
+    0x08 => reg32 rtsr {       //! Rising trigger selection
+      0..22 => tr[23]
+    }
+    0x0c => reg32 ftsr {       //! Falling trigger selection
+      0..22 => tr[23]
+    }
+    0x10 => reg32 swier {      //! Software interrupt event
+      0..22 => swier[23]
+    }
+    0x14 => reg32 pr {         //! Pending
+      0..22 => pr[23]: set_to_clear
+    }
+  });
+
+  extern {
+    #[link_name="stm32f4_iomem_SYSCFG"] pub static SYSCFG: SYSCFG;
+    #[link_name="stm32f4_iomem_EXTI"] pub static EXTI: EXTI;
+  }
 }
@@ -0,0 +1,245 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+Universal synchronous asynchronous receiver transmitter (USART).
+
+USART1 and USART6 are clocked from APB2, the others from APB1, so the baud
+rate is computed from the clocks set up by `init`. UART4 and UART5 don't
+support half and one and a half stop bits.
+*/
+
+use core::fmt;
+use core::result::Result;
+use core::intrinsics::abort;
+use core::option::Option::{self, Some, None};
+
+use drivers::chario::{CharIO, CharInput};
+use hal::uart;
+use hal::stm32f4::init;
+use hal::stm32f4::peripheral_clock::PeripheralClock;
+
+use self::UsartPeripheral::*;
+
+#[path="../../util/wait_for.rs"]
+#[macro_use] mod wait_for;
+
+/// Available USART peripherals.
+#[allow(missing_docs)]
+#[repr(u8)]
+#[derive(Clone, Copy)]
+pub enum UsartPeripheral {
+  Usart1,
+  Usart2,
+  Usart3,
+  Uart4,
+  Uart5,
+  Usart6,
+}
+
+/// USART word length, including the parity bit.
+#[allow(missing_docs)]
+#[repr(u8)]
+#[derive(Clone, Copy)]
+pub enum WordLen {
+  WordLen8bits = 0,
+  WordLen9bits = 1,
+}
+
+/// Stop bits configuration.
+#[repr(u8)]
+#[derive(Clone, Copy)]
+pub enum StopBit {
+  /// Single stop bit.
+  StopBit1bit       = 0,
+  /// A half stop bit.
+  StopBit05bits    = 1,
+  /// Two stop bits.
+  StopBit2bits      = 2,
+  /// One and a half stop bits.
+  StopBit15bits    = 3,
+}
+
+/// Structure describing a USART instance.
+#[derive(Clone, Copy)]
+pub struct Usart {
+  reg: &'static reg::USART,
+}
+
+impl Usart {
+  /// Create a new USART port.
+  ///
+  /// Aborts on forced parity, and on half or one and a half stop bits for
+  /// UART4 and UART5.
+  pub fn new(peripheral: UsartPeripheral, baudrate: u32, word_len: WordLen,
+             parity: uart::Parity, stop_bits: StopBit) -> Usart {
+    use hal::uart::Parity::*;
+    use self::StopBit::*;
+
+    match (peripheral, stop_bits) {
+      (Uart4, StopBit05bits) | (Uart4, StopBit15bits) |
+      (Uart5, StopBit05bits) | (Uart5, StopBit15bits) => unsafe { abort() },
+      _ => {},
+    }
+
+    let (reg, clock) = match peripheral {
+      Usart1 => (&reg::USART1, PeripheralClock::USART1Clock),
+      Usart2 => (&reg::USART2, PeripheralClock::USART2Clock),
+      Usart3 => (&reg::USART3, PeripheralClock::USART3Clock),
+      Uart4  => (&reg::UART4,  PeripheralClock::UART4Clock),
+      Uart5  => (&reg::UART5,  PeripheralClock::UART5Clock),
+      Usart6 => (&reg::USART6, PeripheralClock::USART6Clock),
+    };
+    let bus_clock = match peripheral {
+      Usart1 | Usart6 => init::apb_high_clock(),
+      _               => init::apb_low_clock(),
+    };
+
+    clock.enable();
+
+    reg.cr1.set_word_length(word_len as usize != 0);
+    reg.cr2.set_stop_bits(stop_bits as u16);
+
+    // Tx/Rx baud = Fck / (16 * USARTDIV), BRR holds USARTDIV in 1/16ths
+    reg.cr1.set_oversample_8bit_enable(false);
+    reg.brr.set_divisor(((bus_clock + baudrate / 2) / baudrate) as u16);
+
+    let (pe_on, pe_select) = match parity {
+        Disabled => (false, false),
+        Even => (true, false),
+        Odd => (true, true),
+        _ => unsafe { abort() }, // not supported
+    };
+    reg.cr1.set_parity_control_enable(pe_on);
+    reg.cr1.set_parity_selection(pe_select);
+    reg.cr1.set_transmitter_enable(true);
+    reg.cr1.set_receiver_enable(true);
+
+    reg.cr1.set_usart_enable(true);
+
+    Usart {
+      reg: reg,
+    }
+  }
+}
+
+impl CharIO for Usart {
+  fn putc(&self, value: char) {
+    wait_for!(self.reg.sr.transmit_data_empty());
+    self.reg.dr.set_data(value as u16);
+  }
+}
+
+impl CharInput for Usart {
+  fn try_getc(&self) -> Option<char> {
+    if self.reg.sr.read_data_not_empty() {
+      Some(self.reg.dr.data() as u8 as char)
+    } else {
+      None
+    }
+  }
+}
+
+impl fmt::Write for Usart {
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    use core::str::StrExt;
+    for b in s.bytes() {
+      wait_for!(self.reg.sr.transmit_data_empty());
+      self.reg.dr.set_data(b as u16);
+    }
+    Result::Ok(())
+  }
+}
+
+mod reg {
+  use volatile_cell::VolatileCell;
+  use core::ops::Drop;
+
+  ioregs!(USART = {
+    0x00 => reg16 sr {  // status
+      0 => error_parity     : ro,
+      1 => error_framing    : ro,
+      2 => error_noise      : ro,
+      3 => error_overrun    : ro,
+      4 => idle_line        : ro,
+      5 => read_data_not_empty      : ro,
+      6 => transmission_complete    : ro,
+      7 => transmit_data_empty      : ro,
+      8 => lin_break        : ro,
+      9 => cts              : ro
+    },
+    0x04 => reg16 dr {  // data
+      8..0 => data : rw,
+    },
+    0x08 => reg16 brr { // baud rate
+      15..0 => divisor : rw,
+    },
+    0x0C => reg16 cr1 { // control 1
+      0 => send_break : rw,
+      1 => receiver_wakeup : rw,
+      2 => receiver_enable : rw,
+      3 => transmitter_enable : rw,
+      4 => int_idle_enable : rw,
+      5 => int_read_data_not_empty_enable : rw,
+      6 => int_transmission_complete_enable : rw,
+      7 => int_transmission_data_empty_enable : rw,
+      8 => int_pe_enable : rw,
+      9 => parity_selection : rw,
+      10 => parity_control_enable : rw,
+      11 => wakeup_method : rw,
+      12 => word_length : rw,
+      13 => usart_enable : rw,
+      15 => oversample_8bit_enable : rw,
+    },
+    0x10 => reg16 cr2 { // control 2
+      3..0 => address : rw,
+      5 => line_break_length : rw,
+      6 => int_line_break_enable : rw,
+      8 => last_bit_clock_pulse : rw,
+      9 => clock_phase : rw,
+      10 => clock_polarity : rw,
+      11 => clock_enable : rw,
+      13..12 => stop_bits : rw,
+      14 => line_mode_enable : rw,
+    },
+    0x14 => reg16 cr3 { // control 3
+      0 => int_error_enable : rw,
+      1 => irda_mode_enable : rw,
+      2 => irda_low_power : rw,
+      3 => half_duplex_selection : rw,
+      4 => smartcard_nack_enable : rw,
+      5 => smartcard_mode_enable : rw,
+      6 => dma_receiver_enable : rw,
+      7 => dma_transmitter_enable : rw,
+      8 => rts_enable : rw,
+      9 => cts_enable : rw,
+      10 => int_cts_enable : rw,
+      11 => one_sample_method_enable : rw,
+    },
+    0x18 => reg16 gtpr {    // guard time and prescaler
+      7..0  => prescaler  : rw,
+      15..8 => guard_time : rw,
+    },
+  });
+
+  extern {
+    #[link_name="stm32f4_iomem_USART1"] pub static USART1: USART;
+    #[link_name="stm32f4_iomem_USART2"] pub static USART2: USART;
+    #[link_name="stm32f4_iomem_USART3"] pub static USART3: USART;
+    #[link_name="stm32f4_iomem_UART4"]  pub static UART4:  USART;
+    #[link_name="stm32f4_iomem_UART5"]  pub static UART5:  USART;
+    #[link_name="stm32f4_iomem_USART6"] pub static USART6: USART;
+  }
+}
@@ -18,4 +18,8 @@
 pub mod init;
 pub mod peripheral_clock;
 pub mod pin;
+pub mod pwm;
+pub mod spi;
 pub mod timer;
+pub mod usart;
+pub mod watchdog;
@@ -20,6 +20,7 @@
 //!
 //! Note: this module is used as part of initial setup if PLL is used.
 
+use super::init;
 use super::init::reg;
 use core::marker::Copy;
 
@@ -110,6 +111,7 @@ pub enum PeripheralClock {
   ADC3Clock,
   SDMMC1Clock,
   SPI1Clock,
+  SPI4Clock,
   SYSCFGClock,
   TIM9Clock,
   TIM10Clock,
@@ -123,6 +125,16 @@ pub enum PeripheralClock {
 
 impl Copy for PeripheralClock {}
 
+/// Bus a peripheral clock is attached to.
+#[derive(Clone, Copy)]
+enum Bus {
+  Ahb1,
+  Ahb2,
+  Ahb3,
+  Apb1,
+  Apb2,
+}
+
 impl PeripheralClock {
   /// Enables the given peripheral clock.
   pub fn enable(self) {
@@ -133,6 +145,15 @@ impl PeripheralClock {
     self.set_reg(false);
   }
 
+  /// Returns the frequency of the bus the peripheral is attached to.
+  pub fn frequency(self) -> u32 {
+    match self.bus() {
+      Bus::Ahb1|Bus::Ahb2|Bus::Ahb3 => init::system_clock(),
+      Bus::Apb1                     => init::apb_low_clock(),
+      Bus::Apb2                     => init::apb_high_clock(),
+    }
+  }
+
   fn to_reg_bit(self) -> u32 {
     1 << match self {
       GPIOAClock      => 0,
@@ -207,6 +228,7 @@ impl PeripheralClock {
       ADC3Clock       => 10,
       SDMMC1Clock     => 11,
       SPI1Clock       => 12,
+      SPI4Clock       => 13,
       SYSCFGClock     => 14,
       TIM9Clock       => 16,
       TIM10Clock      => 17,
@@ -219,33 +241,43 @@ impl PeripheralClock {
     }
   }
 
-  fn set_reg(self, enable: bool) {
-    let reg_bit = self.to_reg_bit();
-    let mask: u32 = !reg_bit;
-    let bit: u32 = if enable {reg_bit} else {0};
+  fn bus(self) -> Bus {
     match self {
       GPIOAClock|GPIOBClock|GPIOCClock|GPIODClock|GPIOEClock|GPIOFClock|
       GPIOGClock|GPIOHClock|GPIOIClock|GPIOJClock|GPIOKClock|CRCClock|
       BKPSRAMClock|DTCMClock|DMA1Clock|DMA2Clock|DMA2DClock|
       ETHMACClock|ETHMACTxClock|ETHMACRxClock|
-      ETHMACPTPClock|OTGHSClock|OTGHSULPIClock => {
+      ETHMACPTPClock|OTGHSClock|OTGHSULPIClock => Bus::Ahb1,
+      DCMIClock|CRYPClock|HASHClock|RNGClock|OTGFSClock => Bus::Ahb2,
+      FSMCClock|QSPIClock => Bus::Ahb3,
+      TIM2Clock|TIM3Clock|TIM4Clock|TIM5Clock|TIM6Clock|TIM7Clock|TIM12Clock|
+      TIM13Clock|TIM14Clock|LPTIM1Clock|WWDGClock|SPI2Clock|SPI3Clock|SPDIFClock|
+      USART2Clock|USART3Clock|UART4Clock|UART5Clock|I2C1Clock|I2C2Clock|I2C3Clock|
+      I2C4Clock|CAN1Clock|CAN2Clock|CECClock|PWRClock|DACClock|UART7Clock|UART8Clock => Bus::Apb1,
+      TIM1Clock|TIM8Clock|USART1Clock|USART6Clock|ADC1Clock|ADC2Clock|ADC3Clock|
+      SDMMC1Clock|SPI1Clock|SPI4Clock|SYSCFGClock|TIM9Clock|TIM10Clock|TIM11Clock|
+      SPI5Clock|SPI6Clock|SAI1Clock|SAI2Clock|LTDCClock => Bus::Apb2,
+    }
+  }
+
+  fn set_reg(self, enable: bool) {
+    let reg_bit = self.to_reg_bit();
+    let mask: u32 = !reg_bit;
+    let bit: u32 = if enable {reg_bit} else {0};
+    match self.bus() {
+      Bus::Ahb1 => {
         reg::RCC.ahb1enr.set_enable((reg::RCC.ahb1enr.enable() & mask) | bit);
       },
-      DCMIClock|CRYPClock|HASHClock|RNGClock|OTGFSClock => {
+      Bus::Ahb2 => {
         reg::RCC.ahb2enr.set_enable((reg::RCC.ahb2enr.enable() & mask) | bit);
       },
-      FSMCClock|QSPIClock => {
+      Bus::Ahb3 => {
         reg::RCC.ahb3enr.set_enable((reg::RCC.ahb3enr.enable() & mask) | bit);
       },
-      TIM2Clock|TIM3Clock|TIM4Clock|TIM5Clock|TIM6Clock|TIM7Clock|TIM12Clock|
-      TIM13Clock|TIM14Clock|LPTIM1Clock|WWDGClock|SPI2Clock|SPI3Clock|SPDIFClock|
-      USART2Clock|USART3Clock|UART4Clock|UART5Clock|I2C1Clock|I2C2Clock|I2C3Clock|
-      I2C4Clock|CAN1Clock|CAN2Clock|CECClock|PWRClock|DACClock|UART7Clock|UART8Clock => {
+      Bus::Apb1 => {
         reg::RCC.apb1enr.set_enable((reg::RCC.apb1enr.enable() & mask) | bit);
       },
-      TIM1Clock|TIM8Clock|USART1Clock|USART6Clock|ADC1Clock|ADC2Clock|ADC3Clock|
-      SDMMC1Clock|SPI1Clock|SYSCFGClock|TIM9Clock|TIM10Clock|TIM11Clock|
-      SPI5Clock|SPI6Clock|SAI1Clock|SAI2Clock|LTDCClock => {
+      Bus::Apb2 => {
         reg::RCC.apb2enr.set_enable((reg::RCC.apb2enr.enable() & mask) | bit);
       },
     }
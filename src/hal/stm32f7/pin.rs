@@ -172,6 +172,46 @@ impl Gpio for Pin {
   }
 }
 
+/// Alternate Function Pin
+pub struct AlternateFunctionPin {
+  /// Pin
+  pub pin: Pin,
+  /// Alternate function number
+  pub alternate_function: u16,
+}
+
+impl AlternateFunctionPin {
+  /// Create new alterate function pin
+  pub fn new(pin: u8, port: Port, alt_func: u16) -> AlternateFunctionPin {
+    AlternateFunctionPin {
+      pin: Pin {
+        pin: pin,
+        port: port,
+        function: Function::AltFunction,
+      },
+      alternate_function: alt_func,
+    }
+  }
+
+  /// Setup the pin
+  #[inline(always)]
+  pub fn setup(&self) {
+    use self::reg::GPIO_moder_mode as RegMode;
+
+    self.pin.port.clock().enable();
+
+    let offset = self.pin.pin as usize;
+    let gpreg = self.pin.get_reg();
+
+    gpreg.moder.set_mode(offset, RegMode::Alternate);
+    if offset > 7 {
+      gpreg.afrh.set_afrh(offset - 8, self.alternate_function as u32);
+    } else {
+      gpreg.afrl.set_afrl(offset, self.alternate_function as u32);
+    }
+  }
+}
+
 #[allow(dead_code)]
 mod reg {
   use core::ops::Drop;
@@ -182,8 +222,8 @@ mod reg {
       0..31 => mode[16] {
         0 => Input,
         1 => Output,
-        3 => Alternate,
-        4 => Analog
+        2 => Alternate,
+        3 => Analog
       }
     }
     0x04 => reg32 otyper {
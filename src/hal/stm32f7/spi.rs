@@ -0,0 +1,395 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serial Peripheral Interface for STM32F7.
+//!
+//! Frames can be 4 to 16 bits long. The data register packs two frames into
+//! a 16-bit access, so frames of up to 8 bits are moved with byte accesses.
+//! `write()` and `read()` of `hal::spi::Spi` truncate longer frames, use
+//! `transfer_frame()` for those.
+
+use core::intrinsics::{abort, volatile_load, volatile_store};
+use core::result::Result;
+use core::result::Result::{Ok, Err};
+
+use hal::stm32f7::peripheral_clock::PeripheralClock;
+
+#[path="../../util/wait_for.rs"]
+#[macro_use] mod wait_for;
+
+/// Available SPI peripherals.
+#[allow(missing_docs)]
+#[repr(u8)]
+#[derive(Clone, Copy)]
+pub enum Peripheral {
+  Spi1,
+  Spi2,
+  Spi3,
+  Spi4,
+  Spi5,
+  Spi6,
+}
+
+/// SPI direction modes.
+#[repr(u8)]
+#[derive(PartialEq, Clone, Copy)]
+pub enum Direction {
+  /// 2 lines, default mode
+  FullDuplex,
+  /// 2 lines, but read-only
+  RxOnly,
+  /// 1 line, read
+  Rx,
+  /// 1 line, transmit
+  Tx,
+}
+
+#[allow(missing_docs)]
+#[repr(u8)]
+#[derive(Clone, Copy)]
+pub enum Role {
+  Slave = 0,
+  Master = 1,
+}
+
+/// SPI data format.
+#[repr(u8)]
+#[derive(Clone, Copy)]
+pub enum DataFormat {
+  /// Most Significant Bit
+  MsbFirst = 0,
+  /// Least Significant Bit
+  LsbFirst = 1,
+}
+
+#[allow(missing_docs)]
+#[repr(u8)]
+#[derive(PartialEq, Clone, Copy)]
+pub enum ClockPhase {
+  Edge1 = 0,
+  Edge2 = 1,
+}
+
+#[allow(missing_docs)]
+#[repr(u8)]
+#[derive(PartialEq, Clone, Copy)]
+pub enum ClockPolarity {
+  Low = 0,
+  High = 1,
+}
+
+/// Transfer settings applied through `Configure`.
+#[derive(PartialEq, Clone, Copy)]
+pub struct Settings {
+  /// Clock phase.
+  pub phase: ClockPhase,
+  /// Clock polarity.
+  pub polarity: ClockPolarity,
+  /// Baud rate prescaler, the bus runs at the APB clock divided by
+  /// `1 << prescaler_shift`. Valid values are 1 to 8, `configure()` aborts
+  /// on others.
+  pub prescaler_shift: u8,
+}
+
+/// SPI initialization errors.
+#[repr(u8)]
+#[derive(Clone, Copy)]
+pub enum Error {
+  /// Invalid baud rate shift.
+  BaudRate,
+  /// Frame size outside of 4 to 16 bits.
+  DataSize,
+  /// Invalid resulting mode.
+  Mode,
+}
+
+/// Structure describing a SPI instance.
+#[derive(Clone, Copy)]
+pub struct Spi {
+  reg: &'static reg::SPI,
+  clock: PeripheralClock,
+  data_size: u8,
+}
+
+impl Spi {
+  /// Create a new SPI port with frames of `data_size` bits.
+  pub fn new(peripheral: Peripheral, direction: Direction,
+             role: Role, data_size: u8, format: DataFormat,
+             prescaler_shift: u8) -> Result<Spi, Error> {
+    let (reg, clock) = match peripheral {
+      Peripheral::Spi1 => (&reg::SPI1, PeripheralClock::SPI1Clock),
+      Peripheral::Spi2 => (&reg::SPI2, PeripheralClock::SPI2Clock),
+      Peripheral::Spi3 => (&reg::SPI3, PeripheralClock::SPI3Clock),
+      Peripheral::Spi4 => (&reg::SPI4, PeripheralClock::SPI4Clock),
+      Peripheral::Spi5 => (&reg::SPI5, PeripheralClock::SPI5Clock),
+      Peripheral::Spi6 => (&reg::SPI6, PeripheralClock::SPI6Clock),
+    };
+
+    if data_size < 4 || data_size > 16 {
+      return Err(Error::DataSize)
+    }
+    if prescaler_shift<1 || prescaler_shift>8 {
+      return Err(Error::BaudRate)
+    }
+
+    clock.enable();
+    reg.cr1.set_spi_enable(false);
+
+    // set direction
+    reg.cr1
+      .set_receive_only(direction == Direction::RxOnly)
+      .set_bidirectional_data_mode(direction == Direction::Rx
+          || direction == Direction::Tx)
+      .set_bidirectional_output_enable(direction == Direction::Tx);
+
+    // set role
+    reg.cr1
+      .set_master(role as usize != 0)
+      .set_internal_slave_select(role as usize != 0)
+      .set_software_slave_management(true);
+    reg.cr2.set_ss_output_enable(false);
+
+    // set data size and format (MSB or LSB), RXNE fires on every frame of
+    // up to 8 bits instead of every two
+    reg.cr2
+      .set_data_size(data_size as u32 - 1)
+      .set_rx_fifo_threshold(data_size <= 8);
+    reg.cr1.set_frame_format(format as usize != 0);
+
+    // set baud rate and clock mode
+    reg.cr1
+      .set_baud_rate(prescaler_shift as u32 - 1)
+      .set_clock_phase(ClockPhase::Edge1 as usize != 0)
+      .set_clock_polarity(ClockPolarity::Low as usize != 0);
+
+    reg.i2s_cfgr.set_enable(false);
+    reg.cr1.set_hardware_crc_enable(false);
+
+    if reg.sr.mode_fault() {
+      Err(Error::Mode)
+    } else {
+      reg.cr1.set_spi_enable(true);
+      Ok(Spi {
+        reg: reg,
+        clock: clock,
+        data_size: data_size,
+      })
+    }
+  }
+
+  /// Returns the SPI bus frequency.
+  pub fn frequency(&self) -> u32 {
+    self.clock.frequency() >> (self.reg.cr1.baud_rate() + 1)
+  }
+
+  /// Writes one frame, then returns the frame read at the same time.
+  pub fn transfer_frame(&self, value: u16) -> u16 {
+    wait_for!(self.reg.sr.transmit_buffer_empty());
+    self.write_data(value);
+    wait_for!(self.reg.sr.receive_buffer_not_empty());
+    self.read_data()
+  }
+
+  fn data_register(&self) -> usize {
+    &self.reg.dr as *const reg::SPI_dr as usize
+  }
+
+  fn write_data(&self, value: u16) {
+    unsafe {
+      if self.data_size <= 8 {
+        volatile_store(self.data_register() as *mut u8, value as u8);
+      } else {
+        volatile_store(self.data_register() as *mut u16, value);
+      }
+    }
+  }
+
+  fn read_data(&self) -> u16 {
+    unsafe {
+      if self.data_size <= 8 {
+        volatile_load(self.data_register() as *const u8) as u16
+      } else {
+        volatile_load(self.data_register() as *const u16)
+      }
+    }
+  }
+}
+
+impl ::hal::spi::Frames for Spi {
+  /// The receive FIFO holds two frames of up to 16 bits, so two frames in
+  /// flight never overrun it.
+  fn frames_in_flight(&self) -> usize {
+    2
+  }
+
+  fn tx_ready(&self) -> bool {
+    self.reg.sr.transmit_buffer_empty()
+  }
+
+  fn rx_ready(&self) -> bool {
+    self.reg.sr.receive_buffer_not_empty()
+  }
+
+  /// BSY alone may read clear between two frames, so a frame still waiting
+  /// in the transmit buffer counts as well.
+  fn busy(&self) -> bool {
+    !self.reg.sr.transmit_buffer_empty() || self.reg.sr.busy_flag()
+  }
+
+  fn write_frame(&self, value: u8) {
+    self.write_data(value as u16);
+  }
+
+  fn read_frame(&self) -> u8 {
+    self.read_data() as u8
+  }
+}
+
+impl ::hal::spi::Configure for Spi {
+  type Settings = Settings;
+
+  /// Aborts if `settings.prescaler_shift` is not between 1 and 8.
+  fn configure(&self, settings: Settings) {
+    if settings.prescaler_shift<1 || settings.prescaler_shift>8 {
+      unsafe { abort() };
+    }
+
+    wait_for!(self.reg.sr.tx_fifo_level() == 0);
+    wait_for!(!self.reg.sr.busy_flag());
+
+    self.reg.cr1.set_spi_enable(false);
+    self.reg.cr1
+      .set_baud_rate(settings.prescaler_shift as u32 - 1)
+      .set_clock_phase(settings.phase as usize != 0)
+      .set_clock_polarity(settings.polarity as usize != 0);
+    self.reg.cr1.set_spi_enable(true);
+  }
+}
+
+impl ::hal::spi::Spi for Spi {
+  fn write(&self, value: u8) {
+    wait_for!(self.reg.sr.transmit_buffer_empty());
+    self.write_data(value as u16);
+  }
+
+  fn read(&self) -> u8 {
+    wait_for!(self.reg.sr.receive_buffer_not_empty());
+    self.read_data() as u8
+  }
+
+  fn flush(&self) {
+    wait_for!(self.reg.sr.transmit_buffer_empty());
+    wait_for!(!self.reg.sr.busy_flag());
+  }
+
+  fn write_all(&self, data: &[u8]) {
+    ::hal::spi::write_frames(self, data);
+  }
+
+  fn transfer_in_place(&self, buf: &mut [u8]) {
+    ::hal::spi::transfer_frames(self, buf);
+  }
+
+  fn read_into(&self, buf: &mut [u8], fill: u8) {
+    ::hal::spi::read_frames(self, buf, fill);
+  }
+}
+
+mod reg {
+  use volatile_cell::VolatileCell;
+  use core::ops::Drop;
+
+  ioregs!(SPI = {
+    0x00 => reg32 cr1 { // control 1
+      0 => clock_phase : rw,
+      1 => clock_polarity : rw,
+      2 => master : rw,
+      5..3 => baud_rate : rw,
+      6 => spi_enable : rw,
+      7 => frame_format : rw,
+      8 => internal_slave_select : rw,
+      9 => software_slave_management : rw,
+      10 => receive_only : rw,
+      11 => crc_length : rw,
+      12 => transmit_crc_next : rw,
+      13 => hardware_crc_enable : rw,
+      14 => bidirectional_output_enable : rw,
+      15 => bidirectional_data_mode : rw,
+    },
+    0x04 => reg32 cr2 { // control 2
+      0 => rx_dma_enable : rw,
+      1 => tx_dma_enable : rw,
+      2 => ss_output_enable : rw,
+      3 => nss_pulse : rw,
+      4 => frame_format : rw,
+      5 => error_interrupt_enable : rw,
+      6 => rx_buffer_not_empty_interrupt_enable : rw,
+      7 => tx_buffer_empty_interrupt_enable : rw,
+      11..8 => data_size : rw,
+      12 => rx_fifo_threshold : rw,
+      13 => last_dma_rx : rw,
+      14 => last_dma_tx : rw,
+    },
+    0x08 => reg32 sr { // status
+      0 => receive_buffer_not_empty : ro,
+      1 => transmit_buffer_empty : ro,
+      2 => channel_side : ro,
+      3 => underrun_flag : ro,
+      4 => crc_error : ro,
+      5 => mode_fault : ro,
+      6 => overrun_flag : ro,
+      7 => busy_flag : ro,
+      8 => frame_error : ro,
+      10..9 => rx_fifo_level : ro,
+      12..11 => tx_fifo_level : ro,
+    },
+    0x0C => reg32 dr { // data, see `write_data()` for the access width
+      15..0 => data : rw,
+    },
+    0x10 => reg32 crc { // CRC
+      15..0 => polynomial : rw,
+    },
+    0x14 => reg32 rx_crc { // Rx CRC
+      15..0 => crc : ro,
+    },
+    0x18 => reg32 tx_crc { // Tx CRC
+      15..0 => crc : ro,
+    },
+    0x1C => reg32 i2s_cfgr { // I2S config
+      0 => channel_length : rw,
+      2..1 => data_length : rw,
+      3 => clock_polarity : rw,
+      5..4 => standard_selection : rw,
+      7 => pcm_frame_sync : rw,
+      9..8 => configuration_mode : rw,
+      10 => enable : rw,
+      11 => mode_selection : rw,
+    },
+    0x20 => reg32 i2s_pr { // I2S prescaler
+      7..0 => linear_prescaler : rw,
+      8 => odd_factor : rw,
+      9 => master_clock_output_enable : rw,
+    },
+  });
+
+  extern {
+    #[link_name="stm32f7_iomem_SPI1"] pub static SPI1: SPI;
+    #[link_name="stm32f7_iomem_SPI2"] pub static SPI2: SPI;
+    #[link_name="stm32f7_iomem_SPI3"] pub static SPI3: SPI;
+    #[link_name="stm32f7_iomem_SPI4"] pub static SPI4: SPI;
+    #[link_name="stm32f7_iomem_SPI5"] pub static SPI5: SPI;
+    #[link_name="stm32f7_iomem_SPI6"] pub static SPI6: SPI;
+  }
+}
@@ -0,0 +1,295 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+Universal synchronous asynchronous receiver transmitter (USART).
+
+USARTs are clocked from their APB clock, which `init` selects for all of them
+on startup. Oversampling by 16 is used, so the baud rate can't exceed 1/16 of
+that clock.
+*/
+
+use core::fmt;
+use core::result::Result;
+use core::intrinsics::abort;
+use core::option::Option::{self, Some, None};
+
+use drivers::chario::{CharIO, CharInput};
+use hal::uart;
+use hal::stm32f7::peripheral_clock::PeripheralClock;
+
+use self::UsartPeripheral::*;
+
+#[path="../../util/wait_for.rs"]
+#[macro_use] mod wait_for;
+
+/// Available USART peripherals.
+#[allow(missing_docs)]
+#[repr(u8)]
+#[derive(Clone, Copy)]
+pub enum UsartPeripheral {
+  Usart1,
+  Usart2,
+  Usart3,
+  Uart4,
+  Uart5,
+  Usart6,
+  Uart7,
+  Uart8,
+}
+
+/// USART word length, including the parity bit.
+#[allow(missing_docs)]
+#[repr(u8)]
+#[derive(Clone, Copy)]
+pub enum WordLen {
+  WordLen7bits,
+  WordLen8bits,
+  WordLen9bits,
+}
+
+/// Stop bits configuration.
+#[repr(u8)]
+#[derive(Clone, Copy)]
+pub enum StopBit {
+  /// Single stop bit.
+  StopBit1bit       = 0,
+  /// A half stop bit.
+  StopBit05bits    = 1,
+  /// Two stop bits.
+  StopBit2bits      = 2,
+  /// One and a half stop bits.
+  StopBit15bits    = 3,
+}
+
+/// Structure describing a USART instance.
+#[derive(Clone, Copy)]
+pub struct Usart {
+  reg: &'static reg::USART,
+}
+
+impl Usart {
+  /// Create a new USART port.
+  pub fn new(peripheral: UsartPeripheral, baudrate: u32, word_len: WordLen,
+             parity: uart::Parity, stop_bits: StopBit) -> Usart {
+    use hal::uart::Parity::*;
+
+    let (reg, clock) = match peripheral {
+      Usart1 => (&reg::USART1, PeripheralClock::USART1Clock),
+      Usart2 => (&reg::USART2, PeripheralClock::USART2Clock),
+      Usart3 => (&reg::USART3, PeripheralClock::USART3Clock),
+      Uart4  => (&reg::UART4,  PeripheralClock::UART4Clock),
+      Uart5  => (&reg::UART5,  PeripheralClock::UART5Clock),
+      Usart6 => (&reg::USART6, PeripheralClock::USART6Clock),
+      Uart7  => (&reg::UART7,  PeripheralClock::UART7Clock),
+      Uart8  => (&reg::UART8,  PeripheralClock::UART8Clock),
+    };
+
+    clock.enable();
+
+    // The configuration is locked while the USART is enabled.
+    reg.cr1.set_usart_enable(false);
+
+    let (m1, m0) = match word_len {
+      WordLen::WordLen7bits => (true, false),
+      WordLen::WordLen8bits => (false, false),
+      WordLen::WordLen9bits => (false, true),
+    };
+    reg.cr1
+      .set_word_length_1(m1)
+      .set_word_length_0(m0);
+    reg.cr2.set_stop_bits(stop_bits as u32);
+
+    // Tx/Rx baud = Fck / USARTDIV, with oversampling by 16
+    let bus_clock = clock.frequency();
+    reg.cr1.set_oversample_8bit_enable(false);
+    reg.brr.set_divisor((bus_clock + baudrate / 2) / baudrate);
+
+    let (pe_on, pe_select) = match parity {
+        Disabled => (false, false),
+        Even => (true, false),
+        Odd => (true, true),
+        _ => unsafe { abort() }, // not supported
+    };
+    reg.cr1
+      .set_parity_control_enable(pe_on)
+      .set_parity_selection(pe_select);
+    reg.cr1
+      .set_transmitter_enable(true)
+      .set_receiver_enable(true);
+
+    reg.cr1.set_usart_enable(true);
+
+    Usart {
+      reg: reg,
+    }
+  }
+}
+
+impl CharIO for Usart {
+  fn putc(&self, value: char) {
+    wait_for!(self.reg.isr.transmit_data_empty());
+    self.reg.tdr.set_data(value as u32);
+  }
+}
+
+impl CharInput for Usart {
+  fn try_getc(&self) -> Option<char> {
+    // An overrun blocks reception until it's cleared.
+    if self.reg.isr.error_overrun() {
+      self.reg.icr.set_overrun_clear(true);
+    }
+    if self.reg.isr.read_data_not_empty() {
+      Some(self.reg.rdr.data() as u8 as char)
+    } else {
+      None
+    }
+  }
+}
+
+impl fmt::Write for Usart {
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    use core::str::StrExt;
+    for b in s.bytes() {
+      wait_for!(self.reg.isr.transmit_data_empty());
+      self.reg.tdr.set_data(b as u32);
+    }
+    Result::Ok(())
+  }
+}
+
+mod reg {
+  use volatile_cell::VolatileCell;
+  use core::ops::Drop;
+
+  ioregs!(USART = {
+    0x00 => reg32 cr1 {  // control 1
+      0 => usart_enable : rw,
+      1 => usart_enable_in_stop_mode : rw,
+      2 => receiver_enable : rw,
+      3 => transmitter_enable : rw,
+      4 => int_idle_enable : rw,
+      5 => int_read_data_not_empty_enable : rw,
+      6 => int_transmission_complete_enable : rw,
+      7 => int_transmission_data_empty_enable : rw,
+      8 => int_pe_enable : rw,
+      9 => parity_selection : rw,
+      10 => parity_control_enable : rw,
+      11 => wakeup_method : rw,
+      12 => word_length_0 : rw,
+      13 => mute_mode_enable : rw,
+      14 => int_character_match_enable : rw,
+      15 => oversample_8bit_enable : rw,
+      20..16 => driver_enable_deassertion_time : rw,
+      25..21 => driver_enable_assertion_time : rw,
+      26 => int_receiver_timeout_enable : rw,
+      27 => int_end_of_block_enable : rw,
+      28 => word_length_1 : rw,
+    },
+    0x04 => reg32 cr2 {  // control 2
+      4 => address_detection_7bit : rw,
+      5 => line_break_length : rw,
+      6 => int_line_break_enable : rw,
+      8 => last_bit_clock_pulse : rw,
+      9 => clock_phase : rw,
+      10 => clock_polarity : rw,
+      11 => clock_enable : rw,
+      13..12 => stop_bits : rw,
+      14 => line_mode_enable : rw,
+      15 => swap_tx_rx : rw,
+      16 => rx_inverted : rw,
+      17 => tx_inverted : rw,
+      18 => data_inverted : rw,
+      19 => msb_first : rw,
+      31..24 => address : rw,
+    },
+    0x08 => reg32 cr3 {  // control 3
+      0 => int_error_enable : rw,
+      1 => irda_mode_enable : rw,
+      2 => irda_low_power : rw,
+      3 => half_duplex_selection : rw,
+      4 => smartcard_nack_enable : rw,
+      5 => smartcard_mode_enable : rw,
+      6 => dma_receiver_enable : rw,
+      7 => dma_transmitter_enable : rw,
+      8 => rts_enable : rw,
+      9 => cts_enable : rw,
+      10 => int_cts_enable : rw,
+      11 => one_sample_method_enable : rw,
+      12 => overrun_disable : rw,
+      13 => dma_disable_on_reception_error : rw,
+      14 => driver_enable_mode : rw,
+      15 => driver_enable_polarity : rw,
+    },
+    0x0C => reg32 brr {  // baud rate
+      15..0 => divisor : rw,
+    },
+    0x10 => reg32 gtpr { // guard time and prescaler
+      7..0  => prescaler  : rw,
+      15..8 => guard_time : rw,
+    },
+    0x1C => reg32 isr {  // interrupt and status
+      0 => error_parity     : ro,
+      1 => error_framing    : ro,
+      2 => error_noise      : ro,
+      3 => error_overrun    : ro,
+      4 => idle_line        : ro,
+      5 => read_data_not_empty      : ro,
+      6 => transmission_complete    : ro,
+      7 => transmit_data_empty      : ro,
+      8 => lin_break        : ro,
+      9 => cts_interrupt    : ro,
+      10 => cts             : ro,
+      11 => receiver_timeout  : ro,
+      12 => end_of_block    : ro,
+      16 => busy            : ro,
+      17 => character_match : ro,
+      18 => send_break      : ro,
+      19 => receiver_wakeup : ro,
+      21 => transmit_enable_ack : ro,
+      22 => receive_enable_ack  : ro,
+    },
+    0x20 => reg32 icr {  // interrupt flag clear
+      0 => parity_clear     : wo,
+      1 => framing_clear    : wo,
+      2 => noise_clear      : wo,
+      3 => overrun_clear    : wo,
+      4 => idle_line_clear  : wo,
+      6 => transmission_complete_clear : wo,
+      8 => lin_break_clear  : wo,
+      9 => cts_clear        : wo,
+      11 => receiver_timeout_clear : wo,
+      12 => end_of_block_clear : wo,
+      17 => character_match_clear : wo,
+    },
+    0x24 => reg32 rdr {  // receive data
+      8..0 => data : ro,
+    },
+    0x28 => reg32 tdr {  // transmit data
+      8..0 => data : wo,
+    },
+  });
+
+  extern {
+    #[link_name="stm32f7_iomem_USART1"] pub static USART1: USART;
+    #[link_name="stm32f7_iomem_USART2"] pub static USART2: USART;
+    #[link_name="stm32f7_iomem_USART3"] pub static USART3: USART;
+    #[link_name="stm32f7_iomem_UART4"]  pub static UART4:  USART;
+    #[link_name="stm32f7_iomem_UART5"]  pub static UART5:  USART;
+    #[link_name="stm32f7_iomem_USART6"] pub static USART6: USART;
+    #[link_name="stm32f7_iomem_UART7"]  pub static UART7:  USART;
+    #[link_name="stm32f7_iomem_UART8"]  pub static UART8:  USART;
+  }
+}
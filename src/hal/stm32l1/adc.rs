@@ -0,0 +1,391 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Analog to digital converter for ST STM32L1.
+//!
+//! ADC1 has up to 26 channels depending on the package, including the
+//! temperature sensor (16) and internal reference (17). The pins have to be
+//! configured as `Analog` separately. The converter is clocked from HSI, which
+//! is turned on by `ADC::new`.
+//!
+//! Scans use the regular sequence with an end of conversion flag after each
+//! channel. If a result isn't read before the next one is ready, e.g. because
+//! an interrupt held up the CPU, the sequence is converted again. Continuous conversions report their results from the ADC
+//! interrupt, applications using them define `isr_adc1` and call
+//! `handle_interrupt()` from it.
+
+use core::cell::Cell;
+use core::intrinsics::abort;
+use core::option::Option::{self, Some, None};
+
+use hal::adc;
+use hal::adc::{Averaging, ConversionHandler};
+use hal::cortex_m3::irq::NoInterrupts;
+use hal::cortex_m3::nvic;
+use hal::stm32l1::init::reg::RCC;
+use hal::stm32l1::peripheral_clock::{PeripheralClock, BusApb2};
+
+#[path="../../util/wait_for.rs"]
+#[macro_use] mod wait_for;
+
+/// Conversion resolution.
+#[allow(missing_docs)]
+#[derive(Clone, Copy)]
+pub enum Resolution {
+  Bits12 = 0,
+  Bits10 = 1,
+  Bits8  = 2,
+  Bits6  = 3,
+}
+
+impl Resolution {
+  fn bits(self) -> u8 {
+    12 - 2 * self as u8
+  }
+}
+
+const CHANNEL_COUNT: usize = 26;
+
+/// Maximum length of the regular sequence.
+const MAX_SEQUENCE: usize = 27;
+
+const ADC_IRQ: usize = 18;
+
+/// Sampling time of 48 ADC clock cycles.
+const SAMPLE_TIME: u32 = 0b100;
+
+/// HSI divided by 1.
+const PRESCALER: u32 = 0b00;
+
+/// State of a continuous conversion.
+#[derive(Clone, Copy)]
+struct Continuous {
+  handler: Option<ConversionHandler>,
+  channels: [u8; MAX_SEQUENCE],
+  len: usize,
+  position: usize,
+  overruns: u32,
+}
+
+const CONTINUOUS_INIT: Continuous = Continuous {
+  handler: None,
+  channels: [0; MAX_SEQUENCE],
+  len: 0,
+  position: 0,
+  overruns: 0,
+};
+
+#[allow(non_upper_case_globals)]
+static mut ContinuousState: Continuous = CONTINUOUS_INIT;
+
+/// The analog to digital converter.
+pub struct ADC {
+  reg: &'static reg::ADC,
+  resolution: Resolution,
+  reference_mv: u32,
+  averaging: Cell<Averaging>,
+}
+
+impl ADC {
+  /// Powers up the converter. `reference_mv` is the voltage on VREF+ in
+  /// millivolts.
+  pub fn new(resolution: Resolution, reference_mv: u32) -> ADC {
+    RCC.cr.set_hsi_on(true);
+    wait_for!(RCC.cr.hsi_ready());
+    PeripheralClock::Apb2(BusApb2::Adc1).enable();
+    reg::ADC_COMMON.ccr.set_adcpre(PRESCALER);
+
+    let reg = &reg::ADC1;
+    reg.cr1.set_res(resolution as u32);
+    for channel in 0..10 {
+      reg.smpr3.set_smp(channel, SAMPLE_TIME);
+    }
+    for channel in 0..10 {
+      reg.smpr2.set_smp(channel, SAMPLE_TIME);
+    }
+    for channel in 0..(CHANNEL_COUNT - 20) {
+      reg.smpr1.set_smp(channel, SAMPLE_TIME);
+    }
+    reg.cr2.set_eocs(true).set_adon(true);
+    wait_for!(reg.sr.adons());
+
+    ADC {
+      reg: reg,
+      resolution: resolution,
+      reference_mv: reference_mv,
+      averaging: Cell::new(Averaging::NoAveraging),
+    }
+  }
+
+  /// Enables the temperature sensor and internal reference channels.
+  pub fn enable_internal_channels(&self) {
+    reg::ADC_COMMON.ccr.set_tsvrefe(true);
+  }
+
+  fn set_sequence(&self, channels: &[u8]) {
+    if channels.len() == 0 || channels.len() > MAX_SEQUENCE {
+      unsafe { abort() };
+    }
+
+    for (index, &channel) in channels.iter().enumerate() {
+      if channel as usize >= CHANNEL_COUNT {
+        unsafe { abort() };
+      }
+      let channel = channel as u32;
+      match index {
+        0...5   => { self.reg.sqr5.set_sq(index, channel); },
+        6...11  => { self.reg.sqr4.set_sq(index - 6, channel); },
+        12...17 => { self.reg.sqr3.set_sq(index - 12, channel); },
+        18...23 => { self.reg.sqr2.set_sq(index - 18, channel); },
+        _       => { self.reg.sqr1.set_sq(index - 24, channel); },
+      }
+    }
+    self.reg.sqr1.set_l(channels.len() as u32 - 1);
+  }
+
+  /// Waits for the next result, returns None if a result was lost and the
+  /// converter stopped the sequence.
+  fn wait_result(&self) -> Option<u16> {
+    loop {
+      if self.reg.sr.ovr() {
+        return None;
+      }
+      if self.reg.sr.eoc() {
+        return Some(self.reg.dr.data() as u16);
+      }
+    }
+  }
+
+  /// Converts the configured sequence once, adding each result to the
+  /// matching entry of `sums`.
+  fn convert_sequence(&self, sums: &mut [u32]) {
+    let mut results = [0u16; MAX_SEQUENCE];
+    'sequence: loop {
+      self.reg.cr2.set_swstart(true);
+      for result in results.iter_mut().take(sums.len()) {
+        match self.wait_result() {
+          Some(value) => *result = value,
+          None => {
+            // start over from the first channel
+            self.reg.dr.data();
+            self.reg.sr.set_ovr(false);
+            continue 'sequence;
+          },
+        }
+      }
+      break;
+    }
+
+    for (sum, result) in sums.iter_mut().zip(results.iter()) {
+      *sum += *result as u32;
+    }
+  }
+
+  /// Returns the number of times a continuous conversion lost a result
+  /// since it was started.
+  pub fn overruns(&self) -> u32 {
+    let _crit = NoInterrupts::new();
+    unsafe { ContinuousState.overruns }
+  }
+}
+
+impl adc::Adc for ADC {
+  fn channel_count(&self) -> usize {
+    CHANNEL_COUNT
+  }
+
+  fn resolution(&self) -> u8 {
+    self.resolution.bits()
+  }
+
+  fn reference_mv(&self) -> u32 {
+    self.reference_mv
+  }
+
+  fn set_averaging(&self, averaging: Averaging) {
+    self.averaging.set(averaging);
+  }
+
+  fn read_channel(&self, channel: u8) -> u16 {
+    let samples = self.averaging.get().samples();
+    self.set_sequence(&[channel]);
+    self.reg.cr1.set_scan(false);
+
+    let mut sum = [0u32; 1];
+    for _ in 0..samples {
+      self.convert_sequence(&mut sum);
+    }
+
+    (sum[0] / samples) as u16
+  }
+
+  /// Converts up to 27 channels as a regular sequence.
+  fn scan(&self, channels: &[u8], results: &mut [u16]) {
+    let samples = self.averaging.get().samples();
+    let mut sums = [0u32; MAX_SEQUENCE];
+    self.set_sequence(channels);
+    self.reg.cr1.set_scan(true);
+
+    for _ in 0..samples {
+      self.convert_sequence(&mut sums[..channels.len()]);
+    }
+
+    for (result, sum) in results.iter_mut().zip(sums.iter())
+        .take(channels.len()) {
+      *result = (*sum / samples) as u16;
+    }
+  }
+}
+
+impl adc::ContinuousAdc for ADC {
+  /// Repeats the regular sequence until stopped. Averaging doesn't apply,
+  /// each sample is reported.
+  fn start_continuous(&self, channels: &[u8], handler: ConversionHandler) {
+    self.set_sequence(channels);
+    {
+      let _crit = NoInterrupts::new();
+      let state = unsafe { &mut ContinuousState };
+      state.handler = Some(handler);
+      state.len = channels.len();
+      state.position = 0;
+      state.overruns = 0;
+      for (slot, channel) in state.channels.iter_mut().zip(channels.iter()) {
+        *slot = *channel;
+      }
+    }
+
+    self.reg.sr.set_ovr(false);
+    self.reg.cr1.set_scan(true).set_eocie(true).set_ovrie(true);
+    self.reg.cr2.set_cont(true);
+    nvic::enable_irq(ADC_IRQ);
+    self.reg.cr2.set_swstart(true);
+  }
+
+  fn stop_continuous(&self) {
+    self.reg.cr2.set_cont(false);
+    self.reg.cr1.set_eocie(false).set_ovrie(false);
+
+    let _crit = NoInterrupts::new();
+    unsafe { ContinuousState.handler = None };
+  }
+}
+
+/// Services the interrupt of continuous conversions.
+///
+/// Unsafe because it accesses the conversion state without a critical
+/// section, it must only be called from `isr_adc1`.
+pub unsafe fn handle_interrupt() {
+  let reg = &reg::ADC1;
+  let state = &mut ContinuousState;
+  let handler = match state.handler {
+    Some(handler) => handler,
+    None => return,
+  };
+
+  if reg.sr.ovr() {
+    // A result was lost and the sequence stopped. Restart it from the first
+    // channel so that the following results are not reported against the
+    // wrong channels.
+    reg.dr.data();
+    reg.sr.set_ovr(false);
+    state.overruns += 1;
+    state.position = 0;
+    reg.cr2.set_swstart(true);
+    return;
+  }
+  if !reg.sr.eoc() {
+    return;
+  }
+
+  // Reading the data register clears the flag
+  let value = reg.dr.data() as u16;
+  let channel = state.channels[state.position];
+  state.position = (state.position + 1) % state.len;
+  handler(channel, value);
+}
+
+mod reg {
+  use core::ops::Drop;
+  use volatile_cell::VolatileCell;
+
+  ioregs!(ADC = {
+    0x00 => reg32 sr {     //! Status
+      0 => awd,
+      1 => eoc,
+      2 => jeoc,
+      3 => jstrt,
+      4 => strt,
+      5 => ovr,
+      6 => adons,
+      8 => rcnr,
+    }
+    0x04 => reg32 cr1 {    //! Control 1
+      0..4   => awdch,
+      5      => eocie,
+      8      => scan,
+      24..25 => res,
+      26     => ovrie,
+    }
+    0x08 => reg32 cr2 {    //! Control 2
+      0  => adon,
+      1  => cont,
+      8  => dma,
+      9  => dds,
+      10 => eocs,
+      11 => align,
+      30 => swstart,
+    }
+    0x0c => reg32 smpr1 {  //! Sample time, channels 20 to 29
+      0..29 => smp[10],
+    }
+    0x10 => reg32 smpr2 {  //! Sample time, channels 10 to 19
+      0..29 => smp[10],
+    }
+    0x14 => reg32 smpr3 {  //! Sample time, channels 0 to 9
+      0..29 => smp[10],
+    }
+    0x30 => reg32 sqr1 {   //! Regular sequence 25 to 28 and length
+      0..19  => sq[4],
+      20..24 => l,
+    }
+    0x34 => reg32 sqr2 {   //! Regular sequence 19 to 24
+      0..29 => sq[6],
+    }
+    0x38 => reg32 sqr3 {   //! Regular sequence 13 to 18
+      0..29 => sq[6],
+    }
+    0x3c => reg32 sqr4 {   //! Regular sequence 7 to 12
+      0..29 => sq[6],
+    }
+    0x40 => reg32 sqr5 {   //! Regular sequence 1 to 6
+      0..29 => sq[6],
+    }
+    0x58 => reg32 dr {     //! Regular data
+      0..15 => data: ro,
+    }
+  });
+
+  ioregs!(ADC_COMMON = {
+    0x04 => reg32 ccr {    //! Common control
+      16..17 => adcpre,
+      23     => tsvrefe,
+    }
+  });
+
+  extern {
+    #[link_name="stm32l1_iomem_ADC1"] pub static ADC1: ADC;
+    #[link_name="stm32l1_iomem_ADC_COMMON"] pub static ADC_COMMON: ADC_COMMON;
+  }
+}
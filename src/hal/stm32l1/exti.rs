@@ -0,0 +1,45 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! External interrupt controller, shared by pin interrupts and the power
+//! wake-up sources.
+
+use volatile_cell::VolatileCell;
+use core::ops::Drop;
+
+ioregs!(EXTI = {
+  0x00 => reg32 imr {        //! Interrupt mask
+    0..22 => mr[23]
+  }
+  0x04 => reg32 emr {        //! Event mask
+    0..22 => mr[23]
+  }
+  0x08 => reg32 rtsr {       //! Rising trigger selection
+    0..22 => tr[23]
+  }
+  0x0c => reg32 ftsr {       //! Falling trigger selection
+    0..22 => tr[23]
+  }
+  0x10 => reg32 swier {      //! Software interrupt event
+    0..22 => swier[23]
+  }
+  0x14 => reg32 pr {         //! Pending
+    0..22 => pr[23]: set_to_clear
+  }
+});
+
+extern {
+  #[link_name="stm32l1_iomem_EXTI"] pub static EXTI: EXTI;
+}
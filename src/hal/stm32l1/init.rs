@@ -193,8 +193,18 @@ impl ClockConfig {
         2
       },
       SystemClockPLL(pll_source, mul, div) => {
-        r.cr.set_pll_on(true);
-        wait_for!(r.cr.pll_ready());
+        // the PLL and its input are off after reset and after Stop mode, and
+        // the PLL can only be configured while it is off
+        match pll_source {
+          PllClockSource::PllSourceHSI => {
+            r.cr.set_hsi_on(true);
+            wait_for!(r.cr.hsi_ready());
+          },
+          PllClockSource::PllSourceHSE => {
+            r.cr.set_hse_on(true);
+            wait_for!(r.cr.hse_ready());
+          },
+        }
         r.cfgr.set_pll_clock_source(pll_source as usize != 0);
         let factor = match mul {
           3 => 0,
@@ -210,6 +220,8 @@ impl ClockConfig {
         };
         r.cfgr.set_pll_mul_factor(factor);
         r.cfgr.set_pll_output_div(div as u32);
+        r.cr.set_pll_on(true);
+        wait_for!(r.cr.pll_ready());
         3
       }
     };
@@ -0,0 +1,145 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ISR Data for stm32l1
+
+use core::option::Option::{self, Some};
+
+extern {
+  fn isr_wwdg();
+  fn isr_pvd();
+  fn isr_tamper_stamp();
+  fn isr_rtc_wkup();
+  fn isr_flash();
+  fn isr_rcc();
+  fn isr_exti_0();
+  fn isr_exti_1();
+  fn isr_exti_2();
+  fn isr_exti_3();
+  fn isr_exti_4();
+  fn isr_dma1_channel_1();
+  fn isr_dma1_channel_2();
+  fn isr_dma1_channel_3();
+  fn isr_dma1_channel_4();
+  fn isr_dma1_channel_5();
+  fn isr_dma1_channel_6();
+  fn isr_dma1_channel_7();
+  fn isr_adc1();
+  fn isr_usb_hp();
+  fn isr_usb_lp();
+  fn isr_dac();
+  fn isr_comp();
+  fn isr_exti_9_5();
+  fn isr_lcd();
+  fn isr_tim9();
+  fn isr_tim10();
+  fn isr_tim11();
+  fn isr_tim2();
+  fn isr_tim3();
+  fn isr_tim4();
+  fn isr_i2c1_ev();
+  fn isr_i2c1_er();
+  fn isr_i2c2_ev();
+  fn isr_i2c2_er();
+  fn isr_spi1();
+  fn isr_spi2();
+  fn isr_usart1();
+  fn isr_usart2();
+  fn isr_usart3();
+  fn isr_exti_15_10();
+  fn isr_rtc_alarm();
+  fn isr_usb_fs_wkup();
+  fn isr_tim6();
+  fn isr_tim7();
+  fn isr_sdio();
+  fn isr_tim5();
+  fn isr_spi3();
+  fn isr_uart4();
+  fn isr_uart5();
+  fn isr_dma2_channel_1();
+  fn isr_dma2_channel_2();
+  fn isr_dma2_channel_3();
+  fn isr_dma2_channel_4();
+  fn isr_dma2_channel_5();
+  fn isr_aes();
+  fn isr_comp_acq();
+}
+
+#[allow(non_upper_case_globals)]
+const ISRCount: usize = 57;
+
+#[allow(non_upper_case_globals)]
+#[link_section=".isr_vector_nvic"]
+#[no_mangle]
+pub static NVICVectors: [Option<unsafe extern fn()>; ISRCount] = [
+  // s.a. RM0038, table 48 (chapter 10.1.2)
+  Some(isr_wwdg),
+  Some(isr_pvd),
+  Some(isr_tamper_stamp),
+  Some(isr_rtc_wkup),
+  Some(isr_flash),
+  Some(isr_rcc),
+  Some(isr_exti_0),
+  Some(isr_exti_1),
+  Some(isr_exti_2),
+  Some(isr_exti_3),
+  Some(isr_exti_4),
+  Some(isr_dma1_channel_1),
+  Some(isr_dma1_channel_2),
+  Some(isr_dma1_channel_3),
+  Some(isr_dma1_channel_4),
+  Some(isr_dma1_channel_5),
+  Some(isr_dma1_channel_6),
+  Some(isr_dma1_channel_7),
+  Some(isr_adc1),
+  Some(isr_usb_hp),
+  Some(isr_usb_lp),
+  Some(isr_dac),
+  Some(isr_comp),
+  Some(isr_exti_9_5),
+  Some(isr_lcd),
+  Some(isr_tim9),
+  Some(isr_tim10),
+  Some(isr_tim11),
+  Some(isr_tim2),
+  Some(isr_tim3),
+  Some(isr_tim4),
+  Some(isr_i2c1_ev),
+  Some(isr_i2c1_er),
+  Some(isr_i2c2_ev),
+  Some(isr_i2c2_er),
+  Some(isr_spi1),
+  Some(isr_spi2),
+  Some(isr_usart1),
+  Some(isr_usart2),
+  Some(isr_usart3),
+  Some(isr_exti_15_10),
+  Some(isr_rtc_alarm),
+  Some(isr_usb_fs_wkup),
+  Some(isr_tim6),
+  Some(isr_tim7),
+  Some(isr_sdio),
+  Some(isr_tim5),
+  Some(isr_spi3),
+  Some(isr_uart4),
+  Some(isr_uart5),
+  Some(isr_dma2_channel_1),
+  Some(isr_dma2_channel_2),
+  Some(isr_dma2_channel_3),
+  Some(isr_dma2_channel_4),
+  Some(isr_dma2_channel_5),
+  Some(isr_aes),
+  Some(isr_comp_acq),
+];
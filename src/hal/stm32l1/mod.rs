@@ -15,9 +15,15 @@
 
 //! HAL for STM32L1.
 
+pub mod adc;
 pub mod init;
 pub mod peripheral_clock;
 pub mod pin;
+pub mod power;
+pub mod pwm;
 pub mod spi;
 pub mod timer;
 pub mod usart;
+pub mod watchdog;
+
+mod exti;
@@ -17,7 +17,16 @@
 //!
 //! Some pins that could be configured here may be missing from actual MCU
 //! depending on the package.
+//!
+//! Edge interrupts are routed through EXTI. A line can only be connected to one
+//! port at a time, so `PA0` and `PB0` cannot both raise interrupts. The
+//! application defines the `isr_exti_*` handlers it needs and calls
+//! `handle_interrupt()` from them.
 
+use hal::cortex_m3::irq::NoInterrupts;
+use hal::cortex_m3::nvic;
+use hal::pin::{Edge, PinHandler, InterruptPin};
+use super::exti::EXTI;
 use super::peripheral_clock;
 use core::intrinsics::abort;
 use self::Port::*;
@@ -37,6 +46,22 @@ pub enum Port {
   PortH,
 }
 
+impl Port {
+  /// EXTI source code of the port, ports F and G come after H.
+  fn exti_source(self) -> u32 {
+    match self {
+      PortA => 0,
+      PortB => 1,
+      PortC => 2,
+      PortD => 3,
+      PortE => 4,
+      PortH => 5,
+      PortF => 6,
+      PortG => 7,
+    }
+  }
+}
+
 /// Pin output type.
 #[allow(missing_docs)]
 #[repr(u8)]
@@ -109,6 +134,8 @@ pub enum Mode {
 pub struct Pin {
   /// Pin index.
   pub index: u8,
+  /// Port the pin is attached to
+  port: Port,
   /// GPIO register
   reg: &'static reg::GPIO,
 }
@@ -182,11 +209,98 @@ impl Pin {
 
     Pin {
       index: pin_index,
+      port: port,
       reg: reg,
     }
   }
 }
 
+impl InterruptPin for Pin {
+  /// Connects the EXTI line to this pin's port, registers the handler and
+  /// enables the interrupt.
+  fn set_interrupt(&self, edge: Edge, handler: PinHandler) {
+    let line = self.index as usize;
+
+    peripheral_clock::PeripheralClock::Apb2(
+      peripheral_clock::BusApb2::SysCfg).enable();
+    reg::SYSCFG.exticr[line / 4].set_exti(line % 4, self.port.exti_source());
+
+    {
+      let _crit = NoInterrupts::new();
+      unsafe {
+        PinInterrupts[line] = PinInterrupt {
+          handler: Some(handler),
+          edge: edge,
+        };
+      }
+    }
+    self.clear_interrupt();
+    self.enable_interrupt();
+    nvic::enable_irq(exti_irq(line));
+  }
+
+  fn enable_interrupt(&self) {
+    let line = self.index as usize;
+    let edge = unsafe { PinInterrupts[line].edge };
+    let exti = &EXTI;
+
+    exti.rtsr.set_tr(line, edge != Edge::Falling);
+    exti.ftsr.set_tr(line, edge != Edge::Rising);
+    exti.imr.set_mr(line, true);
+  }
+
+  fn disable_interrupt(&self) {
+    EXTI.imr.set_mr(self.index as usize, false);
+  }
+
+  fn clear_interrupt(&self) {
+    EXTI.pr.ignoring_state().clear_pr(self.index as usize);
+  }
+}
+
+#[derive(Clone, Copy)]
+struct PinInterrupt {
+  handler: Option<PinHandler>,
+  edge: Edge,
+}
+
+const PIN_INTERRUPT_INIT: PinInterrupt = PinInterrupt {
+  handler: None,
+  edge: Edge::Both,
+};
+
+#[allow(non_upper_case_globals)]
+static mut PinInterrupts: [PinInterrupt; 16] = [PIN_INTERRUPT_INIT; 16];
+
+fn exti_irq(line: usize) -> usize {
+  match line {
+    0...4   => 6 + line,
+    5...9   => 23,
+    10...15 => 40,
+    _       => unsafe { abort() },
+  }
+}
+
+/// Dispatches the pending interrupts of EXTI lines `first` to `last`.
+///
+/// `isr_exti_0` to `isr_exti_4` serve a single line, `isr_exti_9_5` lines 5 to
+/// 9 and `isr_exti_15_10` lines 10 to 15.
+///
+/// Unsafe because it reads the handlers without a critical section, it must
+/// only be called from the EXTI interrupt handlers.
+pub unsafe fn handle_interrupt(first: usize, last: usize) {
+  let exti = &EXTI;
+  for line in first..last + 1 {
+    if exti.pr.pr(line) {
+      exti.pr.ignoring_state().clear_pr(line);
+      match PinInterrupts[line].handler {
+        Some(handler) => handler(),
+        None => {},
+      }
+    }
+  }
+}
+
 impl ::hal::pin::Gpio for Pin {
   fn set_high(&self) {
     let bit: u32 = 1 << self.index as usize;
@@ -263,4 +377,14 @@ mod reg {
     #[link_name="stm32l1_iomem_GPIOG"] pub static GPIOG: GPIO;
     #[link_name="stm32l1_iomem_GPIOH"] pub static GPIOH: GPIO;
   }
+
+  ioregs!(SYSCFG = {
+    0x08 => reg32 exticr[4] {  //! External interrupt configuration
+      0..15 => exti[4]
+    }
+  });
+
+  extern {
+    #[link_name="stm32l1_iomem_SYSCFG"] pub static SYSCFG: SYSCFG;
+  }
 }
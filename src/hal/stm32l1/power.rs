@@ -0,0 +1,233 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Low-power modes for ST STM32L1.
+//!
+//! `DeepSleep` and `PowerDown` enter Stop mode, with the main and the
+//! low-power regulator respectively. Any EXTI line wakes the MCU from Stop,
+//! which includes pins set up with `InterruptPin` and the RTC sources in
+//! `WakeUp`. The MCU wakes up on the MSI, so the clock is set up again with
+//! `ClockConfig::setup()`.
+//!
+//! `DeepPowerDown` enters Standby mode, which is left through the WKUP pins,
+//! the RTC or a reset. All of them restart the MCU.
+//!
+//! The RTC interrupts are left to the application, whose `isr_rtc_alarm` and
+//! `isr_rtc_wkup` handlers call `WakeUp::acknowledge()`.
+
+use hal::cortex_m3::{nvic, scb};
+use hal::power::{self, Mode};
+use hal::stm32l1::init::ClockConfig;
+use hal::stm32l1::init::reg::PWR;
+use hal::stm32l1::peripheral_clock::{PeripheralClock, BusApb1};
+use hal::stm32l1::exti::EXTI;
+use util::support::wfi;
+
+use self::reg::RTC;
+
+#[allow(non_upper_case_globals)]
+static ControlLowPowerRegulator: u32 = 1 << 0;
+#[allow(non_upper_case_globals)]
+static ControlPowerDownDeepSleep: u32 = 1 << 1;
+#[allow(non_upper_case_globals)]
+static ControlClearWakeUpFlag: u32 = 1 << 2;
+#[allow(non_upper_case_globals)]
+static ControlClearStandbyFlag: u32 = 1 << 3;
+#[allow(non_upper_case_globals)]
+static ControlUltraLowPower: u32 = 1 << 9;
+
+#[allow(non_upper_case_globals)]
+static StatusStandbyFlag: u32 = 1 << 1;
+#[allow(non_upper_case_globals)]
+static StatusEnableWakeUpPin1: u32 = 1 << 8;
+
+#[allow(non_upper_case_globals)]
+static RtcStatusInit: u32 = 1 << 7;
+#[allow(non_upper_case_globals)]
+static RtcStatusAlarmAFlag: u32 = 1 << 8;
+#[allow(non_upper_case_globals)]
+static RtcStatusWakeUpTimerFlag: u32 = 1 << 10;
+
+const WAKEUP_PIN_COUNT: u8 = 3;
+
+const RTC_ALARM_LINE: usize = 17;
+const RTC_WAKEUP_LINE: usize = 20;
+const RTC_ALARM_IRQ: usize = 41;
+const RTC_WAKEUP_IRQ: usize = 3;
+
+/// Wake-up source errors.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Error {
+  /// WKUP pin number outside of 1 to 3.
+  InvalidPin,
+}
+
+/// Sources that wake the MCU up, besides pin interrupts.
+#[derive(Clone, Copy)]
+pub enum WakeUp {
+  /// Rising edge on WKUP pin 1 to 3, wakes the MCU from Standby only.
+  Pin(u8),
+  /// RTC alarm, through EXTI line 17.
+  RtcAlarm,
+  /// RTC periodic wake-up timer, through EXTI line 20.
+  RtcWakeupTimer,
+}
+
+impl WakeUp {
+  fn exti(self) -> Option<(usize, usize)> {
+    match self {
+      WakeUp::Pin(_)         => None,
+      WakeUp::RtcAlarm       => Some((RTC_ALARM_LINE, RTC_ALARM_IRQ)),
+      WakeUp::RtcWakeupTimer => Some((RTC_WAKEUP_LINE, RTC_WAKEUP_IRQ)),
+    }
+  }
+
+  fn pin_enable_bit(n: u8) -> Result<u32, Error> {
+    match n {
+      1...WAKEUP_PIN_COUNT => Ok(StatusEnableWakeUpPin1 << (n - 1)),
+      _ => Err(Error::InvalidPin),
+    }
+  }
+
+  /// Clears the RTC flag and the EXTI line of an RTC source, to be called
+  /// from its interrupt handler. Does nothing for WKUP pins.
+  ///
+  /// The RTC flags are cleared by writing zero, so the other flags are
+  /// written as one and INIT is kept as is.
+  pub fn acknowledge(self) {
+    let flag = match self {
+      WakeUp::Pin(_)         => return,
+      WakeUp::RtcAlarm       => RtcStatusAlarmAFlag,
+      WakeUp::RtcWakeupTimer => RtcStatusWakeUpTimerFlag,
+    };
+    let init = RTC.isr.status() & RtcStatusInit;
+    let value = (!(flag | RtcStatusInit) & 0xffff) | init;
+    RTC.isr.ignoring_state().set_status(value);
+
+    let (line, _) = self.exti().unwrap();
+    EXTI.pr.ignoring_state().clear_pr(line);
+  }
+}
+
+/// Power mode control.
+#[derive(Clone, Copy)]
+pub struct Power {
+  clock: ClockConfig,
+}
+
+impl Power {
+  /// Creates power mode control that restores `clock` after waking up.
+  pub fn new(clock: ClockConfig) -> Power {
+    PeripheralClock::Apb1(BusApb1::Pwr).enable();
+    Power {
+      clock: clock,
+    }
+  }
+
+  /// Lets `source` wake the MCU up.
+  ///
+  /// RTC sources are routed to their EXTI line and interrupt. The
+  /// application provides the interrupt handler, which calls
+  /// `WakeUp::acknowledge()`; the RTC itself is set up by the application
+  /// too.
+  pub fn enable_wakeup(&self, source: WakeUp) -> Result<(), Error> {
+    match source {
+      WakeUp::Pin(n) => {
+        let bit = try!(WakeUp::pin_enable_bit(n));
+        PWR.csr.set_status(PWR.csr.status() | bit);
+      },
+      _ => {
+        let (line, irq) = source.exti().unwrap();
+        EXTI.pr.ignoring_state().clear_pr(line);
+        EXTI.rtsr.set_tr(line, true);
+        EXTI.imr.set_mr(line, true);
+        nvic::enable_irq(irq);
+      },
+    }
+    Ok(())
+  }
+
+  /// Stops `source` from waking the MCU up.
+  pub fn disable_wakeup(&self, source: WakeUp) -> Result<(), Error> {
+    match source {
+      WakeUp::Pin(n) => {
+        let bit = try!(WakeUp::pin_enable_bit(n));
+        PWR.csr.set_status(PWR.csr.status() & !bit);
+      },
+      _ => {
+        let (line, irq) = source.exti().unwrap();
+        nvic::disable_irq(irq);
+        EXTI.imr.set_mr(line, false);
+      },
+    }
+    Ok(())
+  }
+
+  /// Returns true if the MCU was restarted by leaving Standby, and clears
+  /// the flag.
+  pub fn woke_from_standby(&self) -> bool {
+    let woke = PWR.csr.status() & StatusStandbyFlag != 0;
+    if woke {
+      PWR.cr.set_control(PWR.cr.control() | ControlClearStandbyFlag);
+    }
+    woke
+  }
+}
+
+impl power::LowPower for Power {
+  fn enter(&self, mode: Mode) {
+    let control = PWR.cr.control() & !(ControlLowPowerRegulator |
+        ControlPowerDownDeepSleep | ControlUltraLowPower);
+
+    let (control, deep) = match mode {
+      Mode::Sleep         => (control, false),
+      Mode::DeepSleep     => (control, true),
+      Mode::PowerDown     =>
+          (control | ControlLowPowerRegulator | ControlUltraLowPower, true),
+      Mode::DeepPowerDown => (control | ControlPowerDownDeepSleep, true),
+    };
+
+    // a pending wake-up flag would make Standby exit right away
+    PWR.cr.set_control(control | ControlClearWakeUpFlag);
+    scb::set_sleep_deep(deep);
+    if let Mode::DeepPowerDown = mode {
+      // a pending interrupt makes wfi return without entering Standby
+      loop {
+        wfi();
+      }
+    }
+    wfi();
+    scb::set_sleep_deep(false);
+
+    if deep {
+      self.clock.setup();
+    }
+  }
+}
+
+mod reg {
+  use volatile_cell::VolatileCell;
+  use core::ops::Drop;
+
+  ioregs!(RTC = {
+    0x0c => reg32 isr {   // initialization and status
+      31..0 => status : rw,
+    },
+  });
+
+  extern {
+    #[link_name="stm32l1_iomem_RTC"] pub static RTC: RTC;
+  }
+}
@@ -0,0 +1,96 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! PWM support for ST STM32L1.
+//!
+//! Each of the four channels of TIM2 to TIM5 can drive a PWM output. Channels
+//! of the same timer share its period. Output pins are configured separately
+//! with `Mode::AltFunction`, using `PwmTimer::alt_mode()`.
+//!
+//! STM32L1 has no advanced timers, so there are no complementary outputs.
+
+use hal::stm32_common::pwm;
+use hal::stm32l1::peripheral_clock as pc;
+use hal::stm32l1::pin::AltMode;
+
+pub use hal::stm32_common::pwm::{PwmChannel, Alignment, Error};
+
+use self::PwmTimer::*;
+
+/// PWM output on a timer channel, created with `PWM::new()`.
+pub type PWM = pwm::PWM<PwmTimer>;
+
+/// Timers that can generate PWM.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum PwmTimer {
+  Timer2,
+  Timer3,
+  Timer4,
+  Timer5,
+}
+
+impl PwmTimer {
+  /// Alternate function connecting the timer channels to their pins.
+  pub fn alt_mode(self) -> AltMode {
+    match self {
+      Timer2 => AltMode::AfTim2,
+      _      => AltMode::AfTim3_Tim4_Tim5,
+    }
+  }
+}
+
+impl pwm::Timer for PwmTimer {
+  fn reg(self) -> &'static pwm::reg::TIM {
+    match self {
+      Timer2 => &reg::TIM2,
+      Timer3 => &reg::TIM3,
+      Timer4 => &reg::TIM4,
+      Timer5 => &reg::TIM5,
+    }
+  }
+
+  fn enable_clock(self) {
+    let clock = match self {
+      Timer2 => pc::PeripheralClock::Apb1(pc::BusApb1::Tim2),
+      Timer3 => pc::PeripheralClock::Apb1(pc::BusApb1::Tim3),
+      Timer4 => pc::PeripheralClock::Apb1(pc::BusApb1::Tim4),
+      Timer5 => pc::PeripheralClock::Apb1(pc::BusApb1::Tim5),
+    };
+    clock.enable();
+  }
+
+  fn max_reload(self) -> u32 {
+    match self {
+      Timer5 => 0xffff_ffff,
+      _ => 0xffff,
+    }
+  }
+
+  fn is_advanced(self) -> bool {
+    false
+  }
+}
+
+mod reg {
+  use hal::stm32_common::pwm::reg::TIM;
+
+  extern {
+    #[link_name="stm32l1_iomem_TIM2"] pub static TIM2: TIM;
+    #[link_name="stm32l1_iomem_TIM3"] pub static TIM3: TIM;
+    #[link_name="stm32l1_iomem_TIM4"] pub static TIM4: TIM;
+    #[link_name="stm32l1_iomem_TIM5"] pub static TIM5: TIM;
+  }
+}
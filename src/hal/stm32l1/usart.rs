@@ -21,8 +21,9 @@ Universal synchronous asynchronous receiver transmitter (USART).
 use core::fmt;
 use core::result::Result;
 use core::intrinsics::abort;
+use core::option::Option::{self, Some, None};
 
-use drivers::chario::CharIO;
+use drivers::chario::{CharIO, CharInput};
 use hal::uart;
 use hal::stm32l1::init;
 
@@ -131,6 +132,16 @@ impl CharIO for Usart {
   }
 }
 
+impl CharInput for Usart {
+  fn try_getc(&self) -> Option<char> {
+    if self.reg.sr.read_data_not_empty() {
+      Some(self.reg.dr.data() as u8 as char)
+    } else {
+      None
+    }
+  }
+}
+
 impl fmt::Write for Usart {
   fn write_str(&mut self, s: &str) -> fmt::Result {
     use core::str::StrExt;
@@ -0,0 +1,78 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Independent watchdog for ST STM32L1.
+//!
+//! The IWDG counts down from the internal low speed oscillator, which the
+//! hardware starts together with the watchdog. The LSI is only nominally
+//! 37 kHz, so timeouts are approximate. Once started, the watchdog runs until
+//! the next reset.
+//!
+//! The reset flags in RCC_CSR survive every reset but a power-on one.
+//! `Watchdog::new()` latches the watchdog one for `caused_reset()` and, if it
+//! is set, clears them all.
+
+use hal::stm32l1::init::reg::RCC;
+use hal::stm32_common::iwdg;
+use hal::watchdog;
+
+/// Set once the watchdog reset flag was seen.
+#[allow(non_upper_case_globals)]
+static mut WatchdogReset: bool = false;
+
+/// Nominal LSI frequency in Hz.
+const LSI_FREQUENCY: u32 = 37_000;
+
+/// Independent watchdog.
+#[derive(Clone, Copy)]
+pub struct Watchdog;
+
+impl Watchdog {
+  /// Returns the independent watchdog, latching its reset flag.
+  pub fn new() -> Watchdog {
+    latch_reset();
+    Watchdog
+  }
+}
+
+impl watchdog::Watchdog for Watchdog {
+  fn start(&self, timeout_ms: u32) {
+    iwdg::start(&reg::IWDG, LSI_FREQUENCY, timeout_ms);
+  }
+
+  fn feed(&self) {
+    iwdg::feed(&reg::IWDG);
+  }
+
+  fn caused_reset(&self) -> bool {
+    latch_reset();
+    unsafe { WatchdogReset }
+  }
+}
+
+fn latch_reset() {
+  if RCC.csr.independent_watchdog_reset() {
+    unsafe { WatchdogReset = true };
+    RCC.csr.set_remove_reset(true);
+  }
+}
+
+mod reg {
+  use hal::stm32_common::iwdg::reg::IWDG;
+
+  extern {
+    #[link_name="stm32l1_iomem_IWDG"] pub static IWDG: IWDG;
+  }
+}
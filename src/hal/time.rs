@@ -0,0 +1,288 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+Monotonic system time.
+
+`Instant` and `Duration` are 64-bit microsecond counts, they don't overflow in
+the lifetime of a device.
+
+On Cortex-M, the clock is driven by SysTick, which is set to wrap every
+millisecond and owned by this module after `init()`. The application must call
+`systick_tick()` from its `isr_systick` handler:
+
+```ignore
+#[no_mangle]
+pub unsafe extern fn isr_systick() {
+  zinc::hal::time::systick_tick();
+}
+```
+
+`init()` takes the core clock frequency, which comes from the MCU clock
+configuration: `lpc17xx::system_clock::system_clock()`,
+`stm32f4::init::system_clock()`, `ClockConfig::get_ahb_frequency()` on
+stm32f1 and stm32l1, or `tiva_c::sysctl::clock::sysclk_get()`.
+
+`now()` can be called from interrupt handlers.
+*/
+
+use core::ops::{Add, Sub, Mul, Div};
+
+/// Span of time, with microsecond resolution.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Duration {
+  micros: u64,
+}
+
+impl Duration {
+  /// Creates a duration of `secs` seconds.
+  pub fn from_secs(secs: u64) -> Duration {
+    Duration { micros: secs * 1_000_000 }
+  }
+
+  /// Creates a duration of `millis` milliseconds.
+  pub fn from_millis(millis: u64) -> Duration {
+    Duration { micros: millis * 1_000 }
+  }
+
+  /// Creates a duration of `micros` microseconds.
+  pub fn from_micros(micros: u64) -> Duration {
+    Duration { micros: micros }
+  }
+
+  /// Returns the number of whole seconds.
+  pub fn as_secs(&self) -> u64 {
+    self.micros / 1_000_000
+  }
+
+  /// Returns the number of whole milliseconds.
+  pub fn as_millis(&self) -> u64 {
+    self.micros / 1_000
+  }
+
+  /// Returns the number of microseconds.
+  pub fn as_micros(&self) -> u64 {
+    self.micros
+  }
+
+  /// Subtracts `other`, returning `None` if it's longer than `self`.
+  pub fn checked_sub(&self, other: Duration) -> Option<Duration> {
+    self.micros.checked_sub(other.micros).map(Duration::from_micros)
+  }
+}
+
+impl Add for Duration {
+  type Output = Duration;
+
+  fn add(self, other: Duration) -> Duration {
+    Duration { micros: self.micros + other.micros }
+  }
+}
+
+impl Sub for Duration {
+  type Output = Duration;
+
+  fn sub(self, other: Duration) -> Duration {
+    Duration { micros: self.micros - other.micros }
+  }
+}
+
+impl Mul<u32> for Duration {
+  type Output = Duration;
+
+  fn mul(self, n: u32) -> Duration {
+    Duration { micros: self.micros * n as u64 }
+  }
+}
+
+impl Div<u32> for Duration {
+  type Output = Duration;
+
+  fn div(self, n: u32) -> Duration {
+    Duration { micros: self.micros / n as u64 }
+  }
+}
+
+/// Point in time, measured from `init()`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Instant {
+  micros: u64,
+}
+
+impl Instant {
+  /// Creates an instant `micros` microseconds after the clock start.
+  pub fn from_micros(micros: u64) -> Instant {
+    Instant { micros: micros }
+  }
+
+  /// Returns the time elapsed from the clock start.
+  pub fn since_start(&self) -> Duration {
+    Duration::from_micros(self.micros)
+  }
+
+  /// Returns the time elapsed from `earlier`, or zero if `earlier` is later.
+  pub fn duration_since(&self, earlier: Instant) -> Duration {
+    Duration::from_micros(self.micros.saturating_sub(earlier.micros))
+  }
+
+  /// Returns the time elapsed since this instant.
+  #[cfg(any(feature = "cpu_cortex-m0",
+            feature = "cpu_cortex-m3",
+            feature = "cpu_cortex-m4",
+            feature = "cpu_cortex-m7"))]
+  pub fn elapsed(&self) -> Duration {
+    now().duration_since(*self)
+  }
+}
+
+impl Add<Duration> for Instant {
+  type Output = Instant;
+
+  fn add(self, d: Duration) -> Instant {
+    Instant { micros: self.micros + d.micros }
+  }
+}
+
+impl Sub<Duration> for Instant {
+  type Output = Instant;
+
+  fn sub(self, d: Duration) -> Instant {
+    Instant { micros: self.micros - d.micros }
+  }
+}
+
+impl Sub for Instant {
+  type Output = Duration;
+
+  fn sub(self, other: Instant) -> Duration {
+    Duration { micros: self.micros - other.micros }
+  }
+}
+
+/// Converts a cycle count at `clock` Hz into microseconds, without overflowing
+/// for any realistic count.
+fn cycles_to_micros(cycles: u64, clock: u32) -> u64 {
+  let clock = clock as u64;
+  (cycles / clock) * 1_000_000 + (cycles % clock) * 1_000_000 / clock
+}
+
+#[cfg(any(feature = "cpu_cortex-m0",
+          feature = "cpu_cortex-m3",
+          feature = "cpu_cortex-m4",
+          feature = "cpu_cortex-m7"))]
+mod clock {
+  use hal::cortex_common::irq::NoInterrupts;
+  use hal::cortex_common::{scb, systick};
+  use super::{Instant, cycles_to_micros};
+
+  #[allow(non_upper_case_globals)]
+  static mut CoreClock: u32 = 0;
+  #[allow(non_upper_case_globals)]
+  static mut Reload: u32 = 0;
+  #[allow(non_upper_case_globals)]
+  static mut Wraps: u64 = 0;
+
+  /// Starts the clock, `core_clock` being the SysTick input frequency in Hz.
+  pub fn init(core_clock: u32) {
+    let reload = core_clock / 1000 - 1;
+    {
+      let _crit = NoInterrupts::new();
+      unsafe {
+        CoreClock = core_clock;
+        Reload = reload;
+        Wraps = 0;
+      }
+    }
+
+    systick::setup(reload);
+    systick::enable_irq();
+    systick::enable();
+  }
+
+  /// Accounts for a SysTick wrap, must be called from `isr_systick`.
+  pub fn systick_tick() {
+    let _crit = NoInterrupts::new();
+    unsafe { Wraps += 1 };
+  }
+
+  /// Returns the current time.
+  pub fn now() -> Instant {
+    let _crit = NoInterrupts::new();
+    let (wraps, reload, clock) = unsafe { (Wraps, Reload, CoreClock) };
+    if clock == 0 {
+      return Instant::from_micros(0);
+    }
+
+    let mut current = systick::get_current();
+    let mut wraps = wraps;
+    if scb::systick_pending() {
+      // The counter wrapped but the interrupt is masked, the value read before
+      // might predate the wrap.
+      current = systick::get_current();
+      wraps += 1;
+    }
+
+    let cycles = wraps * (reload as u64 + 1) + (reload - current) as u64;
+    Instant::from_micros(cycles_to_micros(cycles, clock))
+  }
+}
+
+#[cfg(any(feature = "cpu_cortex-m0",
+          feature = "cpu_cortex-m3",
+          feature = "cpu_cortex-m4",
+          feature = "cpu_cortex-m7"))]
+pub use self::clock::{init, now, systick_tick};
+
+#[cfg(test)]
+mod test {
+  use hal::time::{Duration, Instant, cycles_to_micros};
+
+  #[test]
+  fn converts_durations() {
+    assert_eq!(Duration::from_secs(3).as_micros(), 3_000_000);
+    assert_eq!(Duration::from_millis(1_500).as_secs(), 1);
+    assert_eq!(Duration::from_micros(2_500).as_millis(), 2);
+  }
+
+  #[test]
+  fn does_duration_arithmetic() {
+    let d = Duration::from_millis(10);
+    assert_eq!(d + d, Duration::from_millis(20));
+    assert_eq!(d * 3 - d, Duration::from_millis(20));
+    assert_eq!(d / 4, Duration::from_micros(2_500));
+    assert_eq!(d.checked_sub(d * 2), None);
+  }
+
+  #[test]
+  fn does_instant_arithmetic() {
+    let start = Instant::from_micros(1_000);
+    let later = start + Duration::from_millis(5);
+    assert_eq!(later - start, Duration::from_millis(5));
+    assert_eq!(later - Duration::from_millis(5), start);
+    assert_eq!(start.duration_since(later), Duration::from_micros(0));
+    assert!(later > start);
+  }
+
+  #[test]
+  fn converts_cycles_without_overflow() {
+    assert_eq!(cycles_to_micros(100_000_000, 100_000_000), 1_000_000);
+    assert_eq!(cycles_to_micros(50, 100_000_000), 0);
+    assert_eq!(cycles_to_micros(65_536, 65_536), 1_000_000);
+    // a year at 100MHz
+    let year = 365 * 24 * 3600 * 100_000_000u64;
+    assert_eq!(cycles_to_micros(year, 100_000_000),
+        365 * 24 * 3600 * 1_000_000);
+  }
+}
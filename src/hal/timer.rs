@@ -19,11 +19,177 @@ Timer interface.
 TimerConf is a MCU-specific struct.
 
 Timers provide a simple way to delay program execution for some time.
+
+Timers implementing `Alarms` can also call a handler from their interrupt once
+the counter reaches a given value. The handler decides what happens next by
+returning an `AlarmAction`, which makes both one-shot and periodic alarms
+possible, as well as rescheduling or cancelling them on the fly.
+
+Timers implementing `Capture` latch the counter on edges of an input pin and
+queue the timestamps, which can be used to measure pulse widths and
+frequencies without polling the pin.
 */
 
+use core::option::Option::{self, Some, None};
+
+use hal::pin::Edge;
+
 #[path="../util/wait_for.rs"]
 #[macro_use] mod wait_for;
 
+/// What an alarm does after its handler returns.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AlarmAction {
+  /// Fire again after the period the alarm was set with, periodic alarms
+  /// only. One-shot alarms are disabled.
+  Continue,
+  /// Fire again the given number of ticks from now.
+  Reschedule(u32),
+  /// Disable the alarm.
+  Cancel,
+}
+
+/// Alarm handler, called from the timer interrupt.
+pub type AlarmHandler = fn() -> AlarmAction;
+
+/// Timer with match interrupts.
+///
+/// Alarm times are given in counter ticks, as returned by `get_counter()`.
+pub trait Alarms: Timer {
+  /// Returns the number of alarms available on this timer.
+  fn alarm_count(&self) -> usize;
+
+  /// Calls `handler` once, `ticks` from now.
+  fn set_alarm(&self, alarm: usize, ticks: u32, handler: AlarmHandler);
+
+  /// Calls `handler` every `period` ticks, starting `period` from now.
+  ///
+  /// The period is kept relative to the previous deadline, so it doesn't
+  /// drift with interrupt latency.
+  fn set_periodic_alarm(&self, alarm: usize, period: u32,
+      handler: AlarmHandler);
+
+  /// Disables the alarm, its handler won't be called anymore.
+  fn cancel_alarm(&self, alarm: usize);
+}
+
+/// Counter value latched on an edge of a capture input.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CaptureEvent {
+  /// Counter value at the time of the edge.
+  pub timestamp: u32,
+  /// Edge that triggered the capture, either `Rising` or `Falling`.
+  pub edge: Edge,
+}
+
+/// Period and high time of a periodic signal, in counter ticks.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PulseMeasurement {
+  /// Time between two edges of the same direction.
+  pub period: u32,
+  /// Time the signal spent high within one period.
+  pub high: u32,
+}
+
+impl PulseMeasurement {
+  /// Computes the measurement from three consecutive edges, the first and last
+  /// one being of the same direction.
+  ///
+  /// Counter wrap-around between the edges is accounted for, as long as the
+  /// period is shorter than a full counter cycle.
+  pub fn from_edges(first: CaptureEvent, second: CaptureEvent,
+      third: CaptureEvent) -> Option<PulseMeasurement> {
+    if first.edge != third.edge || first.edge == second.edge ||
+        first.edge == Edge::Both || second.edge == Edge::Both {
+      return None;
+    }
+
+    let period = third.timestamp.wrapping_sub(first.timestamp);
+    let first_half = second.timestamp.wrapping_sub(first.timestamp);
+    if period == 0 || first_half > period {
+      return None;
+    }
+
+    let high = match first.edge {
+      Edge::Rising => first_half,
+      _            => period - first_half,
+    };
+    Some(PulseMeasurement {
+      period: period,
+      high: high,
+    })
+  }
+
+  /// Time the signal spent low within one period.
+  pub fn low(&self) -> u32 {
+    self.period - self.high
+  }
+
+  /// Signal frequency in Hz, given the counter rate in ticks per second.
+  pub fn frequency(&self, tick_rate: u32) -> u32 {
+    (tick_rate + self.period / 2) / self.period
+  }
+
+  /// Duty cycle in percent.
+  pub fn duty_cycle(&self) -> u32 {
+    ((self.high as u64 * 100 + self.period as u64 / 2) /
+        self.period as u64) as u32
+  }
+}
+
+/// Timer with input capture channels.
+///
+/// Captured edges are queued by the timer interrupt and read back in order.
+pub trait Capture: Timer {
+  /// Returns the number of capture channels available on this timer.
+  fn capture_count(&self) -> usize;
+
+  /// Starts capturing `edge` on the channel, dropping any queued events.
+  fn start_capture(&self, channel: usize, edge: Edge);
+
+  /// Stops capturing on the channel, queued events can still be read.
+  fn stop_capture(&self, channel: usize);
+
+  /// Returns the oldest queued event, if any.
+  fn read_capture(&self, channel: usize) -> Option<CaptureEvent>;
+
+  /// Returns the number of events dropped because the queue was full.
+  fn capture_overruns(&self, channel: usize) -> usize;
+
+  /// Waits for the next event, giving up after `timeout` ticks.
+  fn wait_capture(&self, channel: usize, timeout: u32)
+      -> Option<CaptureEvent> {
+    let start = self.get_counter();
+    loop {
+      match self.read_capture(channel) {
+        Some(event) => return Some(event),
+        None => {},
+      }
+      if self.get_counter().wrapping_sub(start) >= timeout {
+        return None;
+      }
+    }
+  }
+
+  /// Measures one period of the signal on the channel.
+  ///
+  /// `timeout` applies to each of the three edges needed for the measurement.
+  /// The channel is left stopped.
+  fn measure_pulse(&self, channel: usize, timeout: u32)
+      -> Option<PulseMeasurement> {
+    self.start_capture(channel, Edge::Both);
+    let first = self.wait_capture(channel, timeout);
+    let second = self.wait_capture(channel, timeout);
+    let third = self.wait_capture(channel, timeout);
+    self.stop_capture(channel);
+
+    match (first, second, third) {
+      (Some(a), Some(b), Some(c)) => PulseMeasurement::from_edges(a, b, c),
+      _ => None,
+    }
+  }
+}
+
 /// Timer implementation.
 pub trait Timer {
   /// Implementation-specific method to wait a given number of microseconds.
@@ -48,3 +214,53 @@ pub trait Timer {
     self.wait_us(s * 1000000);
   }
 }
+
+#[cfg(test)]
+mod test {
+  use core::option::Option::{Some, None};
+
+  use hal::pin::Edge;
+  use super::{CaptureEvent, PulseMeasurement};
+
+  fn event(timestamp: u32, edge: Edge) -> CaptureEvent {
+    CaptureEvent {
+      timestamp: timestamp,
+      edge: edge,
+    }
+  }
+
+  #[test]
+  fn measures_from_rising_edge() {
+    let m = PulseMeasurement::from_edges(event(100, Edge::Rising),
+        event(130, Edge::Falling), event(200, Edge::Rising)).unwrap();
+    assert_eq!(m.period, 100);
+    assert_eq!(m.high, 30);
+    assert_eq!(m.low(), 70);
+    assert_eq!(m.duty_cycle(), 30);
+    assert_eq!(m.frequency(1000000), 10000);
+  }
+
+  #[test]
+  fn measures_from_falling_edge() {
+    let m = PulseMeasurement::from_edges(event(100, Edge::Falling),
+        event(130, Edge::Rising), event(200, Edge::Falling)).unwrap();
+    assert_eq!(m.period, 100);
+    assert_eq!(m.high, 70);
+  }
+
+  #[test]
+  fn handles_counter_wrap() {
+    let m = PulseMeasurement::from_edges(event(0xffff_fff0, Edge::Rising),
+        event(0x10, Edge::Falling), event(0x30, Edge::Rising)).unwrap();
+    assert_eq!(m.period, 0x40);
+    assert_eq!(m.high, 0x20);
+  }
+
+  #[test]
+  fn rejects_inconsistent_edges() {
+    assert_eq!(PulseMeasurement::from_edges(event(0, Edge::Rising),
+        event(10, Edge::Rising), event(20, Edge::Rising)), None);
+    assert_eq!(PulseMeasurement::from_edges(event(0, Edge::Rising),
+        event(10, Edge::Falling), event(20, Edge::Falling)), None);
+  }
+}
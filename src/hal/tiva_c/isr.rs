@@ -15,18 +15,39 @@
 
 //! ISR data for tiva_c
 
-use core::option::Option::{self, None};
+use core::option::Option::{self, Some, None};
+
+extern {
+  fn isr_gpio_a();
+  fn isr_gpio_b();
+  fn isr_gpio_c();
+  fn isr_gpio_d();
+  fn isr_gpio_e();
+  fn isr_gpio_f();
+  fn isr_timer_0a();
+  fn isr_timer_1a();
+  fn isr_timer_2a();
+  fn isr_timer_3a();
+  fn isr_timer_4a();
+  fn isr_timer_5a();
+  fn isr_timer_w0a();
+  fn isr_timer_w1a();
+  fn isr_timer_w2a();
+  fn isr_timer_w3a();
+  fn isr_timer_w4a();
+  fn isr_timer_w5a();
+}
 
 const ISRCOUNT: usize = 139;
 
 #[link_section=".isr_vector_nvic"]
 #[no_mangle]
 pub static NVIC_VECTOR: [Option<unsafe extern fn()>; ISRCOUNT] = [
-    None,                      // GPIO Port A
-    None,                      // GPIO Port B
-    None,                      // GPIO Port C
-    None,                      // GPIO Port D
-    None,                      // GPIO Port E
+    Some(isr_gpio_a),          // GPIO Port A
+    Some(isr_gpio_b),          // GPIO Port B
+    Some(isr_gpio_c),          // GPIO Port C
+    Some(isr_gpio_d),          // GPIO Port D
+    Some(isr_gpio_e),          // GPIO Port E
     None,                      // UART0 Rx and Tx
     None,                      // UART1 Rx and Tx
     None,                      // SSI0 Rx and Tx
@@ -41,23 +62,23 @@ pub static NVIC_VECTOR: [Option<unsafe extern fn()>; ISRCOUNT] = [
     None,                      // ADC Sequence 2
     None,                      // ADC Sequence 3
     None,                      // Watchdog timer
-    None,                      // Timer 0 subtimer A
+    Some(isr_timer_0a),        // Timer 0 subtimer A
     None,                      // Timer 0 subtimer B
-    None,                      // Timer 1 subtimer A
+    Some(isr_timer_1a),        // Timer 1 subtimer A
     None,                      // Timer 1 subtimer B
-    None,                      // Timer 2 subtimer A
+    Some(isr_timer_2a),        // Timer 2 subtimer A
     None,                      // Timer 2 subtimer B
     None,                      // Analog Comparator 0
     None,                      // Analog Comparator 1
     None,                      // Analog Comparator 2
     None,                      // System Control (PLL, OSC, BO)
     None,                      // FLASH Control
-    None,                      // GPIO Port F
+    Some(isr_gpio_f),          // GPIO Port F
     None,                      // GPIO Port G
     None,                      // GPIO Port H
     None,                      // UART2 Rx and Tx
     None,                      // SSI1 Rx and Tx
-    None,                      // Timer 3 subtimer A
+    Some(isr_timer_3a),        // Timer 3 subtimer A
     None,                      // Timer 3 subtimer B
     None,                      // I2C1 Master and Slave
     None,                      // Quadrature Encoder 1
@@ -92,7 +113,7 @@ pub static NVIC_VECTOR: [Option<unsafe extern fn()>; ISRCOUNT] = [
     None,                      // Reserved
     None,                      // I2C2 Master and Slave
     None,                      // I2C3 Master and Slave
-    None,                      // Timer 4 subtimer A
+    Some(isr_timer_4a),        // Timer 4 subtimer A
     None,                      // Timer 4 subtimer B
     None,                      // Reserved
     None,                      // Reserved
@@ -114,19 +135,19 @@ pub static NVIC_VECTOR: [Option<unsafe extern fn()>; ISRCOUNT] = [
     None,                      // Reserved
     None,                      // Reserved
     None,                      // Reserved
-    None,                      // Timer 5 subtimer A
+    Some(isr_timer_5a),        // Timer 5 subtimer A
     None,                      // Timer 5 subtimer B
-    None,                      // Wide Timer 0 subtimer A
+    Some(isr_timer_w0a),       // Wide Timer 0 subtimer A
     None,                      // Wide Timer 0 subtimer B
-    None,                      // Wide Timer 1 subtimer A
+    Some(isr_timer_w1a),       // Wide Timer 1 subtimer A
     None,                      // Wide Timer 1 subtimer B
-    None,                      // Wide Timer 2 subtimer A
+    Some(isr_timer_w2a),       // Wide Timer 2 subtimer A
     None,                      // Wide Timer 2 subtimer B
-    None,                      // Wide Timer 3 subtimer A
+    Some(isr_timer_w3a),       // Wide Timer 3 subtimer A
     None,                      // Wide Timer 3 subtimer B
-    None,                      // Wide Timer 4 subtimer A
+    Some(isr_timer_w4a),       // Wide Timer 4 subtimer A
     None,                      // Wide Timer 4 subtimer B
-    None,                      // Wide Timer 5 subtimer A
+    Some(isr_timer_w5a),       // Wide Timer 5 subtimer A
     None,                      // Wide Timer 5 subtimer B
     None,                      // FPU
     None,                      // Reserved
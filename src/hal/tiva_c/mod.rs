@@ -21,5 +21,6 @@ pub mod pin;
 pub mod timer;
 pub mod uart;
 pub mod spi;
+pub mod watchdog;
 
 #[path="../../util/ioreg.rs"] mod util;
@@ -16,8 +16,15 @@
 //! Pin configuration
 //! Allows GPIO configuration
 //! Pin muxing not implemented yet.
+//!
+//! Edge interrupts are supported through `InterruptPin`. The application
+//! defines the `isr_gpio_*` handlers it needs and calls `handle_interrupt()`
+//! from them.
 
+use hal::cortex_m4::irq::NoInterrupts;
+use hal::cortex_m4::nvic;
 use hal::pin::{Gpio, GpioDirection, In, Out, GpioLevel, High, Low};
+use hal::pin::{Edge, PinHandler, InterruptPin};
 use hal::tiva_c::sysctl;
 use util::support::get_reg_ref;
 
@@ -34,11 +41,37 @@ pub enum PortId {
   PortF,
 }
 
+impl PortId {
+  fn irq(self) -> usize {
+    match self {
+      PortId::PortA => 0,
+      PortId::PortB => 1,
+      PortId::PortC => 2,
+      PortId::PortD => 3,
+      PortId::PortE => 4,
+      PortId::PortF => 30,
+    }
+  }
+
+  fn regs(self) -> &'static reg::Port {
+    get_reg_ref(match self {
+      PortId::PortA => reg::PORT_A,
+      PortId::PortB => reg::PORT_B,
+      PortId::PortC => reg::PORT_C,
+      PortId::PortD => reg::PORT_D,
+      PortId::PortE => reg::PORT_E,
+      PortId::PortF => reg::PORT_F,
+    })
+  }
+}
+
 /// Structure describing a single HW pin
 #[derive(Clone, Copy)]
 pub struct Pin {
   /// Timer register interface
   regs: &'static reg::Port,
+  /// Port the pin belongs to
+  port: PortId,
   /// Pin index in the port
   index: usize,
 }
@@ -51,18 +84,22 @@ impl Pin {
              function:  u8) -> Pin {
 
     // Retrieve GPIO port peripheral to enable it
-    let (periph, regs) = match pid {
-      PortId::PortA => (sysctl::periph::gpio::PORT_A, reg::PORT_A),
-      PortId::PortB => (sysctl::periph::gpio::PORT_B, reg::PORT_B),
-      PortId::PortC => (sysctl::periph::gpio::PORT_C, reg::PORT_C),
-      PortId::PortD => (sysctl::periph::gpio::PORT_D, reg::PORT_D),
-      PortId::PortE => (sysctl::periph::gpio::PORT_E, reg::PORT_E),
-      PortId::PortF => (sysctl::periph::gpio::PORT_F, reg::PORT_F),
+    let periph = match pid {
+      PortId::PortA => sysctl::periph::gpio::PORT_A,
+      PortId::PortB => sysctl::periph::gpio::PORT_B,
+      PortId::PortC => sysctl::periph::gpio::PORT_C,
+      PortId::PortD => sysctl::periph::gpio::PORT_D,
+      PortId::PortE => sysctl::periph::gpio::PORT_E,
+      PortId::PortF => sysctl::periph::gpio::PORT_F,
     };
 
     periph.ensure_enabled();
 
-    let pin = Pin { regs: get_reg_ref(regs), index: pin_index as usize };
+    let pin = Pin {
+      regs: pid.regs(),
+      port: pid,
+      index: pin_index as usize,
+    };
 
     pin.configure(dir, function);
 
@@ -143,6 +180,66 @@ impl Gpio for Pin {
   }
 }
 
+impl InterruptPin for Pin {
+  /// Registers the handler and enables an edge interrupt on the pin.
+  fn set_interrupt(&self, edge: Edge, handler: PinHandler) {
+    {
+      let _crit = NoInterrupts::new();
+      unsafe {
+        PinHandlers[self.port as usize][self.index] = Some(handler);
+      }
+    }
+
+    // Mask the pin while the trigger is reconfigured, changing the sense
+    // registers can raise a spurious interrupt.
+    self.disable_interrupt();
+    self.regs.is.set_is(self.index, reg::Port_is_is::EDGE);
+    match edge {
+      Edge::Both => self.regs.ibe.set_ibe(self.index, true),
+      Edge::Rising | Edge::Falling => {
+        self.regs.ibe.set_ibe(self.index, false);
+        self.regs.iev.set_iev(self.index, edge == Edge::Rising);
+      },
+    }
+    self.clear_interrupt();
+    self.enable_interrupt();
+    nvic::enable_irq(self.port.irq());
+  }
+
+  fn enable_interrupt(&self) {
+    self.regs.im.set_im(self.index, true);
+  }
+
+  fn disable_interrupt(&self) {
+    self.regs.im.set_im(self.index, false);
+  }
+
+  fn clear_interrupt(&self) {
+    self.regs.icr.clear_ic(self.index);
+  }
+}
+
+#[allow(non_upper_case_globals)]
+static mut PinHandlers: [[Option<PinHandler>; 8]; 6] = [[None; 8]; 6];
+
+/// Dispatches the pending pin interrupts of `port`.
+///
+/// Unsafe because it reads the handlers without a critical section, it must
+/// only be called from the interrupt handler of the port.
+pub unsafe fn handle_interrupt(port: PortId) {
+  let regs = port.regs();
+
+  for index in 0..8 {
+    if regs.mis.mis(index) {
+      regs.icr.clear_ic(index);
+      match PinHandlers[port as usize][index] {
+        Some(handler) => handler(),
+        None => {},
+      }
+    }
+  }
+}
+
 pub mod reg {
   //! Pin registers definition
   use volatile_cell::VolatileCell;
@@ -162,6 +259,44 @@ pub mod reg {
       }
     }
 
+    0x404 => reg32 is {
+      //! Interrupt sense
+      0..7 => is[8] {
+        0 => EDGE,
+        1 => LEVEL,
+      }
+    }
+
+    0x408 => reg32 ibe {
+      //! Interrupt on both edges
+      0..7 => ibe[8]
+    }
+
+    0x40C => reg32 iev {
+      //! Interrupt event, set for rising edge or high level
+      0..7 => iev[8]
+    }
+
+    0x410 => reg32 im {
+      //! Interrupt mask, set to enable
+      0..7 => im[8]
+    }
+
+    0x414 => reg32 ris {
+      //! Raw interrupt status
+      0..7 => ris[8]: ro
+    }
+
+    0x418 => reg32 mis {
+      //! Masked interrupt status
+      0..7 => mis[8]: ro
+    }
+
+    0x41C => reg32 icr {
+      //! Interrupt clear
+      0..7 => ic[8]: set_to_clear
+    }
+
     0x420 => reg32 afsel {
       //! Pin alternate function
       0..7 => afsel[8] {
@@ -23,6 +23,7 @@ mod clock_pt;
 mod pin_pt;
 mod timer_pt;
 mod uart_pt;
+mod watchdog_pt;
 
 pub fn attach(builder: &mut Builder, cx: &mut ExtCtxt, node: Rc<node::Node>) {
   node.materializer.set(Some(verify as fn(&mut Builder, &mut ExtCtxt, Rc<node::Node>)));
@@ -34,6 +35,7 @@ pub fn attach(builder: &mut Builder, cx: &mut ExtCtxt, node: Rc<node::Node>) {
       "gpio"  => pin_pt  ::attach(builder, cx, sub.clone()),
       "timer" => timer_pt::attach(builder, cx, sub.clone()),
       "uart"  => uart_pt ::attach(builder, cx, sub.clone()),
+      "watchdog" => watchdog_pt::attach(builder, cx, sub.clone()),
       _       => (),
     }
   }
@@ -41,7 +43,7 @@ pub fn attach(builder: &mut Builder, cx: &mut ExtCtxt, node: Rc<node::Node>) {
 
 fn verify(_: &mut Builder, cx: &mut ExtCtxt, node: Rc<node::Node>) {
   node.expect_no_attributes(cx);
-  node.expect_subnodes(cx, &["clock", "gpio", "timer", "uart"]);
+  node.expect_subnodes(cx, &["clock", "gpio", "timer", "uart", "watchdog"]);
 }
 
 pub fn add_node_dependency_on_clock(builder: &mut Builder,
@@ -27,6 +27,9 @@ use util::support::get_reg_ref;
 #[path="../../util/wait_for.rs"]
 #[macro_use] mod wait_for;
 
+/// Depth of the SSI transmit and receive FIFOs.
+const FIFO_DEPTH: usize = 8;
+
 /// There are 4 SSI instances an SPI interface can use
 /// See the TM4C123GH6PM datasheet page 954 for detailed signal to pin mappings
 #[allow(missing_docs)]
@@ -190,6 +193,13 @@ impl Spi {
     self.regs.ssicr1.set_sse(true);
   }
 
+  /// Set SPI mode, clock polarity is bit 1 and clock phase is bit 0
+  fn set_mode(&self, mode: u8) {
+    self.regs.ssicr0
+      .set_sph(mode & 0x1 != 0)
+      .set_spo(mode & 0x2 != 0);
+  }
+
   /// Set SPI frequency
   ///
   /// This function computes the divisor and exponent (for lack of a better name).
@@ -240,6 +250,46 @@ impl Spi {
   }
 }
 
+impl ::hal::spi::Frames for Spi {
+  /// The receive FIFO holds `FIFO_DEPTH` frames, so it never overflows.
+  fn frames_in_flight(&self) -> usize {
+    FIFO_DEPTH
+  }
+
+  fn tx_ready(&self) -> bool {
+    self.regs.ssisr.tnf()
+  }
+
+  fn rx_ready(&self) -> bool {
+    self.regs.ssisr.rne()
+  }
+
+  fn busy(&self) -> bool {
+    self.regs.ssisr.bsy()
+  }
+
+  fn write_frame(&self, value: u8) {
+    self.regs.ssidr.set_data(value as u16);
+  }
+
+  fn read_frame(&self) -> u8 {
+    self.regs.ssidr.data() as u8
+  }
+}
+
+impl ::hal::spi::Configure for Spi {
+  type Settings = ::hal::spi::Settings;
+
+  fn configure(&self, settings: ::hal::spi::Settings) {
+    wait_for!(!self.regs.ssisr.bsy());
+
+    self.regs.ssicr1.set_sse(false);
+    self.set_frequency(settings.frequency);
+    self.set_mode(settings.mode);
+    self.regs.ssicr1.set_sse(true);
+  }
+}
+
 impl ::hal::spi::Spi for Spi {
   fn write(&self, value: u8) {
     wait_for!(self.writeable());
@@ -252,6 +302,22 @@ impl ::hal::spi::Spi for Spi {
 
     self.regs.ssidr.data() as u8
   }
+
+  fn flush(&self) {
+    wait_for!(!self.regs.ssisr.bsy());
+  }
+
+  fn write_all(&self, data: &[u8]) {
+    ::hal::spi::write_frames(self, data);
+  }
+
+  fn transfer_in_place(&self, buf: &mut [u8]) {
+    ::hal::spi::transfer_frames(self, buf);
+  }
+
+  fn read_into(&self, buf: &mut [u8], fill: u8) {
+    ::hal::spi::read_frames(self, buf, fill);
+  }
 }
 
 #[allow(missing_docs)]
@@ -417,6 +417,17 @@ pub mod periph {
       super::PeripheralClock { class: CLASS, id: 7 };
   }
 
+  #[allow(missing_docs)]
+  pub mod watchdog {
+    //! Watchdog timer peripherals instances
+    const CLASS: u8 = 0x0 / 4;
+
+    pub const WDT_0: super::PeripheralClock =
+      super::PeripheralClock { class: CLASS, id: 0 };
+    pub const WDT_1: super::PeripheralClock =
+      super::PeripheralClock { class: CLASS, id: 1 };
+  }
+
   #[allow(missing_docs)]
   pub mod ssi {
     //! SSI peripherals instances
@@ -448,6 +459,15 @@ pub mod reg {
       10 => vddaris:    ro,  //= VDDA Power OK event raw interrupt status
       11 => bor0ris:    ro,  //= VDD under BOR0 raw interrupt status
     }
+    0x05c => reg32 resc {
+      0  => ext,             //= External reset
+      1  => por,             //= Power-on reset
+      2  => bor,             //= Brown-out reset
+      3  => wdt0,            //= Watchdog timer 0 reset
+      4  => sw,              //= Software reset
+      5  => wdt1,            //= Watchdog timer 1 reset
+      16 => moscfail,        //= Main oscillator failure reset
+    }
     0x060 => reg32 rcc {
       0      => mosdis,      //= Main oscillator disable
       4..5   => oscsrc,      //= Oscillator source
@@ -15,9 +15,18 @@
 
 //! Timer configuration
 //! This code should support both standand and wide timers
+//!
+//! Each timer provides one alarm on the timer A match register. Its handler is
+//! called from `handle_interrupt()`, which the application calls from the
+//! timer A interrupt handler (`isr_timer_0a` to `isr_timer_w5a`).
 
+use core::intrinsics::abort;
+
+use hal::cortex_m4::irq::NoInterrupts;
+use hal::cortex_m4::nvic;
 use hal::tiva_c::sysctl;
 use hal::timer;
+use hal::timer::{AlarmAction, AlarmHandler};
 use util::support::get_reg_ref;
 
 /// There are 6 standard 16/32bit timers and 6 "wide" 32/64bit timers
@@ -58,9 +67,52 @@ pub enum Mode {
   PWM,
 }
 
+impl TimerId {
+  fn regs(self) -> &'static reg::Timer {
+    get_reg_ref(match self {
+      TimerId::Timer0  => reg::TIMER_0,
+      TimerId::Timer1  => reg::TIMER_1,
+      TimerId::Timer2  => reg::TIMER_2,
+      TimerId::Timer3  => reg::TIMER_3,
+      TimerId::Timer4  => reg::TIMER_4,
+      TimerId::Timer5  => reg::TIMER_5,
+      TimerId::TimerW0 => reg::TIMER_W_0,
+      TimerId::TimerW1 => reg::TIMER_W_1,
+      TimerId::TimerW2 => reg::TIMER_W_2,
+      TimerId::TimerW3 => reg::TIMER_W_3,
+      TimerId::TimerW4 => reg::TIMER_W_4,
+      TimerId::TimerW5 => reg::TIMER_W_5,
+    })
+  }
+
+  fn is_wide(self) -> bool {
+    self as usize >= TimerId::TimerW0 as usize
+  }
+
+  /// Timer A interrupt number
+  fn irq(self) -> usize {
+    match self {
+      TimerId::Timer0  => 19,
+      TimerId::Timer1  => 21,
+      TimerId::Timer2  => 23,
+      TimerId::Timer3  => 35,
+      TimerId::Timer4  => 70,
+      TimerId::Timer5  => 92,
+      TimerId::TimerW0 => 94,
+      TimerId::TimerW1 => 96,
+      TimerId::TimerW2 => 98,
+      TimerId::TimerW3 => 100,
+      TimerId::TimerW4 => 102,
+      TimerId::TimerW5 => 104,
+    }
+  }
+}
+
 /// Structure describing a single timer counter (both 16/32bit and 32/64bit)
 #[derive(Clone, Copy)]
 pub struct Timer {
+  /// Timer instance
+  id      : TimerId,
   /// Timer register interface
   regs    : &'static reg::Timer,
   /// True if the counter is wide 32/64bit
@@ -103,7 +155,7 @@ impl Timer {
 
     periph.ensure_enabled();
 
-    let timer = Timer { regs: get_reg_ref(regs), wide: wide, mode: mode};
+    let timer = Timer { id: id, regs: get_reg_ref(regs), wide: wide, mode: mode};
 
     timer.configure(prescale);
 
@@ -144,6 +196,30 @@ impl Timer {
     // Timer is now configured, we can enable it
     self.regs.ctl.set_taen(true);
   }
+
+  fn arm(&self, alarm: usize, ticks: u32, handler: AlarmHandler,
+         period: Option<u32>) {
+    let prescale = self.regs.apr.psr();
+    if alarm >= ALARM_COUNT ||
+        ticks as u64 > counter_period(self.wide, prescale) {
+      unsafe { abort() };
+    }
+
+    let _crit = NoInterrupts::new();
+    unsafe {
+      Alarms[self.id as usize] = Alarm {
+        handler: Some(handler),
+        period: period,
+        rearmed: true,
+      };
+    }
+    set_match(self.id,
+        counter_after(get_value(self.id), ticks, self.wide, prescale));
+    self.regs.icr.clear_tamcint();
+    self.regs.amr.set_mie(true);
+    self.regs.imr.set_tamim(true);
+    nvic::enable_irq(self.id.irq());
+  }
 }
 
 impl timer::Timer for Timer {
@@ -156,6 +232,204 @@ impl timer::Timer for Timer {
   }
 }
 
+impl timer::Alarms for Timer {
+  fn alarm_count(&self) -> usize {
+    ALARM_COUNT
+  }
+
+  /// `ticks` are timer clock cycles and must fit in one counter period,
+  /// 2^16 (2^32 on wide timers) times the prescaler division.
+  fn set_alarm(&self, alarm: usize, ticks: u32, handler: AlarmHandler) {
+    self.arm(alarm, ticks, handler, None);
+  }
+
+  fn set_periodic_alarm(&self, alarm: usize, period: u32,
+                        handler: AlarmHandler) {
+    self.arm(alarm, period, handler, Some(period));
+  }
+
+  fn cancel_alarm(&self, alarm: usize) {
+    if alarm >= ALARM_COUNT {
+      unsafe { abort() };
+    }
+
+    let _crit = NoInterrupts::new();
+    disable_match(self.id);
+    unsafe { Alarms[self.id as usize].handler = None };
+  }
+}
+
+const ALARM_COUNT: usize = 1;
+
+#[derive(Clone, Copy)]
+struct Alarm {
+  handler: Option<AlarmHandler>,
+  period: Option<u32>,
+  /// Set when the alarm is armed, so that the ISR can tell if the handler
+  /// re-armed it.
+  rearmed: bool,
+}
+
+const ALARM_INIT: Alarm = Alarm {
+  handler: None,
+  period: None,
+  rearmed: false,
+};
+
+#[allow(non_upper_case_globals)]
+static mut Alarms: [Alarm; 12] = [ALARM_INIT; 12];
+
+/// Returns the mask of the counter value. In half-width mode standard timers
+/// have a 16 bit counter, wide timers a 32 bit one.
+fn counter_mask(wide: bool) -> u32 {
+  if wide { 0xffff_ffff } else { 0xffff }
+}
+
+/// Returns the number of timer clock ticks in one period of the counter. The
+/// prescaler divides the clock by `prescale + 1` in front of the counter.
+fn counter_period(wide: bool, prescale: u32) -> u64 {
+  (counter_mask(wide) as u64 + 1) * (prescale as u64 + 1)
+}
+
+/// Returns the `(counter, prescaler)` value `ticks` after `value`.
+///
+/// Both count down, the prescaler from `prescale` to 0 before each counter
+/// decrement, so the deadline is below the current value in tick space,
+/// wrapping at the counter period.
+fn counter_after(value: (u32, u32), ticks: u32, wide: bool, prescale: u32)
+    -> (u32, u32) {
+  let divisor = prescale as u64 + 1;
+  let period = counter_period(wide, prescale);
+  let (count, ps) = value;
+  let now = (count & counter_mask(wide)) as u64 * divisor + ps as u64;
+  let deadline = (now + period - ticks as u64 % period) % period;
+  ((deadline / divisor) as u32, (deadline % divisor) as u32)
+}
+
+/// Returns the current counter and prescaler values.
+fn get_value(id: TimerId) -> (u32, u32) {
+  let regs = id.regs();
+  if id.is_wide() {
+    // The prescaler has its own register, retry if the counter moved between
+    // the reads
+    loop {
+      let count = regs.tav.v();
+      let ps = regs.tapv.v();
+      if regs.tav.v() == count {
+        return (count, ps);
+      }
+    }
+  } else {
+    // Bits 23:16 hold the prescaler in down-count mode
+    let value = regs.tav.v();
+    (value & 0xffff, (value >> 16) & 0xff)
+  }
+}
+
+/// Returns the current match value, in the same format as `get_value()`
+fn get_match(id: TimerId) -> (u32, u32) {
+  let regs = id.regs();
+  (regs.tamatchr.v() & counter_mask(id.is_wide()), regs.tapmr.v())
+}
+
+fn set_match(id: TimerId, value: (u32, u32)) {
+  let regs = id.regs();
+  regs.tamatchr.set_v(value.0);
+  regs.tapmr.set_v(value.1);
+}
+
+fn disable_match(id: TimerId) {
+  let regs = id.regs();
+  regs.imr.set_tamim(false);
+  regs.amr.set_mie(false);
+}
+
+/// Services the alarm of timer `id`.
+///
+/// Unsafe because it accesses the alarm state without a critical section, it
+/// must only be called from the timer A interrupt handler.
+pub unsafe fn handle_interrupt(id: TimerId) {
+  let regs = id.regs();
+  if !regs.mis.tammis() {
+    return;
+  }
+  regs.icr.clear_tamcint();
+
+  let state = &mut Alarms[id as usize];
+  let handler = match state.handler {
+    Some(handler) => handler,
+    None => {
+      disable_match(id);
+      return;
+    },
+  };
+  state.rearmed = false;
+  let action = handler();
+
+  // The handler might have re-armed or cancelled the alarm itself
+  if state.rearmed || state.handler.is_none() {
+    return;
+  }
+  let prescale = regs.apr.psr();
+  match (action, state.period) {
+    (AlarmAction::Continue, Some(period)) => set_match(id,
+        counter_after(get_match(id), period, id.is_wide(), prescale)),
+    (AlarmAction::Reschedule(ticks), _) => set_match(id,
+        counter_after(get_value(id), ticks, id.is_wide(), prescale)),
+    _ => {
+      disable_match(id);
+      state.handler = None;
+    },
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{counter_after, counter_period};
+
+  #[test]
+  fn counts_down_to_deadline() {
+    assert_eq!(counter_after((0x1000, 0), 0x100, false, 0), (0x0f00, 0));
+    assert_eq!(counter_after((0x1000, 0), 0x100, true, 0), (0x0f00, 0));
+  }
+
+  #[test]
+  fn wraps_at_standard_counter_width() {
+    assert_eq!(counter_after((0x10, 0), 0x20, false, 0), (0xfff0, 0));
+    assert_eq!(counter_after((0, 0), 1, false, 0), (0xffff, 0));
+  }
+
+  #[test]
+  fn wraps_at_wide_counter_width() {
+    assert_eq!(counter_after((0x10, 0), 0x20, true, 0), (0xffff_fff0, 0));
+  }
+
+  #[test]
+  fn ignores_bits_above_standard_counter() {
+    assert_eq!(counter_after((0xff00_1000, 0), 0x100, false, 0), (0x0f00, 0));
+  }
+
+  #[test]
+  fn counts_prescaled_ticks() {
+    // Dividing by 4, (0x10, 2) is 0x42 ticks and 0x22 is (8, 2)
+    assert_eq!(counter_after((0x10, 2), 0x20, false, 3), (8, 2));
+    assert_eq!(counter_after((0x10, 2), 3, false, 3), (0xf, 3));
+  }
+
+  #[test]
+  fn wraps_prescaled_ticks() {
+    assert_eq!(counter_after((0, 1), 3, false, 3), (0xffff, 2));
+    assert_eq!(counter_after((1, 0), 0x4_0000, false, 3), (1, 0));
+  }
+
+  #[test]
+  fn scales_counter_period() {
+    assert_eq!(counter_period(false, 0), 0x1_0000);
+    assert_eq!(counter_period(false, 0xff), 0x100_0000);
+    assert_eq!(counter_period(true, 1), 0x2_0000_0000);
+  }
+}
+
 pub mod reg {
   //! Timer registers definition
   use volatile_cell::VolatileCell;
@@ -209,9 +483,27 @@ pub mod reg {
       13     => tbote,     //= Timer B output trigger enable
       14     => tbpwml,    //= Timer B PWM output level
     }
+    0x18 => reg32 imr {
+      0      => tatoim,    //= Timer A time-out interrupt mask
+      4      => tamim,     //= Timer A match interrupt mask
+    }
+    0x20 => reg32 mis {
+      0      => tatomis: ro, //= Timer A time-out masked interrupt status
+      4      => tammis: ro,  //= Timer A match masked interrupt status
+    }
+    0x24 => reg32 icr {
+      0      => tatocint: set_to_clear, //= Timer A time-out interrupt clear
+      4      => tamcint: set_to_clear,  //= Timer A match interrupt clear
+    }
     0x28 => reg32 tailr {
       0..31 => tailr,      //= Timer A interval load
     }
+    0x30 => reg32 tamatchr {
+      0..31 => v,          //= Timer A match value
+    }
+    0x40 => reg32 tapmr {
+      0..15 => v,          //= Timer A prescale match
+    }
     0x38 => reg32 apr {
       0..15 => psr,        //= Timer A prescale value
                            //= Only 8bit for 16/32bit timers
@@ -219,6 +511,9 @@ pub mod reg {
     0x50 => reg32 tav {
       0..31 => v,          // Timer A counter value
     }
+    0x64 => reg32 tapv {
+      0..15 => v: ro,      //= Timer A prescaler value
+    }
   });
 
   #[allow(missing_docs)]
@@ -15,10 +15,12 @@
 
 //! UART configuration
 
+use core::option::Option::{self, Some, None};
+
 use hal::tiva_c::sysctl;
 use util::support::get_reg_ref;
 
-use drivers::chario::CharIO;
+use drivers::chario::{CharIO, CharInput};
 use hal::uart;
 
 #[path="../../util/ioreg.rs"]
@@ -91,8 +93,8 @@ impl Uart {
       .set_uarten(false)
       // Enable TX
       .set_txe(true)
-      // Enable TX
-      .set_rxe(false)
+      // Enable RX
+      .set_rxe(true)
       // Disable High-Speed
       .set_hse(false);
 
@@ -136,6 +138,16 @@ impl CharIO for Uart {
   }
 }
 
+impl CharInput for Uart {
+  fn try_getc(&self) -> Option<char> {
+    if self.regs.fr.rxfe() {
+      None
+    } else {
+      Some(self.regs.data.data() as u8 as char)
+    }
+  }
+}
+
 pub mod reg {
   //! Uart registers definition
   use volatile_cell::VolatileCell;
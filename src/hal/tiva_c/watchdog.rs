@@ -0,0 +1,122 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Watchdog timer
+//!
+//! Watchdog 0 is clocked from the system clock. It raises its interrupt on a
+//! first timeout and resets the MCU on the second one, so it is loaded with
+//! half of the requested timeout, computed from the system clock frequency
+//! when the watchdog is started.
+
+use hal::tiva_c::sysctl;
+use hal::watchdog;
+use util::support::get_reg_ref;
+
+/// Writing this key to the lock register allows writes to the other registers
+const UNLOCK_KEY: u32 = 0x1ACCE551;
+
+/// Set once the watchdog 0 reset flag was seen.
+#[allow(non_upper_case_globals)]
+static mut WatchdogReset: bool = false;
+
+/// Watchdog timer 0
+#[derive(Clone, Copy)]
+pub struct Watchdog;
+
+impl Watchdog {
+  /// Returns watchdog timer 0
+  ///
+  /// The reset cause flag survives every reset but a power-on one, so it is
+  /// latched here and cleared.
+  pub fn new() -> Watchdog {
+    latch_reset();
+    Watchdog
+  }
+
+  fn regs(&self) -> &'static reg::Wdt {
+    get_reg_ref(reg::WDT_0)
+  }
+}
+
+impl watchdog::Watchdog for Watchdog {
+  fn start(&self, timeout_ms: u32) {
+    sysctl::periph::watchdog::WDT_0.ensure_enabled();
+
+    let clock = sysctl::clock::sysclk_get() as u64;
+    let load = clock * timeout_ms as u64 / 1000 / 2;
+    let wdt = self.regs();
+
+    wdt.lock.set_key(UNLOCK_KEY);
+    wdt.load.set_value(if load > 0xffff_ffff { 0xffff_ffff } else { load as u32 });
+    // Reset has to be enabled first, enabling the interrupt starts the counter
+    wdt.ctl.set_resen(true);
+    wdt.ctl.set_inten(true);
+    wdt.lock.set_key(0);
+  }
+
+  fn feed(&self) {
+    let wdt = self.regs();
+
+    // Clearing the interrupt reloads the counter
+    wdt.lock.set_key(UNLOCK_KEY);
+    wdt.icr.set_clear(1);
+    wdt.lock.set_key(0);
+  }
+
+  fn caused_reset(&self) -> bool {
+    latch_reset();
+    unsafe { WatchdogReset }
+  }
+}
+
+fn latch_reset() {
+  let sysctl = get_reg_ref(sysctl::reg::SYSCTL);
+  if sysctl.resc.wdt0() {
+    unsafe { WatchdogReset = true };
+    sysctl.resc.set_wdt0(false);
+  }
+}
+
+pub mod reg {
+  //! Watchdog registers definition
+  use volatile_cell::VolatileCell;
+  use core::ops::Drop;
+
+  ioregs!(Wdt = {
+    0x000 => reg32 load {
+      0..31 => value,        //= Load value
+    }
+    0x004 => reg32 value {
+      0..31 => value: ro,    //= Current count
+    }
+    0x008 => reg32 ctl {
+      0  => inten,           //= Interrupt enable, starts the counter
+      1  => resen,           //= Reset enable
+      31 => wrc: ro,         //= Write complete, watchdog 1 only
+    }
+    0x00c => reg32 icr {
+      0..31 => clear: wo,    //= Clear interrupt and reload the counter
+    }
+    0x010 => reg32 ris {
+      0 => wdtris: ro,       //= Raw interrupt status
+    }
+    0xc00 => reg32 lock {
+      0..31 => key,          //= Reads 1 when locked
+    }
+  });
+
+  #[allow(missing_docs)]
+  pub const WDT_0: *const Wdt = 0x40000000 as *const Wdt;
+}
@@ -0,0 +1,71 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::rc::Rc;
+use syntax::ext::base::ExtCtxt;
+
+use builder::{Builder, TokenString};
+use node;
+
+pub fn attach(builder: &mut Builder, _: &mut ExtCtxt, node: Rc<node::Node>) {
+  node.materializer.set(Some(build_watchdog as fn(&mut Builder, &mut ExtCtxt, Rc<node::Node>)));
+  super::add_node_dependency_on_clock(builder, &node);
+}
+
+fn build_watchdog(builder: &mut Builder, cx: &mut ExtCtxt,
+    node: Rc<node::Node>) {
+  if !node.expect_attributes(cx, &[("timeout_ms", node::IntAttribute)]) {
+    return
+  }
+
+  let name = TokenString(node.name.clone().unwrap_or("watchdog".to_string()));
+  let timeout_ms: u32 = node.get_int_attr("timeout_ms").unwrap() as u32;
+
+  node.set_type_name("zinc::hal::tiva_c::watchdog::Watchdog".to_string());
+
+  let st = quote_stmt!(&*cx,
+      let $name = zinc::hal::tiva_c::watchdog::Watchdog::new();
+  ).unwrap();
+  builder.add_main_statement(st);
+
+  let st_start = quote_stmt!(&*cx,
+      zinc::hal::watchdog::Watchdog::start(&$name, $timeout_ms);
+  ).unwrap();
+  builder.add_main_statement(st_start);
+}
+
+#[cfg(test)]
+mod test {
+  use builder::Builder;
+  use test_helpers::{assert_equal_source, with_parsed};
+
+  #[test]
+  fn builds_watchdog() {
+    with_parsed("
+      watchdog {
+        timeout_ms = 500;
+      }", |cx, failed, pt| {
+      let mut builder = Builder::new(pt.clone(), cx);
+      super::build_watchdog(&mut builder, cx, pt.get_by_path("watchdog").unwrap());
+      assert!(unsafe{*failed} == false);
+      assert!(builder.main_stmts().len() == 2);
+
+      assert_equal_source(&builder.main_stmts()[0],
+          "let watchdog = zinc::hal::tiva_c::watchdog::Watchdog::new();");
+      assert_equal_source(&builder.main_stmts()[1],
+          "zinc::hal::watchdog::Watchdog::start(&watchdog, 500u32);");
+    });
+  }
+}
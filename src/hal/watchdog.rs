@@ -0,0 +1,70 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Common definitions for watchdog timers.
+
+/// A watchdog timer resets the MCU unless it is fed before its timeout.
+pub trait Watchdog {
+  /// Starts the watchdog with the given timeout in milliseconds.
+  ///
+  /// The timeout is rounded to what the hardware supports. On most MCUs the
+  /// watchdog cannot be stopped again until the next reset.
+  fn start(&self, timeout_ms: u32);
+
+  /// Feeds the watchdog, restarting its timeout.
+  fn feed(&self);
+
+  /// Returns true if the last reset was caused by the watchdog.
+  fn caused_reset(&self) -> bool;
+}
+
+/// Splits a timeout into a power of two prescaler and a reload count.
+///
+/// Picks the smallest shift between `min_shift` and `max_shift` for which
+/// `timeout_ms` of a `clock_hz` counter fits in `max_reload` counts, and
+/// returns it with the count. Timeouts that are too long are clamped to the
+/// longest one supported.
+pub fn prescale(clock_hz: u32, timeout_ms: u32, min_shift: u8, max_shift: u8,
+    max_reload: u32) -> (u8, u32) {
+  let ticks = clock_hz as u64 * timeout_ms as u64 / 1000;
+  for shift in min_shift..max_shift + 1 {
+    let count = ticks >> shift;
+    if count <= max_reload as u64 {
+      return (shift, if count == 0 { 1 } else { count as u32 });
+    }
+  }
+  (max_shift, max_reload)
+}
+
+#[cfg(test)]
+mod test {
+  use super::prescale;
+
+  #[test]
+  fn picks_smallest_prescaler() {
+    // 1 s of 40 kHz is 40000 ticks, 2500 counts after dividing by 16.
+    assert_eq!(prescale(40_000, 1000, 2, 8, 0xfff), (4, 2500));
+  }
+
+  #[test]
+  fn clamps_long_timeouts() {
+    assert_eq!(prescale(40_000, 60_000, 2, 8, 0xfff), (8, 0xfff));
+  }
+
+  #[test]
+  fn never_returns_zero_count() {
+    assert_eq!(prescale(40_000, 0, 2, 8, 0xfff), (2, 1));
+  }
+}
@@ -26,3 +26,4 @@ pub mod syscall;
 pub mod mutex;
 pub mod cond_var;
 pub mod debug;
+pub mod spi_bus;
@@ -0,0 +1,259 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/*!
+Shared SPI bus.
+
+`SpiBus` wraps one SPI peripheral and hands out `SpiDevice` handles, each with
+its own chip-select pin and transfer settings. Talking to a device starts with
+`SpiDevice::select()`, which locks the bus, applies the device settings if
+another device used different ones and asserts CS. The returned `Transaction`
+implements `Spi`; when dropped it waits for the last byte to be shifted out and
+then releases CS and the bus.
+
+The bus is guarded by `os::mutex::Mutex`, so tasks wait for each other under
+multitasking. In the single-task build selecting a device while another
+transaction is in progress aborts.
+*/
+
+use core::cell::Cell;
+use core::ops::Drop;
+use core::option::Option::{self, None, Some};
+
+use hal::pin::Gpio;
+use hal::spi::{Configure, Spi};
+use os::mutex::{Mutex, Guard};
+
+/// SPI bus shared by several devices.
+pub struct SpiBus<S: Configure> {
+  spi: S,
+  lock: Mutex,
+  current: Cell<Option<S::Settings>>,
+}
+
+impl<S: Configure> SpiBus<S> {
+  /// Create a bus on top of a configured SPI peripheral.
+  pub fn new(spi: S) -> SpiBus<S> {
+    SpiBus {
+      spi: spi,
+      lock: Mutex::new(),
+      current: Cell::new(None),
+    }
+  }
+
+  /// Create a handle for the device selected by `cs` (active low).
+  pub fn device<'a, P: Gpio>(&'a self, cs: P, settings: S::Settings)
+      -> SpiDevice<'a, S, P> {
+    cs.set_high();
+    SpiDevice {
+      bus: self,
+      cs: cs,
+      settings: settings,
+    }
+  }
+}
+
+/// Device attached to a `SpiBus`.
+pub struct SpiDevice<'a, S: 'a + Configure, P> {
+  bus: &'a SpiBus<S>,
+  cs: P,
+  settings: S::Settings,
+}
+
+impl<'a, S: Configure, P: Gpio> SpiDevice<'a, S, P> {
+  /// Locks the bus and selects the device.
+  pub fn select<'b>(&'b self) -> Transaction<'b, S, P> {
+    let guard = self.bus.lock.lock();
+
+    if self.bus.current.get() != Some(self.settings) {
+      self.bus.spi.configure(self.settings);
+      self.bus.current.set(Some(self.settings));
+    }
+    self.cs.set_low();
+
+    Transaction {
+      spi: &self.bus.spi,
+      cs: &self.cs,
+      _guard: guard,
+    }
+  }
+
+  /// Changes the settings used by subsequent transactions.
+  pub fn set_settings(&mut self, settings: S::Settings) {
+    self.settings = settings;
+  }
+}
+
+/// Transaction with a selected device, see `SpiDevice::select()`.
+#[must_use]
+pub struct Transaction<'b, S: 'b + Spi, P: 'b + Gpio> {
+  spi: &'b S,
+  cs: &'b P,
+  _guard: Guard<'b>,
+}
+
+impl<'b, S: Spi, P: Gpio> Spi for Transaction<'b, S, P> {
+  fn write(&self, value: u8) {
+    self.spi.write(value)
+  }
+
+  fn read(&self) -> u8 {
+    self.spi.read()
+  }
+
+  fn transfer(&self, value: u8) -> u8 {
+    self.spi.transfer(value)
+  }
+
+  fn flush(&self) {
+    self.spi.flush()
+  }
+
+  fn write_all(&self, data: &[u8]) {
+    self.spi.write_all(data)
+  }
+
+  fn transfer_in_place(&self, buf: &mut [u8]) {
+    self.spi.transfer_in_place(buf)
+  }
+
+  fn read_into(&self, buf: &mut [u8], fill: u8) {
+    self.spi.read_into(buf, fill)
+  }
+}
+
+impl<'b, S: Spi, P: Gpio> Drop for Transaction<'b, S, P> {
+  fn drop(&mut self) {
+    // the bus guard is released after this
+    self.spi.flush();
+    self.cs.set_high();
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use core::cell::RefCell;
+  use std::vec::Vec;
+
+  use hal::pin::{Gpio, GpioDirection, GpioLevel};
+  use hal::spi::{Configure, Spi};
+  use super::SpiBus;
+
+  #[derive(PartialEq, Debug)]
+  enum Event {
+    Configure(u8),
+    Write(u8),
+    Flush,
+    CsLow(u8),
+    CsHigh(u8),
+  }
+
+  struct MockSpi<'a> {
+    log: &'a RefCell<Vec<Event>>,
+  }
+
+  impl<'a> Spi for MockSpi<'a> {
+    fn write(&self, value: u8) {
+      self.log.borrow_mut().push(Event::Write(value));
+    }
+
+    fn read(&self) -> u8 {
+      0
+    }
+
+    fn flush(&self) {
+      self.log.borrow_mut().push(Event::Flush);
+    }
+  }
+
+  impl<'a> Configure for MockSpi<'a> {
+    type Settings = u8;
+
+    fn configure(&self, settings: u8) {
+      self.log.borrow_mut().push(Event::Configure(settings));
+    }
+  }
+
+  struct MockPin<'a> {
+    id: u8,
+    log: &'a RefCell<Vec<Event>>,
+  }
+
+  impl<'a> Gpio for MockPin<'a> {
+    fn set_high(&self) {
+      self.log.borrow_mut().push(Event::CsHigh(self.id));
+    }
+
+    fn set_low(&self) {
+      self.log.borrow_mut().push(Event::CsLow(self.id));
+    }
+
+    fn level(&self) -> GpioLevel {
+      GpioLevel::High
+    }
+
+    fn set_direction(&self, _: GpioDirection) {}
+  }
+
+  #[test]
+  fn selects_device_and_releases_cs() {
+    let log = RefCell::new(Vec::new());
+    let bus = SpiBus::new(MockSpi { log: &log });
+    let dev = bus.device(MockPin { id: 1, log: &log }, 3);
+
+    dev.select().write(0x42);
+
+    assert_eq!(*log.borrow(), [Event::CsHigh(1), Event::Configure(3),
+        Event::CsLow(1), Event::Write(0x42), Event::Flush, Event::CsHigh(1)]);
+  }
+
+  #[test]
+  fn configures_only_when_settings_change() {
+    let log = RefCell::new(Vec::new());
+    let bus = SpiBus::new(MockSpi { log: &log });
+    let a = bus.device(MockPin { id: 1, log: &log }, 3);
+    let b = bus.device(MockPin { id: 2, log: &log }, 3);
+    let c = bus.device(MockPin { id: 3, log: &log }, 0);
+    log.borrow_mut().clear();
+
+    drop(a.select());
+    drop(b.select());
+    drop(a.select());
+    drop(c.select());
+    drop(a.select());
+
+    let log = log.borrow();
+    let configured: Vec<&Event> = log.iter()
+        .filter(|e| match **e { Event::Configure(_) => true, _ => false })
+        .collect();
+    assert_eq!(configured, [&Event::Configure(3), &Event::Configure(0),
+        &Event::Configure(3)]);
+  }
+
+  #[test]
+  fn applies_changed_device_settings() {
+    let log = RefCell::new(Vec::new());
+    let bus = SpiBus::new(MockSpi { log: &log });
+    let mut dev = bus.device(MockPin { id: 1, log: &log }, 3);
+
+    drop(dev.select());
+    dev.set_settings(1);
+    drop(dev.select());
+
+    assert_eq!(*log.borrow(), [Event::CsHigh(1),
+        Event::Configure(3), Event::CsLow(1), Event::Flush, Event::CsHigh(1),
+        Event::Configure(1), Event::CsLow(1), Event::Flush, Event::CsHigh(1)]);
+  }
+}
@@ -0,0 +1,139 @@
+// Zinc, the bare metal stack for rust.
+// Copyright 2016 Zinc Developers <zinc@github.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A statically sized byte FIFO, suitable for sharing with interrupt handlers.
+
+use core::option::Option::{self, Some, None};
+
+/// Number of slots in a `RingBuffer`.
+pub const RING_BUFFER_SIZE: usize = 64;
+
+/// Fixed-size ring buffer of bytes.
+///
+/// One slot is always kept free to tell a full buffer from an empty one, so
+/// the buffer holds at most `RING_BUFFER_SIZE - 1` bytes.
+pub struct RingBuffer {
+  buf: [u8; RING_BUFFER_SIZE],
+  /// Next slot to be written.
+  head: usize,
+  /// Next slot to be read.
+  tail: usize,
+}
+
+/// Static initializer
+pub const RING_BUFFER_INIT: RingBuffer = RingBuffer {
+  buf: [0; RING_BUFFER_SIZE],
+  head: 0,
+  tail: 0,
+};
+
+impl RingBuffer {
+  /// Create a new, empty ring buffer
+  pub fn new() -> RingBuffer {
+    RING_BUFFER_INIT
+  }
+
+  /// Append a byte, returning `false` if the buffer is full.
+  pub fn push(&mut self, value: u8) -> bool {
+    let next = (self.head + 1) % RING_BUFFER_SIZE;
+    if next == self.tail {
+      return false;
+    }
+    self.buf[self.head] = value;
+    self.head = next;
+    true
+  }
+
+  /// Remove and return the oldest byte.
+  pub fn pop(&mut self) -> Option<u8> {
+    if self.is_empty() {
+      return None;
+    }
+    let value = self.buf[self.tail];
+    self.tail = (self.tail + 1) % RING_BUFFER_SIZE;
+    Some(value)
+  }
+
+  /// Returns the number of bytes stored.
+  pub fn len(&self) -> usize {
+    (self.head + RING_BUFFER_SIZE - self.tail) % RING_BUFFER_SIZE
+  }
+
+  /// Returns true if there is nothing to read.
+  pub fn is_empty(&self) -> bool {
+    self.head == self.tail
+  }
+
+  /// Returns true if no more bytes can be pushed.
+  pub fn is_full(&self) -> bool {
+    (self.head + 1) % RING_BUFFER_SIZE == self.tail
+  }
+
+  /// Drop all stored bytes.
+  pub fn clear(&mut self) {
+    self.tail = self.head;
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use core::option::Option::{Some, None};
+
+  use super::{RingBuffer, RING_BUFFER_SIZE};
+
+  #[test]
+  fn pops_bytes_in_push_order() {
+    let mut rb = RingBuffer::new();
+    assert!(rb.is_empty());
+    assert!(rb.push(1));
+    assert!(rb.push(2));
+    assert!(rb.len() == 2);
+    assert!(rb.pop() == Some(1));
+    assert!(rb.pop() == Some(2));
+    assert!(rb.pop() == None);
+  }
+
+  #[test]
+  fn refuses_to_push_when_full() {
+    let mut rb = RingBuffer::new();
+    for i in 0..RING_BUFFER_SIZE - 1 {
+      assert!(rb.push(i as u8));
+    }
+    assert!(rb.is_full());
+    assert!(!rb.push(0xff));
+    assert!(rb.len() == RING_BUFFER_SIZE - 1);
+    assert!(rb.pop() == Some(0));
+  }
+
+  #[test]
+  fn wraps_around() {
+    let mut rb = RingBuffer::new();
+    for i in 0..RING_BUFFER_SIZE * 3 {
+      assert!(rb.push(i as u8));
+      assert!(rb.pop() == Some(i as u8));
+    }
+    assert!(rb.is_empty());
+  }
+
+  #[test]
+  fn clear_drops_everything() {
+    let mut rb = RingBuffer::new();
+    rb.push(1);
+    rb.push(2);
+    rb.clear();
+    assert!(rb.is_empty());
+    assert!(rb.pop() == None);
+  }
+}
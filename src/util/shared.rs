@@ -20,16 +20,21 @@ use core::ops::{Deref, DerefMut};
 use core::marker::{Sync, Send};
 
 #[cfg(feature = "cpu_cortex-m3")]
-use hal::cortex_m3::irq::NoInterrupts;
+use hal::cortex_m3::irq::CriticalSection;
 #[cfg(feature = "cpu_cortex-m4")]
-use hal::cortex_m4::irq::NoInterrupts;
+use hal::cortex_m4::irq::CriticalSection;
+#[cfg(feature = "cpu_cortex-m7")]
+use hal::cortex_m7::irq::CriticalSection;
 // If cpu doesn't have nointerrupts provide dummy implementation
 #[cfg(not(any(feature = "cpu_cortex-m3",
-              feature = "cpu_cortex-m4")))]
-use self::dummy_irq::NoInterrupts;
+              feature = "cpu_cortex-m4",
+              feature = "cpu_cortex-m7")))]
+use self::dummy_irq::CriticalSection;
 
 #[allow(missing_docs)]
 mod dummy_irq {
+  pub unsafe trait CriticalSection {}
+
   pub struct NoInterrupts;
 
   impl NoInterrupts {
@@ -38,10 +43,15 @@ mod dummy_irq {
       NoInterrupts
     }
   }
+
+  unsafe impl CriticalSection for NoInterrupts {}
 }
 
 /// This allows safe sharing of state, ensuring access occurs only
 /// when in a critical section.
+///
+/// The critical section is either `NoInterrupts` or, on cores with BASEPRI,
+/// a `PriorityCeiling` that masks every interrupt handler using the value.
 #[allow(missing_docs)]
 pub struct Shared<T> {
   pub value: UnsafeCell<T>,
@@ -51,7 +61,7 @@ pub struct Shared<T> {
 pub struct SharedRef<'a, T: 'a> {
   ptr: &'a Shared<T>,
   #[allow(dead_code)]
-  crit: &'a NoInterrupts
+  crit: &'a CriticalSection
 }
 
 impl<T> Shared<T> {
@@ -63,7 +73,8 @@ impl<T> Shared<T> {
   }
 
   /// Borrow a reference to the value
-  pub fn borrow<'a>(&'a self, crit: &'a NoInterrupts) -> SharedRef<'a, T> {
+  pub fn borrow<'a, C: CriticalSection + 'a>(&'a self, crit: &'a C)
+      -> SharedRef<'a, T> {
     SharedRef {ptr: self, crit: crit}
   }
 }
@@ -45,7 +45,7 @@ else
   case "$PLATFORM" in
     lpc11xx )
       TARGET=thumbv6m-none-eabi
-      EXAMPLES="empty"
+      EXAMPLES="empty blink_lpc11xx"
       ;;
     lpc17xx )
       TARGET=thumbv7m-none-eabi
@@ -61,7 +61,7 @@ else
       ;;
     stm32f4 )
       TARGET=thumbv7em-none-eabi
-      EXAMPLES="empty blink_stm32f4"
+      EXAMPLES="empty blink_stm32f4 usart_stm32f4"
       ;;
     stm32l1 )
       TARGET=thumbv7m-none-eabi